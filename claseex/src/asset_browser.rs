@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::prefab::Prefab;
+use crate::scene::{PrefabInstance, Scene, SceneObject, Shape};
+use crate::thumbnails::{self, TURNTABLE_FRAMES};
+
+pub const PANEL_WIDTH: f32 = 180.0;
+const CELL_SIZE: f32 = 80.0;
+const COLUMNS: usize = 2;
+const SWATCH_SIZE: f32 = 24.0;
+// Cuadros por segundo de la vuelta que muestra un prefab al pasar el mouse
+const TURNTABLE_FPS: f32 = 8.0;
+
+// Lo que se puede colocar desde el panel de recursos
+#[derive(Clone, Debug, PartialEq)]
+pub enum Asset {
+    Mesh(Shape),
+    Prefab(String),
+    Color(usize), // Slot de la paleta de la escena; se suelta sobre un objeto para pintarlo
+}
+
+impl Asset {
+    pub fn label(&self) -> String {
+        match self {
+            Asset::Mesh(shape) => shape.name().to_string(),
+            Asset::Prefab(name) => name.clone(),
+            Asset::Color(slot) => format!("color {}", slot),
+        }
+    }
+}
+
+// Panel con las formas básicas, los prefabs guardados (con su miniatura) y los colores de la
+// paleta. Un clic deja elegido el recurso hasta el próximo clic en la vista; arrastrarlo lo suelta
+// donde se libera el botón
+pub struct AssetBrowser {
+    prefabs: Vec<String>,
+    thumbnails: HashMap<String, Texture2D>, // Tiras de la vuelta completa que genera --render-thumbnails
+    pub held: Option<Asset>,
+    dragging: bool,
+}
+
+impl AssetBrowser {
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+        let mut browser = AssetBrowser {
+            prefabs: Vec::new(),
+            thumbnails: HashMap::new(),
+            held: None,
+            dragging: false,
+        };
+        browser.refresh(rl, thread);
+        browser
+    }
+
+    // Vuelve a leer la carpeta de prefabs y las miniaturas, por ejemplo después de guardar uno nuevo
+    pub fn refresh(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        self.prefabs = Prefab::available().unwrap_or_default();
+        self.thumbnails.clear();
+        for name in &self.prefabs {
+            let path = thumbnails::thumbnail_path(name);
+            if !std::path::Path::new(&path).exists() {
+                continue;
+            }
+            match rl.load_texture(thread, &path) {
+                Ok(texture) => {
+                    self.thumbnails.insert(name.clone(), texture);
+                }
+                Err(error) => eprintln!("No se pudo cargar la miniatura {}: {}", path, error),
+            }
+        }
+    }
+
+    pub fn thumbnail(&self, name: &str) -> Option<&Texture2D> {
+        self.thumbnails.get(name)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    // Ubicación de cada entrada: formas y prefabs en una grilla, los colores en una fila de muestras
+    pub fn layout(&self, palette_len: usize, x: f32, y: f32) -> Vec<(Asset, Rectangle)> {
+        let mut entries: Vec<Asset> = [Shape::Cube, Shape::Sphere, Shape::Plane].into_iter().map(Asset::Mesh).collect();
+        entries.extend(self.prefabs.iter().cloned().map(Asset::Prefab));
+        let mut layout: Vec<(Asset, Rectangle)> = entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, asset)| {
+                let column = (index % COLUMNS) as f32;
+                let row = (index / COLUMNS) as f32;
+                let cell_x = x + 10.0 + column * CELL_SIZE;
+                let cell_y = y + 30.0 + row * CELL_SIZE;
+                (asset, Rectangle::new(cell_x, cell_y, CELL_SIZE - 4.0, CELL_SIZE - 4.0))
+            })
+            .collect();
+        let rows = layout.len().div_ceil(COLUMNS) as f32;
+        let swatch_y = y + 34.0 + rows * CELL_SIZE;
+        let per_row = ((PANEL_WIDTH - 20.0) / (SWATCH_SIZE + 4.0)) as usize;
+        for slot in 0..palette_len {
+            let column = (slot % per_row) as f32;
+            let row = (slot / per_row) as f32;
+            let bounds = Rectangle::new(
+                x + 10.0 + column * (SWATCH_SIZE + 4.0),
+                swatch_y + row * (SWATCH_SIZE + 4.0),
+                SWATCH_SIZE,
+                SWATCH_SIZE,
+            );
+            layout.push((Asset::Color(slot), bounds));
+        }
+        layout
+    }
+
+    // Área del panel, para no elegir objetos a través de él
+    pub fn bounds(&self, palette_len: usize, x: f32, y: f32) -> Rectangle {
+        let bottom = self
+            .layout(palette_len, x, y)
+            .iter()
+            .map(|(_, bounds)| bounds.y + bounds.height)
+            .fold(y + 30.0, f32::max);
+        Rectangle::new(x, y, PANEL_WIDTH, bottom - y + 10.0)
+    }
+
+    // Procesa el mouse del frame y devuelve el recurso que hay que colocar, si se soltó o se hizo
+    // clic en la vista con uno elegido
+    pub fn handle_mouse(
+        &mut self,
+        mouse: Vector2,
+        pressed: bool,
+        released: bool,
+        over_ui: bool,
+        palette_len: usize,
+        position: Vector2,
+    ) -> Option<Asset> {
+        if pressed {
+            let entry = self
+                .layout(palette_len, position.x, position.y)
+                .into_iter()
+                .find(|(_, bounds)| bounds.check_collision_point_rec(mouse));
+            if let Some((asset, _)) = entry {
+                self.held = Some(asset);
+                self.dragging = true;
+                return None;
+            }
+            if !over_ui {
+                return self.held.take();
+            }
+        }
+        if released && self.dragging {
+            self.dragging = false;
+            if !over_ui {
+                return self.held.take();
+            }
+        }
+        None
+    }
+
+    pub fn cancel(&mut self) {
+        self.held = None;
+        self.dragging = false;
+    }
+
+    // Cuadro de la vuelta de un prefab a mostrar: quieto salvo con el mouse encima
+    pub fn turntable_frame(animated: bool, time: f64) -> u32 {
+        if animated { (time * TURNTABLE_FPS as f64) as u32 % TURNTABLE_FRAMES } else { 0 }
+    }
+}
+
+impl Scene {
+    // Coloca el recurso en `position` (el punto del suelo u objeto bajo el mouse) y devuelve el id del
+    // objeto creado, o del pintado si es un color. `palette_slot` es el color para las instancias nuevas
+    pub fn place_asset(
+        &mut self,
+        asset: &Asset,
+        position: Vector3,
+        target: Option<u32>,
+        palette_slot: Option<usize>,
+    ) -> Result<u32, String> {
+        match asset {
+            Asset::Mesh(shape) => {
+                // Se apoya sobre el punto en lugar de quedar enterrado a la mitad
+                let (size, lift) = match shape {
+                    Shape::Plane => (Vector3::new(4.0, 0.0, 4.0), 0.0),
+                    _ => (Vector3::one(), 0.5),
+                };
+                let color = Color::new(200, 200, 200, 255);
+                let position = position + Vector3::new(0.0, lift, 0.0);
+                Ok(self.add_object(shape.name(), *shape, position, size, color, "props"))
+            }
+            Asset::Prefab(name) => {
+                let prefab = Prefab::load(name)?;
+                let instance = PrefabInstance {
+                    id: self.allocate_id(),
+                    prefab: prefab.name.clone(),
+                    position,
+                    rotation: Vector3::zero(),
+                    scale: 1.0,
+                    palette_slot,
+                    scatter: None,
+                    parts: Vec::new(),
+                };
+                let instance_id = instance.id;
+                self.instantiate(&prefab, instance);
+                self.objects
+                    .iter()
+                    .find(|object| object.instance == Some(instance_id) && object.parent.is_none())
+                    .map(|object| object.id)
+                    .ok_or(format!("El prefab '{}' no tiene objetos", name))
+            }
+            Asset::Color(slot) => {
+                if *slot >= self.palette.len() {
+                    return Err(format!("La paleta no tiene el color {}", slot));
+                }
+                let object = target
+                    .and_then(|id| self.object(id))
+                    .ok_or("El color se suelta sobre un objeto".to_string())?;
+                let (id, instance_id) = (object.id, object.instance);
+                let Some(instance_id) = instance_id else {
+                    if let Some(object) = self.object_mut(id) {
+                        object.palette_slot = Some(*slot);
+                    }
+                    return Ok(id);
+                };
+                // Las partes de un prefab no se guardan sueltas: el color va a la instancia, que las
+                // vuelve a crear al cargar. Igual que al instanciar, solo cambian las que usan la paleta
+                let instance = self
+                    .instances
+                    .iter_mut()
+                    .find(|instance| instance.id == instance_id)
+                    .ok_or("El objeto pertenece a una instancia que ya no existe".to_string())?;
+                let prefab = instance.prefab.clone();
+                let parts: Vec<&mut SceneObject> = self
+                    .objects
+                    .iter_mut()
+                    .filter(|object| object.instance == Some(instance_id) && object.palette_slot.is_some())
+                    .collect();
+                if parts.is_empty() {
+                    return Err(format!("Las partes del prefab '{}' no usan la paleta", prefab));
+                }
+                instance.palette_slot = Some(*slot);
+                for object in parts {
+                    object.palette_slot = Some(*slot);
+                }
+                Ok(id)
+            }
+        }
+    }
+}
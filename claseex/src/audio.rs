@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use raylib::prelude::*;
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use serde_json::{json, Value};
+
+use crate::rng::Rng;
+use crate::scene::{f32_to_json, vec3_from_json, vec3_to_json, Scene};
+
+const SAMPLE_RATE: u32 = 44100;
+// Cuánto avanza la ganancia por muestra hacia el valor pedido, para evitar chasquidos
+const GAIN_SMOOTHING: f32 = 0.002;
+
+// Sonidos procedurales disponibles; no hace falta ningún archivo de audio
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoundKind {
+    Crackle, // Fuego: ruido grave con chasquidos al azar
+    Wind,    // Ruido filtrado que sube y baja lentamente
+    Hum,     // Zumbido grave de motor
+}
+
+impl SoundKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoundKind::Crackle => "crackle",
+            SoundKind::Wind => "wind",
+            SoundKind::Hum => "hum",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SoundKind> {
+        match name {
+            "crackle" => Some(SoundKind::Crackle),
+            "wind" => Some(SoundKind::Wind),
+            "hum" => Some(SoundKind::Hum),
+            _ => None,
+        }
+    }
+}
+
+// Fuente de sonido ubicada en la escena; si tiene objeto, lo sigue
+#[derive(Clone, Debug)]
+pub struct AudioEmitter {
+    pub name: String,
+    pub sound: SoundKind,
+    pub position: Vector3,
+    pub object: Option<String>,
+    pub radius: f32, // Distancia a la que deja de oírse
+    pub volume: f32,
+}
+
+impl Scene {
+    // Posición actual del emisor, tomando la del objeto al que está enganchado
+    pub fn emitter_position(&self, emitter: &AudioEmitter, transforms: &HashMap<u32, Matrix>) -> Vector3 {
+        emitter
+            .object
+            .as_ref()
+            .and_then(|name| self.objects.iter().find(|object| object.name == *name))
+            .and_then(|object| transforms.get(&object.id))
+            .map(|model| Vector3::zero().transform_with(*model))
+            .unwrap_or(emitter.position)
+    }
+}
+
+// Ganancias izquierda y derecha compartidas entre el hilo principal y el de audio
+type SharedGains = Arc<[AtomicU32; 2]>;
+
+// Genera muestras estéreo intercaladas y les aplica la ganancia de cada oído
+struct ProceduralSound {
+    kind: SoundKind,
+    gains: SharedGains,
+    current: [f32; 2],
+    channel: usize,
+    sample: f32,
+    rng: Rng,
+    filtered: f32,
+    burst: f32,
+    time: f32,
+}
+
+impl ProceduralSound {
+    fn new(kind: SoundKind, gains: SharedGains, seed: u32) -> Self {
+        ProceduralSound {
+            kind,
+            gains,
+            current: [0.0; 2],
+            channel: 0,
+            sample: 0.0,
+            rng: Rng::new(seed),
+            filtered: 0.0,
+            burst: 0.0,
+            time: 0.0,
+        }
+    }
+
+    fn noise(&mut self) -> f32 {
+        self.rng.range(-1.0, 1.0)
+    }
+
+    fn next_mono(&mut self) -> f32 {
+        self.time += 1.0 / SAMPLE_RATE as f32;
+        let white = self.noise();
+        match self.kind {
+            SoundKind::Crackle => {
+                self.filtered += (white - self.filtered) * 0.02;
+                if self.noise() > 0.9996 {
+                    self.burst = 0.8 + self.noise().abs() * 0.4;
+                }
+                self.burst *= 0.993;
+                self.filtered * 0.5 + self.noise() * self.burst * 0.6
+            }
+            SoundKind::Wind => {
+                self.filtered += (white - self.filtered) * 0.01;
+                let swell = 0.6 + 0.4 * (self.time * 0.3).sin() * (self.time * 0.17).cos();
+                self.filtered * 3.0 * swell
+            }
+            SoundKind::Hum => {
+                let phase = self.time * std::f32::consts::TAU;
+                ((phase * 55.0).sin() * 0.6 + (phase * 110.0).sin() * 0.25) * 0.5 + white * 0.02
+            }
+        }
+    }
+}
+
+impl Iterator for ProceduralSound {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            self.sample = self.next_mono();
+            for ear in 0..2 {
+                let target = f32::from_bits(self.gains[ear].load(Ordering::Relaxed));
+                self.current[ear] += (target - self.current[ear]).clamp(-GAIN_SMOOTHING, GAIN_SMOOTHING);
+            }
+        }
+        let value = self.sample * self.current[self.channel];
+        self.channel = 1 - self.channel;
+        Some(value)
+    }
+}
+
+impl Source for ProceduralSound {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+struct Voice {
+    _sink: Sink,
+    gains: SharedGains,
+}
+
+// Reproduce un sonido por emisor y ajusta volumen y paneo según la cámara
+pub struct AudioEngine {
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    voices: Vec<Voice>,
+}
+
+impl AudioEngine {
+    // Devuelve None si no hay dispositivo de audio; el diorama sigue funcionando sin sonido
+    pub fn new() -> Option<Self> {
+        match OutputStream::try_default() {
+            Ok((stream, handle)) => Some(AudioEngine {
+                _stream: stream,
+                handle,
+                voices: Vec::new(),
+            }),
+            Err(error) => {
+                eprintln!("Audio desactivado: {}", error);
+                None
+            }
+        }
+    }
+
+    pub fn update(&mut self, scene: &Scene, transforms: &HashMap<u32, Matrix>, listener: &Camera3D) {
+        // Los emisores nuevos reciben su propia voz; los que sobran quedan en silencio
+        while self.voices.len() < scene.emitters.len() {
+            let Ok(sink) = Sink::try_new(&self.handle) else {
+                return;
+            };
+            let gains: SharedGains = Arc::new([AtomicU32::new(0), AtomicU32::new(0)]);
+            let kind = scene.emitters[self.voices.len()].sound;
+            sink.append(ProceduralSound::new(kind, gains.clone(), 0x9E37_79B9 ^ self.voices.len() as u32));
+            self.voices.push(Voice { _sink: sink, gains });
+        }
+
+        let forward = (listener.target - listener.position).normalized();
+        let right = forward.cross(listener.up).normalized();
+        for (index, voice) in self.voices.iter().enumerate() {
+            let [left, right_gain] = match scene.emitters.get(index) {
+                Some(emitter) => {
+                    let position = scene.emitter_position(emitter, transforms);
+                    stereo_gains(emitter, position, listener.position, right)
+                }
+                None => [0.0, 0.0],
+            };
+            voice.gains[0].store(left.to_bits(), Ordering::Relaxed);
+            voice.gains[1].store(right_gain.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+// Atenuación con la misma caída suave que las luces y paneo de potencia constante
+fn stereo_gains(emitter: &AudioEmitter, position: Vector3, listener: Vector3, right: Vector3) -> [f32; 2] {
+    let offset = position - listener;
+    let distance = offset.length();
+    if distance >= emitter.radius {
+        return [0.0, 0.0];
+    }
+    let falloff = (1.0 - (distance / emitter.radius).powi(2)).powi(2);
+    let pan = if distance > 1e-3 { offset.dot(right) / distance } else { 0.0 };
+    let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+    let gain = emitter.volume * falloff;
+    [gain * angle.cos(), gain * angle.sin()]
+}
+
+pub fn emitter_from_json(value: &Value) -> Result<AudioEmitter, String> {
+    let sound_name = value["sound"].as_str().unwrap_or("crackle");
+    Ok(AudioEmitter {
+        name: value["name"].as_str().unwrap_or("sonido").to_string(),
+        sound: SoundKind::from_name(sound_name).ok_or(format!("sonido desconocido: {}", sound_name))?,
+        position: vec3_from_json(&value["position"]).unwrap_or(Vector3::zero()),
+        object: value["object"].as_str().map(str::to_string),
+        radius: value["radius"].as_f64().unwrap_or(8.0) as f32,
+        volume: value["volume"].as_f64().unwrap_or(1.0) as f32,
+    })
+}
+
+pub fn emitter_to_json(emitter: &AudioEmitter) -> Value {
+    let mut value = json!({
+        "name": emitter.name,
+        "sound": emitter.sound.name(),
+        "position": vec3_to_json(emitter.position),
+        "radius": f32_to_json(emitter.radius),
+        "volume": f32_to_json(emitter.volume),
+    });
+    if let Some(object) = &emitter.object {
+        value["object"] = json!(object);
+    }
+    value
+}
@@ -0,0 +1,367 @@
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::rng::noise3;
+use crate::scene::{f32_to_json, vec3_from_json, vec3_to_json, Scene};
+use crate::spatial::SpatialIndex;
+
+// Distancia al destino desde la que un agente empieza a frenar
+const ARRIVE_RADIUS: f32 = 1.5;
+// Qué tan rápido cambia el rumbo al deambular y cuánto puede torcerse respecto del actual (radianes)
+const WANDER_RATE: f32 = 0.35;
+const WANDER_TURN: f32 = 1.2;
+// Peso del desvío para esquivar frente al rumbo deseado
+const AVOID_WEIGHT: f32 = 2.0;
+// Distancia a la que se da por alcanzado un punto de paso del camino
+const WAYPOINT_RADIUS: f32 = 0.3;
+
+// Movimientos reutilizables que se le pueden agregar a cualquier objeto
+#[derive(Clone, Debug, PartialEq)]
+pub enum Behavior {
+    // Gira sin parar; velocidad en grados por segundo en cada eje
+    Spinner { speed: Vector3 },
+    // Sube y baja respecto de su posición; `time` es el avance del vaivén
+    Bobber { amplitude: f32, frequency: f32, time: f32 },
+    // Da vueltas en el plano horizontal alrededor de un centro, sin tocar la altura
+    Orbiter { center: Vector3, radius: f32, speed: f32, angle: f32 },
+    // Camina por el plano horizontal: deambula sin alejarse más de `range` de `home`, o va hacia
+    // `target` si `seek` está activo, esquivando los objetos que tiene adelante. `steering` es cuánto
+    // puede cambiar la velocidad por segundo y `sight` hasta dónde mira para esquivar. Para ir al
+    // destino sigue los puntos de `route`, calculados con A* para `planned`
+    Agent {
+        seek: bool,
+        target: Vector3,
+        home: Vector3,
+        range: f32,
+        speed: f32,
+        steering: f32,
+        sight: f32,
+        velocity: Vector3,
+        time: f32,
+        route: Vec<Vector3>,
+        planned: Option<Vector3>,
+    },
+}
+
+impl Behavior {
+    pub const KINDS: [&'static str; 4] = ["spinner", "bobber", "orbiter", "agent"];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Behavior::Spinner { .. } => "spinner",
+            Behavior::Bobber { .. } => "bobber",
+            Behavior::Orbiter { .. } => "orbiter",
+            Behavior::Agent { .. } => "agent",
+        }
+    }
+
+    // Comportamiento con valores por defecto, ubicado respecto de la posición actual del objeto
+    pub fn with_defaults(kind: &str, position: Vector3) -> Option<Behavior> {
+        match kind {
+            "spinner" => Some(Behavior::Spinner {
+                speed: Vector3::new(0.0, 45.0, 0.0),
+            }),
+            "bobber" => Some(Behavior::Bobber {
+                amplitude: 0.25,
+                frequency: 0.5,
+                time: 0.0,
+            }),
+            "orbiter" => Some(Behavior::Orbiter {
+                center: position - Vector3::new(2.0, 0.0, 0.0),
+                radius: 2.0,
+                speed: 30.0,
+                angle: 0.0,
+            }),
+            "agent" => Some(Behavior::Agent {
+                seek: false,
+                target: position,
+                home: position,
+                range: 4.0,
+                speed: 1.2,
+                steering: 3.0,
+                sight: 2.0,
+                velocity: Vector3::zero(),
+                time: 0.0,
+                route: Vec::new(),
+                planned: None,
+            }),
+            _ => None,
+        }
+    }
+
+    // Desplazamiento vertical del vaivén en un instante dado
+    fn bob_offset(amplitude: f32, frequency: f32, time: f32) -> f32 {
+        amplitude * (time * frequency * std::f32::consts::TAU).sin()
+    }
+
+    // Desplazamiento que suma el comportamiento a un objeto que sigue una curva: el vaivén sube y
+    // baja y la órbita gira alrededor del punto de la curva en lugar de su centro
+    pub fn path_offset(&self) -> Vector3 {
+        match self {
+            Behavior::Bobber {
+                amplitude,
+                frequency,
+                time,
+            } => Vector3::new(0.0, Behavior::bob_offset(*amplitude, *frequency, *time), 0.0),
+            Behavior::Orbiter { radius, angle, .. } => {
+                Vector3::new(radius * angle.to_radians().cos(), 0.0, radius * angle.to_radians().sin())
+            }
+            _ => Vector3::zero(),
+        }
+    }
+}
+
+impl Scene {
+    // Avanza los comportamientos de todos los objetos
+    pub fn update_behaviors(&mut self, dt: f32) {
+        // Los agentes esquivan usando la grilla; solo se arma si hay alguno
+        let has_agents = self
+            .objects
+            .iter()
+            .any(|object| object.behaviors.iter().any(|behavior| matches!(behavior, Behavior::Agent { .. })));
+        let index = has_agents.then(|| SpatialIndex::build(self, &self.world_transforms()));
+        // Los que tienen un destino nuevo calculan primero su camino
+        let pending: Vec<(u32, Vector3)> = self
+            .objects
+            .iter()
+            .flat_map(|object| {
+                object.behaviors.iter().filter_map(move |behavior| match behavior {
+                    Behavior::Agent {
+                        seek: true,
+                        target,
+                        planned,
+                        ..
+                    } if *planned != Some(*target) => Some((object.id, *target)),
+                    _ => None,
+                })
+            })
+            .collect();
+        let plans: Vec<(u32, Vector3, Vec<Vector3>)> = pending
+            .into_iter()
+            // Sin camino se va en línea recta al destino
+            .map(|(id, target)| (id, target, self.plan_route(id, target).unwrap_or_else(|| vec![target])))
+            .collect();
+
+        for object in &mut self.objects {
+            let (id, radius) = (object.id, object.bounding_radius());
+            for behavior in &mut object.behaviors {
+                match behavior {
+                    Behavior::Spinner { speed } => {
+                        let rotation = object.rotation + *speed * dt;
+                        object.rotation = Vector3::new(
+                            rotation.x.rem_euclid(360.0),
+                            rotation.y.rem_euclid(360.0),
+                            rotation.z.rem_euclid(360.0),
+                        );
+                    }
+                    Behavior::Bobber {
+                        amplitude,
+                        frequency,
+                        time,
+                    } => {
+                        // Se aplica solo la diferencia, así el objeto se puede seguir moviendo a mano
+                        let before = Behavior::bob_offset(*amplitude, *frequency, *time);
+                        *time += dt;
+                        object.position.y += Behavior::bob_offset(*amplitude, *frequency, *time) - before;
+                    }
+                    Behavior::Orbiter {
+                        center,
+                        radius,
+                        speed,
+                        angle,
+                    } => {
+                        *angle = (*angle + *speed * dt).rem_euclid(360.0);
+                        object.position.x = center.x + *radius * angle.to_radians().cos();
+                        object.position.z = center.z + *radius * angle.to_radians().sin();
+                    }
+                    Behavior::Agent {
+                        seek,
+                        home,
+                        range,
+                        speed,
+                        steering,
+                        sight,
+                        velocity,
+                        time,
+                        route,
+                        planned,
+                        ..
+                    } => {
+                        let Some(index) = &index else {
+                            continue;
+                        };
+                        if let Some((_, goal, points)) = plans.iter().find(|(agent, ..)| *agent == id) {
+                            *route = points.clone();
+                            *planned = Some(*goal);
+                        }
+                        *time += dt;
+                        let flat = |v: Vector3| Vector3::new(v.x, 0.0, v.z);
+                        let position = object.position;
+                        let heading = if velocity.length() > 1e-3 {
+                            velocity.normalized()
+                        } else {
+                            let yaw = object.rotation.y.to_radians();
+                            Vector3::new(yaw.sin(), 0.0, yaw.cos())
+                        };
+
+                        let from_home = flat(position - *home);
+                        if !*seek {
+                            route.clear();
+                            *planned = None;
+                        }
+                        // Punto de paso alcanzado: se pasa al siguiente. Al alcanzar el último el camino
+                        // queda vacío y el agente se detiene; `planned` sigue igual para no buscarlo de nuevo
+                        if route.first().is_some_and(|point| flat(*point - position).length() < WAYPOINT_RADIUS) {
+                            route.remove(0);
+                        }
+                        let mut desired = if *seek && route.is_empty() {
+                            Vector3::zero()
+                        } else if *seek {
+                            let to_target = flat(route[0] - position);
+                            let distance = to_target.length();
+                            // Solo frena al acercarse al último punto
+                            let arrival = if route.len() > 1 { 1.0 } else { (distance / ARRIVE_RADIUS).min(1.0) };
+                            if distance < 0.05 {
+                                Vector3::zero()
+                            } else {
+                                to_target / distance * *speed * arrival
+                            }
+                        } else if from_home.length() > *range {
+                            from_home.normalized() * -*speed
+                        } else {
+                            // Rumbo que se tuerce de a poco; cada agente usa su propia zona del ruido
+                            let turn = noise3(*time * WANDER_RATE, id as f32 * 17.3, 0.0) * WANDER_TURN;
+                            let (sin, cos) = turn.sin_cos();
+                            Vector3::new(heading.x * cos + heading.z * sin, 0.0, heading.z * cos - heading.x * sin) * *speed
+                        };
+
+                        // Esquivar: empuja hacia el costado a los obstáculos que quedan en el camino,
+                        // más fuerte cuanto más cerca
+                        let mut avoidance = Vector3::zero();
+                        for entry in index.query(position + heading * *sight * 0.5, *sight * 0.5 + radius) {
+                            let vertical_gap = (entry.center.y - position.y).abs();
+                            if entry.root == id || vertical_gap > entry.radius + radius {
+                                continue;
+                            }
+                            let offset = flat(entry.center - position);
+                            let clearance = entry.radius + radius;
+                            let distance = offset.length();
+                            if distance < clearance && distance > 1e-3 {
+                                // Ya lo está tocando: se aleja en línea recta
+                                avoidance += offset / distance * -*speed * (2.0 - distance / clearance);
+                                continue;
+                            }
+                            let ahead = offset.dot(heading);
+                            let lateral = offset - heading * ahead;
+                            if ahead < 0.0 || ahead > *sight || lateral.length() >= clearance {
+                                continue;
+                            }
+                            let side = if lateral.length() > 1e-3 {
+                                lateral.normalized() * -1.0
+                            } else {
+                                heading.cross(Vector3::new(0.0, 1.0, 0.0))
+                            };
+                            avoidance += side * *speed * (1.0 - ahead / *sight);
+                        }
+                        desired += avoidance * AVOID_WEIGHT;
+
+                        let limit = |v: Vector3, max: f32| if v.length() > max { v.normalized() * max } else { v };
+                        let steer = limit(desired - *velocity, *steering);
+                        *velocity = limit(flat(*velocity + steer * dt), *speed);
+                        object.position += *velocity * dt;
+                        // Mira hacia donde camina
+                        if velocity.length() > 0.05 {
+                            object.rotation.y = velocity.x.atan2(velocity.z).to_degrees();
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn behavior_from_json(value: &Value) -> Result<Behavior, String> {
+    match value["type"].as_str() {
+        Some("spinner") => Ok(Behavior::Spinner {
+            speed: vec3_from_json(&value["speed"]).unwrap_or(Vector3::new(0.0, 45.0, 0.0)),
+        }),
+        Some("bobber") => Ok(Behavior::Bobber {
+            amplitude: value["amplitude"].as_f64().unwrap_or(0.25) as f32,
+            frequency: value["frequency"].as_f64().unwrap_or(0.5) as f32,
+            time: value["time"].as_f64().unwrap_or(0.0) as f32,
+        }),
+        Some("orbiter") => Ok(Behavior::Orbiter {
+            center: vec3_from_json(&value["center"]).unwrap_or(Vector3::zero()),
+            radius: value["radius"].as_f64().unwrap_or(2.0) as f32,
+            speed: value["speed"].as_f64().unwrap_or(30.0) as f32,
+            angle: value["angle"].as_f64().unwrap_or(0.0) as f32,
+        }),
+        Some("agent") => {
+            let home = vec3_from_json(&value["home"]).unwrap_or(Vector3::zero());
+            Ok(Behavior::Agent {
+                seek: value["mode"].as_str() == Some("seek"),
+                target: vec3_from_json(&value["target"]).unwrap_or(home),
+                home,
+                range: value["range"].as_f64().unwrap_or(4.0) as f32,
+                speed: value["speed"].as_f64().unwrap_or(1.2) as f32,
+                steering: value["steering"].as_f64().unwrap_or(3.0) as f32,
+                sight: value["sight"].as_f64().unwrap_or(2.0) as f32,
+                velocity: Vector3::zero(),
+                time: 0.0,
+                route: Vec::new(),
+                planned: None,
+            })
+        }
+        other => Err(format!("comportamiento desconocido: {:?}", other)),
+    }
+}
+
+pub fn behavior_to_json(behavior: &Behavior) -> Value {
+    match behavior {
+        Behavior::Spinner { speed } => json!({
+            "type": "spinner",
+            "speed": vec3_to_json(*speed),
+        }),
+        Behavior::Bobber {
+            amplitude,
+            frequency,
+            time,
+        } => json!({
+            "type": "bobber",
+            "amplitude": f32_to_json(*amplitude),
+            "frequency": f32_to_json(*frequency),
+            "time": f32_to_json(*time),
+        }),
+        Behavior::Orbiter {
+            center,
+            radius,
+            speed,
+            angle,
+        } => json!({
+            "type": "orbiter",
+            "center": vec3_to_json(*center),
+            "radius": f32_to_json(*radius),
+            "speed": f32_to_json(*speed),
+            "angle": f32_to_json(*angle),
+        }),
+        Behavior::Agent {
+            seek,
+            target,
+            home,
+            range,
+            speed,
+            steering,
+            sight,
+            ..
+        } => json!({
+            "type": "agent",
+            "mode": if *seek { "seek" } else { "wander" },
+            "target": vec3_to_json(*target),
+            "home": vec3_to_json(*home),
+            "range": f32_to_json(*range),
+            "speed": f32_to_json(*speed),
+            "steering": f32_to_json(*steering),
+            "sight": f32_to_json(*sight),
+        }),
+    }
+}
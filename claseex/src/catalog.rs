@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use raylib::prelude::*;
+use serde_json::Value;
+
+use crate::frustum::Frustum;
+use crate::lighting::LightingParams;
+use crate::photo::CAPTURE_DIR;
+use crate::renderer::{self, ShapeLibrary};
+use crate::scene::{vec3_from_json, Scene};
+use crate::shadow;
+
+pub const CATALOG_DIR: &str = "scenes/tests";
+const CELL_WIDTH: u32 = 400;
+const CELL_HEIGHT: u32 = 300;
+const COLUMNS: u32 = 2;
+const TITLE_HEIGHT: u32 = 28;
+
+// Escenas del catálogo ordenadas por nombre de archivo
+fn catalog_files() -> Result<Vec<String>, String> {
+    let entries = std::fs::read_dir(CATALOG_DIR).map_err(|e| format!("{}: {}", CATALOG_DIR, e))?;
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Cámara de la escena de prueba: la del campo opcional "camera" o una vista en diagonal
+fn catalog_camera(path: &str) -> Camera3D {
+    let value: Value = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or(Value::Null);
+    let camera = &value["camera"];
+    Camera3D::perspective(
+        vec3_from_json(&camera["position"]).unwrap_or(Vector3::new(5.0, 4.0, 5.0)),
+        vec3_from_json(&camera["target"]).unwrap_or(Vector3::zero()),
+        Vector3::new(0.0, 1.0, 0.0),
+        camera["fovy"].as_f64().unwrap_or(45.0) as f32,
+    )
+}
+
+// Dibuja cada escena de `scenes/tests` en una celda de una grilla y la guarda como PNG
+pub fn render_catalog(rl: &mut RaylibHandle, thread: &RaylibThread, library: &ShapeLibrary) -> Result<String, String> {
+    let files = catalog_files()?;
+    if files.is_empty() {
+        return Err(format!("{}: no hay escenas", CATALOG_DIR));
+    }
+    let rows = (files.len() as u32).div_ceil(COLUMNS);
+    let cell_height = CELL_HEIGHT + TITLE_HEIGHT;
+    let mut cell = rl.load_render_texture(thread, CELL_WIDTH, CELL_HEIGHT).map_err(|e| e.to_string())?;
+    let mut grid = rl
+        .load_render_texture(thread, CELL_WIDTH * COLUMNS, cell_height * rows)
+        .map_err(|e| e.to_string())?;
+    {
+        let mut target = rl.begin_texture_mode(thread, &mut grid);
+        target.clear_background(Color::new(30, 30, 30, 255));
+    }
+
+    let aspect = CELL_WIDTH as f32 / CELL_HEIGHT as f32;
+    for (index, path) in files.iter().enumerate() {
+        let scene = Scene::load(path)?;
+        let camera = catalog_camera(path);
+        let transforms = scene.world_transforms();
+        let frustum = Frustum::from_camera(&camera, aspect, 0.1, 100.0);
+        let shadowed = shadow::shadowed_lights(&scene.lights, camera.position, shadow::DEFAULT_SHADOW_BUDGET);
+        let shadows = shadow::build_point_shadows(&scene.lights, &shadowed, &scene, &transforms);
+        let lighting = LightingParams {
+            lights: &scene.lights,
+            ambient_intensity: scene.ambient_intensity,
+            diffuse_intensity: scene.diffuse_intensity,
+            fog: None,
+            shadows: &shadows,
+            shadowed: &shadowed,
+            displacement_shader: None,
+        };
+        {
+            let mut target = rl.begin_texture_mode(thread, &mut cell);
+            target.clear_background(Color::new(50, 50, 50, 255));
+            let mut d3d = target.begin_mode3D(camera);
+            renderer::draw_scene(&mut d3d, library, &scene, &transforms, &frustum, &lighting, &HashSet::new());
+        }
+
+        // La celda queda invertida en Y; se copia con alto negativo y se titula con el archivo
+        let x = (index as u32 % COLUMNS * CELL_WIDTH) as f32;
+        let y = (index as u32 / COLUMNS * cell_height) as f32;
+        let source = Rectangle::new(0.0, 0.0, CELL_WIDTH as f32, -(CELL_HEIGHT as f32));
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let mut target = rl.begin_texture_mode(thread, &mut grid);
+        target.draw_text(name, x as i32 + 8, y as i32 + 6, 18, Color::RAYWHITE);
+        target.draw_texture_rec(cell.texture(), source, Vector2::new(x, y + TITLE_HEIGHT as f32), Color::WHITE);
+    }
+
+    std::fs::create_dir_all(CAPTURE_DIR).map_err(|e| format!("{}: {}", CAPTURE_DIR, e))?;
+    let mut image = grid.load_image().map_err(|e| e.to_string())?;
+    image.flip_vertical();
+    let output = format!("{}/catalogo.png", CAPTURE_DIR);
+    image.export_image(&output);
+    Ok(output)
+}
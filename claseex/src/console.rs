@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::behavior::Behavior;
+use crate::lesson::{Lesson, LessonPlayer};
+use crate::light_rig::LightRig;
+use crate::measure::Annotation;
+use crate::patch::Patch;
+use crate::pool::ObjectPool;
+use crate::prefab::Prefab;
+use crate::presentation::{Shot, DEFAULT_SHOT_DURATION};
+use crate::scatter::ScatterSettings;
+use crate::scene::{Scene, Shape};
+use crate::weather::WeatherPreset;
+
+// Líneas de registro que se conservan en la consola
+const MAX_LOG_LINES: usize = 200;
+
+// Estado al que tienen acceso los comandos y los scripts
+pub struct ConsoleContext<'a> {
+    pub scene: &'a mut Scene,
+    pub camera: &'a mut Camera3D,
+    pub pool: &'a mut ObjectPool,
+    pub lesson: &'a mut LessonPlayer,
+}
+
+// Función registrada desde el código que se puede invocar con `script <nombre>`
+pub type ScriptCallback = Box<dyn FnMut(&mut ConsoleContext) -> String>;
+
+// Consola de comandos: entrada de texto, registro de salida y scripts registrados
+pub struct Console {
+    pub open: bool,
+    pub input: String,
+    pub log: Vec<String>,
+    scripts: HashMap<String, ScriptCallback>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Console {
+            open: false,
+            input: String::new(),
+            log: Vec::new(),
+            scripts: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &str, callback: ScriptCallback) {
+        self.scripts.insert(name.to_string(), callback);
+    }
+
+    pub fn print(&mut self, text: &str) {
+        self.log.extend(text.lines().map(str::to_string));
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.drain(..self.log.len() - MAX_LOG_LINES);
+        }
+    }
+
+    // Ejecuta el texto escrito en la entrada y la vacía
+    pub fn submit(&mut self, context: &mut ConsoleContext) {
+        let command = std::mem::take(&mut self.input);
+        if command.trim().is_empty() {
+            return;
+        }
+        self.print(&format!("> {}", command));
+        let output = self.execute(&command, context);
+        self.print(&output);
+    }
+
+    // Interpreta un comando y devuelve el texto de respuesta
+    pub fn execute(&mut self, command: &str, context: &mut ConsoleContext) -> String {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let number = |index: usize| words.get(index).and_then(|word| word.parse::<f32>().ok());
+
+        match words.as_slice() {
+            [] => String::new(),
+            ["ayuda"] => {
+                let mut names: Vec<&str> = self.scripts.keys().map(String::as_str).collect();
+                names.sort();
+                format!(
+                    "mensaje <texto>  capa <nombre> ver|ocultar  luz <i> intensidad|radio <v>  luz <i> sombra si|no\n\
+                     camara <x y z> [<x y z>]  velocidad <objeto> <v>  ambiente <v>  difusa <v>\n\
+                     nota <x y z> <texto>  notas  borrar_nota <i>\n\
+                     clima clear|rain|snow|storm  parche exportar|aplicar <archivo>\n\
+                     lanzar cube|sphere [cantidad] [<x y z>]  dinamicos [limpiar]\n\
+                     iluminacion tres_puntos|estudio|atardecer|noche [objeto]  agente <objeto> [destino <x y z>]\n\
+                     esparcir <prefab> <superficie> [densidad] [escala_min escala_max] [semilla]  (densidad 0 quita)\n\
+                     toma agregar [segundos]  toma borrar <i>  tomas  presentacion curva <nombre> [velocidad]|tomas\n\
+                     leccion [<nombre>|saltar|salir]\n\
+                     script <nombre>: {}",
+                    names.join(", ")
+                )
+            }
+            ["mensaje", ..] => words[1..].join(" "),
+            ["capa", name, mode @ ("ver" | "ocultar")] => {
+                match context.scene.layers.iter_mut().find(|layer| layer.name == *name) {
+                    Some(layer) => {
+                        layer.visible = *mode == "ver";
+                        format!("Capa '{}' {}", name, if layer.visible { "visible" } else { "oculta" })
+                    }
+                    None => format!("No existe la capa '{}'", name),
+                }
+            }
+            ["luz", index, property @ ("intensidad" | "radio"), _] => {
+                let light = index.parse::<usize>().ok().and_then(|i| context.scene.lights.get_mut(i));
+                match (light, number(3)) {
+                    (Some(light), Some(value)) => {
+                        if *property == "intensidad" {
+                            light.intensity = value.max(0.0);
+                        } else {
+                            light.range = value.max(0.5);
+                        }
+                        format!("Luz {}: {} = {:.2}", index, property, value)
+                    }
+                    _ => "Uso: luz <indice> intensidad|radio <valor>".to_string(),
+                }
+            }
+            ["luz", index, "sombra", mode @ ("si" | "no")] => {
+                match index.parse::<usize>().ok().and_then(|i| context.scene.lights.get_mut(i)) {
+                    Some(light) => {
+                        light.cast_shadows = *mode == "si";
+                        format!("Luz {}: {}", index, if light.cast_shadows { "con sombra" } else { "sin sombra" })
+                    }
+                    None => format!("No existe la luz {}", index),
+                }
+            }
+            ["camara", ..] => {
+                let values: Option<Vec<f32>> = (1..words.len()).map(number).collect();
+                match values.as_deref() {
+                    Some([x, y, z]) => {
+                        context.camera.position = Vector3::new(*x, *y, *z);
+                        "Cámara movida".to_string()
+                    }
+                    Some([x, y, z, tx, ty, tz]) => {
+                        context.camera.position = Vector3::new(*x, *y, *z);
+                        context.camera.target = Vector3::new(*tx, *ty, *tz);
+                        "Cámara movida".to_string()
+                    }
+                    _ => "Uso: camara <x> <y> <z> [<objetivo x> <y> <z>]".to_string(),
+                }
+            }
+            ["ambiente", _] | ["difusa", _] => match number(1) {
+                Some(value) => {
+                    let value = value.clamp(0.0, 2.0);
+                    if words[0] == "ambiente" {
+                        // Un valor elegido a mano pasa a ser el de día, también de noche
+                        context.scene.ambient_intensity = value;
+                        context.scene.day_ambient = None;
+                    } else {
+                        context.scene.diffuse_intensity = value;
+                    }
+                    format!("Luz {}: {:.2}", words[0], value)
+                }
+                None => format!("Uso: {} <valor>", words[0]),
+            },
+            ["velocidad", name, _] => {
+                let value = number(2);
+                let follow = context.scene.find_by_name_mut(name).and_then(|object| object.follow.as_mut());
+                match (follow, value) {
+                    (Some(follow), Some(value)) => {
+                        follow.speed = value;
+                        format!("'{}' recorre su curva a {:.2}", name, value)
+                    }
+                    _ => format!("'{}' no sigue ninguna curva", name),
+                }
+            }
+            ["nota", _, _, _, _, ..] => match (number(1), number(2), number(3)) {
+                (Some(x), Some(y), Some(z)) => {
+                    let text = words[4..].join(" ");
+                    context.scene.annotations.push(Annotation {
+                        position: Vector3::new(x, y, z),
+                        text: text.clone(),
+                    });
+                    format!("Nota {} agregada: {}", context.scene.annotations.len() - 1, text)
+                }
+                _ => "Uso: nota <x> <y> <z> <texto>".to_string(),
+            },
+            ["notas"] => context
+                .scene
+                .annotations
+                .iter()
+                .enumerate()
+                .map(|(index, annotation)| format!("{}: {}", index, annotation.text))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ["borrar_nota", index] => match index.parse::<usize>() {
+                Ok(index) if index < context.scene.annotations.len() => {
+                    let annotation = context.scene.annotations.remove(index);
+                    format!("Nota borrada: {}", annotation.text)
+                }
+                _ => format!("No existe la nota {}", index),
+            },
+            ["clima", name] => match WeatherPreset::from_name(name) {
+                Some(preset) => {
+                    context.scene.weather = preset;
+                    format!("Clima: {}", name)
+                }
+                None => format!("Clima desconocido: {}", name),
+            },
+            ["parche", "exportar", path] => {
+                let patch = context.scene.export_patch();
+                match patch.save(path) {
+                    Ok(()) => format!("Parche con {} cambios guardado en {}", patch.changes.len(), path),
+                    Err(error) => format!("Error al guardar el parche: {}", error),
+                }
+            }
+            ["parche", "aplicar", path] => {
+                match Patch::load(path).and_then(|patch| context.scene.apply_patch(&patch)) {
+                    Ok(conflicts) if conflicts.is_empty() => format!("Parche {} aplicado", path),
+                    Ok(conflicts) => format!(
+                        "Parche {} aplicado con {} conflictos:\n{}",
+                        path,
+                        conflicts.len(),
+                        conflicts.join("\n")
+                    ),
+                    Err(error) => format!("Error al aplicar el parche: {}", error),
+                }
+            }
+            ["lanzar", shape @ ("cube" | "sphere"), ..] => {
+                let count = words.get(2).and_then(|word| word.parse::<usize>().ok()).unwrap_or(8).min(64);
+                // Sin posición salen por encima del punto que mira la cámara
+                let origin = match (number(3), number(4), number(5)) {
+                    (Some(x), Some(y), Some(z)) => Vector3::new(x, y, z),
+                    _ => context.camera.target + Vector3::new(0.0, 1.5, 0.0),
+                };
+                let shape = Shape::from_name(shape).unwrap_or(Shape::Cube);
+                context.pool.burst(context.scene, shape, origin, count);
+                format!("{} objetos lanzados ({} vivos)", count, context.pool.active_count())
+            }
+            ["dinamicos"] => format!(
+                "Objetos dinamicos: {} vivos, {} libres en el pool",
+                context.pool.active_count(),
+                context.pool.free_count()
+            ),
+            ["dinamicos", "limpiar"] => {
+                let count = context.pool.clear(context.scene);
+                format!("{} objetos devueltos al pool", count)
+            }
+            ["iluminacion", name, ..] => match LightRig::from_name(name) {
+                Some(rig) => {
+                    let focus = words
+                        .get(2)
+                        .and_then(|name| context.scene.find_by_name_mut(name))
+                        .map(|object| object.id);
+                    context.scene.apply_light_rig(rig, focus, context.camera);
+                    format!("Iluminacion '{}' con {} luces", rig.label(), context.scene.lights.len())
+                }
+                None => format!("Iluminacion desconocida: {}", name),
+            },
+            // Convierte el objeto en agente si todavía no lo es; sin destino vuelve a deambular
+            ["agente", name, ..] => {
+                let destination = match (words.get(2).copied(), number(3), number(4), number(5)) {
+                    (Some("destino"), Some(x), Some(y), Some(z)) => Some(Vector3::new(x, y, z)),
+                    _ => None,
+                };
+                let Some(id) = context.scene.find_by_name_mut(name).map(|object| object.id) else {
+                    return format!("No existe el objeto '{}'", name);
+                };
+                let planned = destination.map(|point| (point, context.scene.plan_route(id, point)));
+                let Some(object) = context.scene.object_mut(id) else {
+                    return format!("No existe el objeto '{}'", name);
+                };
+                if !object.behaviors.iter().any(|behavior| matches!(behavior, Behavior::Agent { .. })) {
+                    object.behaviors.extend(Behavior::with_defaults("agent", object.position));
+                }
+                for behavior in object.behaviors.iter_mut() {
+                    if let Behavior::Agent {
+                        seek,
+                        target,
+                        route,
+                        planned: planned_target,
+                        ..
+                    } = behavior
+                    {
+                        *seek = destination.is_some();
+                        *target = destination.unwrap_or(*target);
+                        // El camino ya calculado se usa tal cual en lugar de volver a buscarlo; sin camino
+                        // se va en línea recta
+                        *route = planned
+                            .as_ref()
+                            .map(|(point, points)| points.clone().unwrap_or_else(|| vec![*point]))
+                            .unwrap_or_default();
+                        *planned_target = destination;
+                    }
+                }
+                match planned {
+                    Some((point, Some(route))) => format!(
+                        "'{}' va hacia ({:.1}, {:.1}, {:.1}) por {} puntos de paso",
+                        name,
+                        point.x,
+                        point.y,
+                        point.z,
+                        route.len()
+                    ),
+                    Some((_, None)) => format!("'{}' no encuentra camino; va en linea recta", name),
+                    None => format!("'{}' deambula", name),
+                }
+            }
+            // Las instancias quedan en la escena abierta; se guardan con Ctrl+S como cualquier otro cambio
+            ["esparcir", prefab, surface, ..] => {
+                let defaults = ScatterSettings::default();
+                let settings = ScatterSettings {
+                    density: number(3).unwrap_or(defaults.density),
+                    min_scale: number(4).unwrap_or(defaults.min_scale).max(0.05),
+                    max_scale: number(5).unwrap_or(defaults.max_scale).max(0.05),
+                    seed: number(6).map_or(defaults.seed, |seed| seed as u32),
+                };
+                let result = Prefab::load(prefab).and_then(|prefab| context.scene.scatter(&prefab, surface, settings));
+                match result {
+                    Ok(count) => format!("{} instancias de '{}' sobre '{}'", count, prefab, surface),
+                    Err(error) => format!("Error al esparcir: {}", error),
+                }
+            }
+            // Tomas de la cámara de presentación: se agrega la vista actual del editor
+            ["toma", "agregar", ..] => {
+                let duration = number(2).unwrap_or(DEFAULT_SHOT_DURATION).max(1.0);
+                context.scene.presentation.shots.push(Shot {
+                    position: context.camera.position,
+                    target: context.camera.target,
+                    duration,
+                });
+                format!("Toma {} agregada ({:.1} s)", context.scene.presentation.shots.len() - 1, duration)
+            }
+            ["toma", "borrar", index] => match index.parse::<usize>() {
+                Ok(index) if index < context.scene.presentation.shots.len() => {
+                    context.scene.presentation.shots.remove(index);
+                    format!("Toma {} borrada", index)
+                }
+                _ => format!("No existe la toma {}", index),
+            },
+            ["tomas"] => context
+                .scene
+                .presentation
+                .shots
+                .iter()
+                .enumerate()
+                .map(|(index, shot)| {
+                    let p = shot.position;
+                    format!("{}: ({:.1}, {:.1}, {:.1}) {:.1} s", index, p.x, p.y, p.z, shot.duration)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            ["presentacion", "curva", name, ..] => {
+                if context.scene.paths.iter().any(|path| path.name == *name) {
+                    let presentation = &mut context.scene.presentation;
+                    presentation.path = Some(name.to_string());
+                    presentation.speed = number(3).unwrap_or(presentation.speed);
+                    // Mira al punto que está mirando ahora el editor
+                    presentation.look_at = context.camera.target;
+                    format!("La presentacion recorre '{}'", name)
+                } else {
+                    format!("No existe la curva '{}'", name)
+                }
+            }
+            ["presentacion", "tomas"] => {
+                context.scene.presentation.path = None;
+                "La presentacion usa las tomas fijas".to_string()
+            }
+            // Lecciones: pasos que avanzan solos cuando se cumple lo que pide cada uno
+            ["leccion"] => match context.lesson.current() {
+                Some((index, checkpoint)) => format!(
+                    "Paso {}/{}: {}",
+                    index + 1,
+                    context.lesson.lesson().map_or(0, |lesson| lesson.checkpoints.len()),
+                    checkpoint.text
+                ),
+                None => format!("Lecciones: {}", Lesson::available().join(", ")),
+            },
+            ["leccion", "saltar"] => {
+                context.lesson.skip();
+                match context.lesson.current() {
+                    Some((index, checkpoint)) => format!("Paso {}: {}", index + 1, checkpoint.text),
+                    None => "Leccion completa".to_string(),
+                }
+            }
+            ["leccion", "salir"] => {
+                context.lesson.stop();
+                "Leccion cerrada".to_string()
+            }
+            ["leccion", name] => match Lesson::load(name) {
+                Ok(lesson) => {
+                    let message = format!("Leccion '{}': {} pasos", lesson.title, lesson.checkpoints.len());
+                    context.lesson.start(lesson);
+                    message
+                }
+                Err(error) => format!("Error al cargar la leccion: {}", error),
+            },
+            ["script", name] => match self.scripts.get_mut(*name) {
+                Some(callback) => callback(context),
+                None => format!("Script desconocido: {}", name),
+            },
+            _ => format!("Comando desconocido: {} (escribe 'ayuda')", command.trim()),
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::geometry::{self, Geometry, Vertex};
+use crate::renderer::world_vertices;
+use crate::scene::{vec3_from_json, vec3_to_json};
+
+// Tolerancia para decidir si un vértice está sobre un plano
+const EPSILON: f32 = 1e-5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Subtract,
+    Intersect,
+}
+
+impl CsgOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsgOperation::Union => "union",
+            CsgOperation::Subtract => "subtract",
+            CsgOperation::Intersect => "intersect",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CsgOperation> {
+        match name {
+            "union" => Some(CsgOperation::Union),
+            "subtract" => Some(CsgOperation::Subtract),
+            "intersect" => Some(CsgOperation::Intersect),
+            _ => None,
+        }
+    }
+}
+
+// Primitivas que se pueden combinar; el cilindro solo existe dentro de un sólido CSG
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CsgShape {
+    Cube,
+    Sphere,
+    Cylinder,
+}
+
+impl CsgShape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsgShape::Cube => "cube",
+            CsgShape::Sphere => "sphere",
+            CsgShape::Cylinder => "cylinder",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CsgShape> {
+        match name {
+            "cube" => Some(CsgShape::Cube),
+            "sphere" => Some(CsgShape::Sphere),
+            "cylinder" => Some(CsgShape::Cylinder),
+            _ => None,
+        }
+    }
+
+    fn geometry(&self) -> Geometry {
+        match self {
+            CsgShape::Cube => geometry::cube(2),
+            CsgShape::Sphere => geometry::sphere(10, 16),
+            CsgShape::Cylinder => geometry::cylinder(20),
+        }
+    }
+}
+
+// Árbol de operaciones; las primitivas se ubican en el espacio local unitario del objeto
+#[derive(Clone, Debug, PartialEq)]
+pub enum CsgNode {
+    Primitive {
+        shape: CsgShape,
+        position: Vector3,
+        rotation: Vector3, // Grados
+        size: Vector3,
+    },
+    Operation {
+        operation: CsgOperation,
+        a: Box<CsgNode>,
+        b: Box<CsgNode>,
+    },
+}
+
+// Sólido de un objeto con la geometría ya calculada al cargar la escena.
+// Reemplaza a la forma base al dibujar, en las sombras y al elegir con el mouse
+#[derive(Clone, Debug)]
+pub struct Csg {
+    pub tree: CsgNode,
+    pub geometry: Geometry,
+}
+
+impl Csg {
+    pub fn new(tree: CsgNode) -> Self {
+        let polygons = evaluate(&tree);
+        let mut geometry = Geometry::default();
+        for polygon in polygons {
+            // Los polígonos son convexos: alcanza con un abanico desde el primer vértice
+            for i in 1..polygon.vertices.len() - 1 {
+                geometry.vertices.extend_from_slice(&[
+                    polygon.vertices[0],
+                    polygon.vertices[i],
+                    polygon.vertices[i + 1],
+                ]);
+            }
+        }
+        Csg { tree, geometry }
+    }
+}
+
+// Polígono convexo con el plano que lo contiene (normal · p = w)
+#[derive(Clone, Debug)]
+struct Polygon {
+    vertices: Vec<Vertex>,
+    normal: Vector3,
+    w: f32,
+}
+
+impl Polygon {
+    fn new(vertices: Vec<Vertex>) -> Option<Polygon> {
+        let normal = (vertices[1].position - vertices[0].position).cross(vertices[2].position - vertices[0].position);
+        if normal.length() < 1e-12 {
+            return None;
+        }
+        let normal = normal.normalized();
+        let w = normal.dot(vertices[0].position);
+        Some(Polygon { vertices, normal, w })
+    }
+
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        for vertex in &mut self.vertices {
+            vertex.normal = -vertex.normal;
+        }
+        self.normal = -self.normal;
+        self.w = -self.w;
+    }
+}
+
+// Listas donde se reparte un polígono al cortarlo con un plano
+struct Split<'a> {
+    coplanar_front: &'a mut Vec<Polygon>,
+    coplanar_back: &'a mut Vec<Polygon>,
+    front: &'a mut Vec<Polygon>,
+    back: &'a mut Vec<Polygon>,
+}
+
+const COPLANAR: u8 = 0;
+const FRONT: u8 = 1;
+const BACK: u8 = 2;
+const SPANNING: u8 = 3;
+
+// Corta el polígono con el plano; las partes que lo cruzan se dividen interpolando los vértices
+fn split_polygon(plane: (Vector3, f32), polygon: Polygon, out: Split) {
+    let (normal, w) = plane;
+    let sides: Vec<u8> = polygon
+        .vertices
+        .iter()
+        .map(|vertex| {
+            let t = normal.dot(vertex.position) - w;
+            if t < -EPSILON {
+                BACK
+            } else if t > EPSILON {
+                FRONT
+            } else {
+                COPLANAR
+            }
+        })
+        .collect();
+    match sides.iter().fold(COPLANAR, |all, side| all | side) {
+        COPLANAR => {
+            if normal.dot(polygon.normal) > 0.0 {
+                out.coplanar_front.push(polygon);
+            } else {
+                out.coplanar_back.push(polygon);
+            }
+        }
+        FRONT => out.front.push(polygon),
+        BACK => out.back.push(polygon),
+        _ => {
+            let (mut front, mut back) = (Vec::new(), Vec::new());
+            let count = polygon.vertices.len();
+            for i in 0..count {
+                let j = (i + 1) % count;
+                let (vi, vj) = (polygon.vertices[i], polygon.vertices[j]);
+                if sides[i] != BACK {
+                    front.push(vi);
+                }
+                if sides[i] != FRONT {
+                    back.push(vi);
+                }
+                if sides[i] | sides[j] == SPANNING {
+                    let t = (w - normal.dot(vi.position)) / normal.dot(vj.position - vi.position);
+                    let vertex = Vertex {
+                        position: vi.position.lerp(vj.position, t),
+                        normal: vi.normal.lerp(vj.normal, t).normalized(),
+                    };
+                    front.push(vertex);
+                    back.push(vertex);
+                }
+            }
+            // Las partes conservan el plano del polígono original para no acumular error
+            if front.len() >= 3 {
+                out.front.push(Polygon { vertices: front, ..polygon.clone() });
+            }
+            if back.len() >= 3 {
+                out.back.push(Polygon { vertices: back, ..polygon });
+            }
+        }
+    }
+}
+
+// Árbol BSP de un sólido: cada nodo separa lo que queda delante y detrás de su plano
+#[derive(Default)]
+struct BspNode {
+    plane: Option<(Vector3, f32)>,
+    front: Option<Box<BspNode>>,
+    back: Option<Box<BspNode>>,
+    polygons: Vec<Polygon>,
+}
+
+impl BspNode {
+    fn new(polygons: Vec<Polygon>) -> Self {
+        let mut node = BspNode::default();
+        node.build(polygons);
+        node
+    }
+
+    // Intercambia interior y exterior del sólido
+    fn invert(&mut self) {
+        for polygon in &mut self.polygons {
+            polygon.flip();
+        }
+        if let Some((normal, w)) = self.plane {
+            self.plane = Some((-normal, -w));
+        }
+        if let Some(front) = self.front.as_mut() {
+            front.invert();
+        }
+        if let Some(back) = self.back.as_mut() {
+            back.invert();
+        }
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+
+    // Quita de la lista lo que queda dentro del sólido de este árbol
+    fn clip_polygons(&self, polygons: Vec<Polygon>) -> Vec<Polygon> {
+        let Some(plane) = self.plane else {
+            return polygons;
+        };
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        let (mut coplanar_front, mut coplanar_back) = (Vec::new(), Vec::new());
+        for polygon in polygons {
+            split_polygon(
+                plane,
+                polygon,
+                Split {
+                    coplanar_front: &mut coplanar_front,
+                    coplanar_back: &mut coplanar_back,
+                    front: &mut front,
+                    back: &mut back,
+                },
+            );
+        }
+        front.append(&mut coplanar_front);
+        back.append(&mut coplanar_back);
+        let mut front = match &self.front {
+            Some(node) => node.clip_polygons(front),
+            None => front,
+        };
+        let back = match &self.back {
+            Some(node) => node.clip_polygons(back),
+            None => Vec::new(),
+        };
+        front.extend(back);
+        front
+    }
+
+    // Recorta los polígonos de este árbol contra el sólido de `other`
+    fn clip_to(&mut self, other: &BspNode) {
+        self.polygons = other.clip_polygons(std::mem::take(&mut self.polygons));
+        if let Some(front) = self.front.as_mut() {
+            front.clip_to(other);
+        }
+        if let Some(back) = self.back.as_mut() {
+            back.clip_to(other);
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<Polygon> {
+        let mut polygons = self.polygons.clone();
+        if let Some(front) = &self.front {
+            polygons.extend(front.all_polygons());
+        }
+        if let Some(back) = &self.back {
+            polygons.extend(back.all_polygons());
+        }
+        polygons
+    }
+
+    fn build(&mut self, polygons: Vec<Polygon>) {
+        let Some(first) = polygons.first() else {
+            return;
+        };
+        let plane = *self.plane.get_or_insert((first.normal, first.w));
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        let (mut coplanar_front, mut coplanar_back) = (Vec::new(), Vec::new());
+        for polygon in polygons {
+            split_polygon(
+                plane,
+                polygon,
+                Split {
+                    coplanar_front: &mut coplanar_front,
+                    coplanar_back: &mut coplanar_back,
+                    front: &mut front,
+                    back: &mut back,
+                },
+            );
+        }
+        self.polygons.append(&mut coplanar_front);
+        self.polygons.append(&mut coplanar_back);
+        if !front.is_empty() {
+            self.front.get_or_insert_with(Default::default).build(front);
+        }
+        if !back.is_empty() {
+            self.back.get_or_insert_with(Default::default).build(back);
+        }
+    }
+}
+
+// Polígonos del sólido descripto por el árbol
+fn evaluate(node: &CsgNode) -> Vec<Polygon> {
+    match node {
+        CsgNode::Primitive {
+            shape,
+            position,
+            rotation,
+            size,
+        } => {
+            let rotation = Vector3::new(rotation.x.to_radians(), rotation.y.to_radians(), rotation.z.to_radians());
+            let model = Matrix::scale(size.x, size.y, size.z)
+                * Matrix::rotate_xyz(rotation)
+                * Matrix::translate(position.x, position.y, position.z);
+            world_vertices(&shape.geometry(), model)
+                .chunks(3)
+                .filter_map(|triangle| Polygon::new(triangle.to_vec()))
+                .collect()
+        }
+        CsgNode::Operation { operation, a, b } => {
+            let mut a = BspNode::new(evaluate(a));
+            let mut b = BspNode::new(evaluate(b));
+            // Combinaciones clásicas con árboles BSP: recortar cada sólido contra el otro
+            match operation {
+                CsgOperation::Union => {
+                    a.clip_to(&b);
+                    b.clip_to(&a);
+                    b.invert();
+                    b.clip_to(&a);
+                    b.invert();
+                    a.build(b.all_polygons());
+                }
+                CsgOperation::Subtract => {
+                    a.invert();
+                    a.clip_to(&b);
+                    b.clip_to(&a);
+                    b.invert();
+                    b.clip_to(&a);
+                    b.invert();
+                    a.build(b.all_polygons());
+                    a.invert();
+                }
+                CsgOperation::Intersect => {
+                    a.invert();
+                    b.clip_to(&a);
+                    b.invert();
+                    a.clip_to(&b);
+                    b.clip_to(&a);
+                    a.build(b.all_polygons());
+                    a.invert();
+                }
+            }
+            a.all_polygons()
+        }
+    }
+}
+
+// Formato: { "operation": "subtract", "a": { "shape": "cube" }, "b": { "shape": "cylinder", "size": [...] } }
+pub fn csg_from_json(value: &Value) -> Result<CsgNode, String> {
+    if let Some(name) = value["operation"].as_str() {
+        let operation = CsgOperation::from_name(name).ok_or(format!("operación CSG desconocida: {}", name))?;
+        return Ok(CsgNode::Operation {
+            operation,
+            a: Box::new(csg_from_json(&value["a"])?),
+            b: Box::new(csg_from_json(&value["b"])?),
+        });
+    }
+    let name = value["shape"].as_str().ok_or("nodo CSG sin 'operation' ni 'shape'")?;
+    Ok(CsgNode::Primitive {
+        shape: CsgShape::from_name(name).ok_or(format!("primitiva CSG desconocida: {}", name))?,
+        position: vec3_from_json(&value["position"]).unwrap_or(Vector3::zero()),
+        rotation: vec3_from_json(&value["rotation"]).unwrap_or(Vector3::zero()),
+        size: vec3_from_json(&value["size"]).unwrap_or(Vector3::one()),
+    })
+}
+
+pub fn csg_to_json(node: &CsgNode) -> Value {
+    match node {
+        CsgNode::Primitive {
+            shape,
+            position,
+            rotation,
+            size,
+        } => json!({
+            "shape": shape.name(),
+            "position": vec3_to_json(*position),
+            "rotation": vec3_to_json(*rotation),
+            "size": vec3_to_json(*size),
+        }),
+        CsgNode::Operation { operation, a, b } => json!({
+            "operation": operation.name(),
+            "a": csg_to_json(a),
+            "b": csg_to_json(b),
+        }),
+    }
+}
@@ -0,0 +1,82 @@
+use raylib::prelude::*;
+
+use crate::audio::AudioEmitter;
+use crate::frustum::Frustum;
+use crate::lighting::{Light, LightKind};
+use crate::path::{SplinePath, CONTROL_POINT_RADIUS};
+
+// Dibuja las aristas del frustum de una cámara vista desde otra
+pub fn draw_frustum(d3d: &mut impl RaylibDraw3D, frustum: &Frustum, color: Color) {
+    let n = &frustum.near_corners;
+    let f = &frustum.far_corners;
+
+    for i in 0..4 {
+        let next = (i + 1) % 4;
+        d3d.draw_line_3D(n[i], n[next], color);
+        d3d.draw_line_3D(f[i], f[next], color);
+        d3d.draw_line_3D(n[i], f[i], color);
+        // Líneas tenues desde la posición de la cámara para ubicar el ápice
+        d3d.draw_line_3D(frustum.apex, n[i], color.alpha(0.4));
+    }
+
+    d3d.draw_sphere(frustum.apex, 0.1, color);
+}
+
+// Dibuja el volumen de alcance de una luz en alambre
+pub fn draw_light_volume(d3d: &mut impl RaylibDraw3D, light: &Light, color: Color) {
+    d3d.draw_sphere(light.position, 0.15, light.color);
+
+    match light.kind {
+        LightKind::Point => {
+            d3d.draw_sphere_wires(light.position, light.range, 8, 16, color);
+        }
+        LightKind::Spot => {
+            // El cono termina en la base a la distancia de alcance
+            let base_center = light.position + light.direction * light.range;
+            let base_radius = light.range * light.cone_angle.to_radians().tan();
+            d3d.draw_cylinder_wires_ex(light.position, base_center, 0.0, base_radius, 16, color);
+            d3d.draw_line_3D(light.position, base_center, color.alpha(0.5));
+        }
+    }
+}
+
+// Dibuja el alcance de un emisor de sonido como una esfera de alambre
+pub fn draw_audio_emitter(d3d: &mut impl RaylibDraw3D, emitter: &AudioEmitter, position: Vector3, color: Color) {
+    d3d.draw_cube(position, 0.2, 0.2, 0.2, color);
+    d3d.draw_sphere_wires(position, emitter.radius, 6, 12, color.alpha(0.5));
+}
+
+// Dibuja una curva y, mientras se edita, sus puntos de control
+pub fn draw_path(
+    d3d: &mut impl RaylibDraw3D,
+    path: &SplinePath,
+    color: Color,
+    show_points: bool,
+    selected_point: Option<usize>,
+) {
+    let points: Vec<Vector3> = path.sample().points().collect();
+    for pair in points.windows(2) {
+        d3d.draw_line_3D(pair[0], pair[1], color);
+    }
+
+    if show_points {
+        for (index, point) in path.points.iter().enumerate() {
+            let point_color = if selected_point == Some(index) { Color::YELLOW } else { color };
+            d3d.draw_sphere(*point, CONTROL_POINT_RADIUS, point_color);
+        }
+        // Polígono de control tenue para ver cómo influye cada punto
+        for pair in path.points.windows(2) {
+            d3d.draw_line_3D(pair[0], pair[1], color.alpha(0.3));
+        }
+    }
+}
+
+// Dibuja el camino que sigue un agente: desde su posición por cada punto de paso
+pub fn draw_route(d3d: &mut impl RaylibDraw3D, from: Vector3, route: &[Vector3], color: Color) {
+    let mut previous = from;
+    for point in route {
+        d3d.draw_line_3D(previous, *point, color);
+        d3d.draw_sphere(*point, 0.06, color);
+        previous = *point;
+    }
+}
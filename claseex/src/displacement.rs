@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
+
+use raylib::ffi;
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::geometry::Vertex;
+use crate::renderer;
+use crate::rng::noise3;
+use crate::scene::{f32_to_json, Scene};
+
+// Mueve cada vértice sobre `vertexNormal` (la dirección de desplazamiento) con el mismo ruido de valor
+// que rng::noise3: la misma grilla, el mismo hash y la misma curva, así la GPU y la CPU coinciden
+const DISPLACEMENT_VERTEX: &str = r#"#version 330
+in vec3 vertexPosition;
+in vec3 vertexNormal;
+in vec4 vertexColor;
+
+uniform mat4 mvp;
+uniform float amplitude;
+uniform float scale;
+uniform float speed;
+uniform float time;
+
+out vec2 fragTexCoord;
+out vec4 fragColor;
+
+float lattice(ivec3 cell) {
+    uint h = uint(cell.x)*0x8da6b343u ^ uint(cell.y)*0xd8163841u ^ uint(cell.z)*0xcb1ab31fu;
+    h ^= h >> 13u;
+    h *= 0x5bd1e995u;
+    h ^= h >> 15u;
+    return float(h)/4294967295.0*2.0 - 1.0;
+}
+
+float noise3(vec3 p) {
+    vec3 cell = floor(p);
+    vec3 t = p - cell;
+    vec3 f = t*t*t*(t*(t*6.0 - 15.0) + 10.0);
+    ivec3 i = ivec3(cell);
+    float c000 = lattice(i);
+    float c100 = lattice(i + ivec3(1, 0, 0));
+    float c010 = lattice(i + ivec3(0, 1, 0));
+    float c110 = lattice(i + ivec3(1, 1, 0));
+    float c001 = lattice(i + ivec3(0, 0, 1));
+    float c101 = lattice(i + ivec3(1, 0, 1));
+    float c011 = lattice(i + ivec3(0, 1, 1));
+    float c111 = lattice(i + ivec3(1, 1, 1));
+    return mix(
+        mix(mix(c000, c100, f.x), mix(c010, c110, f.x), f.y),
+        mix(mix(c001, c101, f.x), mix(c011, c111, f.x), f.y),
+        f.z);
+}
+
+void main() {
+    float phase = time*speed;
+    vec3 p = vertexPosition*scale + phase*vec3(1.0, 0.7, 1.3);
+    vec3 position = vertexPosition + vertexNormal*noise3(p)*amplitude;
+    fragTexCoord = vec2(0.0);
+    fragColor = vertexColor;
+    gl_Position = mvp*vec4(position, 1.0);
+}
+"#;
+
+// Opción de material: mueve los vértices sobre su normal con ruido animado (gelatina, banderas).
+// Con el shader de vértices cargado se desplaza en la GPU; si no, o en los buffers que arma la CPU
+// (normales de los reflejos, miniaturas), se desplaza acá con el mismo ruido
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Displacement {
+    pub amplitude: f32, // Desplazamiento máximo en unidades de mundo
+    pub scale: f32,     // Frecuencia espacial del ruido: más alto, ondas más chicas
+    pub speed: f32,     // Velocidad con la que avanza el ruido
+    pub time: f32,      // No se guarda: es solo la fase de la animación
+}
+
+impl Displacement {
+    // Dirección en la que se mueve cada vértice. Los que comparten posición (aristas del cubo) usan la
+    // misma para no abrirse; si sus normales se anulan (caras opuestas pegadas) se usa la propia
+    pub fn directions(vertices: &[Vertex]) -> Vec<Vector3> {
+        let key = |p: Vector3| ((p.x * 1000.0).round() as i32, (p.y * 1000.0).round() as i32, (p.z * 1000.0).round() as i32);
+        let mut sums: HashMap<(i32, i32, i32), Vector3> = HashMap::new();
+        for vertex in vertices {
+            *sums.entry(key(vertex.position)).or_insert(Vector3::zero()) += vertex.normal;
+        }
+        vertices
+            .iter()
+            .map(|vertex| {
+                let sum = sums[&key(vertex.position)];
+                if sum.length() > 1e-4 { sum.normalized() } else { vertex.normal }
+            })
+            .collect()
+    }
+
+    // Cuánto se mueve un punto de mundo sobre su dirección en este momento de la animación
+    pub fn offset(&self, p: Vector3) -> f32 {
+        let phase = self.time * self.speed;
+        let n = noise3(p.x * self.scale + phase, p.y * self.scale + phase * 0.7, p.z * self.scale + phase * 1.3);
+        n * self.amplitude
+    }
+
+    // Desplaza vértices ya en coordenadas de mundo y corrige sus normales con la inclinación nueva
+    pub fn apply(&self, vertices: &mut [Vertex]) {
+        let directions = Self::directions(vertices);
+        let face_normal = |t: &[Vertex]| (t[1].position - t[0].position).cross(t[2].position - t[0].position).normalized();
+
+        for (triangle, directions) in vertices.chunks_mut(3).zip(directions.chunks(3)) {
+            let before = face_normal(triangle);
+            for (vertex, direction) in triangle.iter_mut().zip(directions) {
+                vertex.position += *direction * self.offset(vertex.position);
+            }
+            // La normal de cada vértice gira lo mismo que giró su cara, así se notan las ondas
+            let turn = face_normal(triangle) - before;
+            for vertex in triangle.iter_mut() {
+                vertex.normal = (vertex.normal + turn).normalized();
+            }
+        }
+    }
+}
+
+// Shader de vértices que desplaza en la GPU, con los mismos parámetros que `Displacement::apply`
+#[derive(Debug)]
+pub struct DisplacementShader {
+    shader: Shader,
+    amplitude: i32,
+    scale: i32,
+    speed: i32,
+    time: i32,
+}
+
+impl DisplacementShader {
+    pub fn load(rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<Self, String> {
+        let shader = rl.load_shader_from_memory(thread, Some(DISPLACEMENT_VERTEX), None);
+        // Si no compila raylib devuelve su shader por defecto
+        if shader.id == unsafe { ffi::rlGetShaderIdDefault() } {
+            return Err("no compiló el shader de desplazamiento".to_string());
+        }
+        Ok(DisplacementShader {
+            amplitude: shader.get_shader_location("amplitude"),
+            scale: shader.get_shader_location("scale"),
+            speed: shader.get_shader_location("speed"),
+            time: shader.get_shader_location("time"),
+            shader,
+        })
+    }
+
+    // Dibuja triángulos de mundo sin desplazar, cada vértice con su dirección de desplazamiento en el
+    // lugar de la normal; requiere estar dentro del modo 3D. La luz ya viene calculada en el color
+    pub fn draw(&self, d3d: &mut impl RaylibDraw3D, displacement: &Displacement, vertices: &[(Vector3, Vector3, Color)]) {
+        let shader = *self.shader.as_ref();
+        let uniforms = [
+            (self.amplitude, displacement.amplitude),
+            (self.scale, displacement.scale),
+            (self.speed, displacement.speed),
+            (self.time, displacement.time),
+        ];
+        unsafe {
+            // Cambiar de shader vacía el lote, así los uniforms de este objeto no pisan los del anterior
+            ffi::BeginShaderMode(shader);
+            for (location, value) in uniforms {
+                ffi::SetShaderValue(
+                    shader,
+                    location,
+                    &value as *const f32 as *const c_void,
+                    ffi::ShaderUniformDataType::SHADER_UNIFORM_FLOAT as i32,
+                );
+            }
+        }
+        renderer::draw_colored_triangles(d3d, vertices);
+        unsafe {
+            ffi::EndShaderMode();
+        }
+    }
+}
+
+impl Scene {
+    pub fn update_displacements(&mut self, dt: f32) {
+        for displacement in self.objects.iter_mut().filter_map(|object| object.displacement.as_mut()) {
+            displacement.time += dt;
+        }
+    }
+}
+
+pub fn displacement_from_json(value: &Value) -> Option<Displacement> {
+    value.is_object().then(|| Displacement {
+        amplitude: value["amplitude"].as_f64().unwrap_or(0.1) as f32,
+        scale: value["scale"].as_f64().unwrap_or(2.0) as f32,
+        speed: value["speed"].as_f64().unwrap_or(1.0) as f32,
+        time: 0.0,
+    })
+}
+
+pub fn displacement_to_json(displacement: &Displacement) -> Value {
+    json!({
+        "amplitude": f32_to_json(displacement.amplitude),
+        "scale": f32_to_json(displacement.scale),
+        "speed": f32_to_json(displacement.speed),
+    })
+}
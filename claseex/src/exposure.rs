@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::lighting::{irradiance, LightingParams};
+use crate::picking;
+use crate::scene::Scene;
+
+// Grilla de rayos desde la cámara con la que se mide la luminancia de la vista
+const GRID_COLUMNS: usize = 8;
+const GRID_ROWS: usize = 6;
+// Luminancia media a la que se lleva la imagen
+const KEY_VALUE: f32 = 0.3;
+const MIN_EXPOSURE: f32 = 0.35;
+const MAX_EXPOSURE: f32 = 2.5;
+// Velocidades de adaptación: el ojo se acostumbra antes a la luz que a la oscuridad
+const DARKEN_SPEED: f32 = 3.0;
+const BRIGHTEN_SPEED: f32 = 1.0;
+
+// Adaptación del ojo: mide la luz de lo que se ve y ajusta la exposición de a poco
+pub struct AutoExposure {
+    pub exposure: f32,
+    pub luminance: f32, // Última luminancia media medida, antes de exponer
+}
+
+fn luminance(color: [f32; 3]) -> f32 {
+    0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]
+}
+
+impl AutoExposure {
+    pub fn new() -> Self {
+        AutoExposure {
+            exposure: 1.0,
+            luminance: KEY_VALUE,
+        }
+    }
+
+    // Media logarítmica de la luz reflejada por lo que tocan los rayos de la grilla.
+    // Sin saturar, así una zona muy iluminada pesa más que una apenas iluminada
+    pub fn measure(
+        &mut self,
+        rl: &RaylibHandle,
+        scene: &Scene,
+        transforms: &HashMap<u32, Matrix>,
+        lighting: &LightingParams,
+        camera: Camera3D,
+    ) {
+        let (width, height) = (rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        let mut log_sum = 0.0;
+        let mut samples = 0;
+        for row in 0..GRID_ROWS {
+            for column in 0..GRID_COLUMNS {
+                let screen = Vector2::new(
+                    (column as f32 + 0.5) / GRID_COLUMNS as f32 * width,
+                    (row as f32 + 0.5) / GRID_ROWS as f32 * height,
+                );
+                let ray = rl.get_screen_to_world_ray(screen, camera);
+                let Some(hit) = picking::pick(scene, transforms, ray) else {
+                    continue;
+                };
+                let Some(object) = scene.object(hit.id) else {
+                    continue;
+                };
+                // Se toma la superficie como si mirara a la cámara; alcanza para un promedio
+                let light = irradiance(
+                    hit.point,
+                    -ray.direction,
+                    lighting.lights,
+                    lighting.shadows,
+                    lighting.ambient_intensity,
+                    lighting.diffuse_intensity,
+                );
+                let albedo = scene.object_color(object);
+                let reflected = [
+                    light[0] * albedo.r as f32 / 255.0,
+                    light[1] * albedo.g as f32 / 255.0,
+                    light[2] * albedo.b as f32 / 255.0,
+                ];
+                log_sum += (luminance(reflected) + 1e-3).ln();
+                samples += 1;
+            }
+        }
+        // Si no se ve nada se mantiene la medición anterior
+        if samples > 0 {
+            self.luminance = (log_sum / samples as f32).exp();
+        }
+    }
+
+    // Acerca la exposición a la que deja la luminancia medida en el valor clave
+    pub fn update(&mut self, dt: f32) {
+        let target = (KEY_VALUE / self.luminance.max(1e-3)).clamp(MIN_EXPOSURE, MAX_EXPOSURE);
+        let speed = if target < self.exposure { DARKEN_SPEED } else { BRIGHTEN_SPEED };
+        self.exposure += (target - self.exposure) * (1.0 - (-speed * dt).exp());
+    }
+}
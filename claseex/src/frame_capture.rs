@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use raylib::prelude::*;
+
+use crate::frustum::Frustum;
+use crate::geometry::Vertex;
+use crate::lighting::LightingParams;
+use crate::photo::CAPTURE_DIR;
+use crate::reflections::{surface_reflectivity, Reflections};
+use crate::renderer::{self, ShapeLibrary};
+use crate::scene::{Scene, SceneObject};
+use crate::weather::Weather;
+
+// Pixeles por texel al guardar los mapas de sombra, que son de muy baja resolución
+const SHADOW_SCALE: i32 = 4;
+const LABEL_SIZE: i32 = 20;
+
+// Color de cada vértice en una pasada de depuración
+type VertexShade<'a> = &'a dyn Fn(&SceneObject, &Vertex) -> Color;
+
+// Lo que hace falta para volver a dibujar cada pasada del frame
+pub struct CaptureContext<'a> {
+    pub library: &'a ShapeLibrary,
+    pub scene: &'a Scene,
+    pub transforms: &'a HashMap<u32, Matrix>,
+    pub frustum: &'a Frustum,
+    pub lighting: &'a LightingParams<'a>,
+    pub weather: &'a Weather,
+    pub far: f32, // Distancia que corresponde al negro en la pasada de profundidad
+}
+
+// Carpeta de la captura, con la hora para no pisar las anteriores
+fn capture_dir() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    format!("{}/frame_{}", CAPTURE_DIR, seconds)
+}
+
+// Nombre de la pasada en la esquina superior, sobre una franja oscura para que se lea en cualquier fondo
+fn label(image: &mut Image, text: &str) {
+    let width = text.len() as i32 * LABEL_SIZE / 2 + 16;
+    image.draw_rectangle(0, 0, width, LABEL_SIZE + 8, Color::new(0, 0, 0, 170));
+    image.draw_text(text, 6, 4, LABEL_SIZE, Color::YELLOW);
+}
+
+fn export(image: &mut Image, dir: &str, file: &str, text: &str) -> String {
+    label(image, text);
+    let path = format!("{}/{}.png", dir, file);
+    image.export_image(&path);
+    path
+}
+
+// Guarda como PNG cada pasada intermedia del frame, al tamaño interno de la escena: profundidad, normales, reflectividad, un mapa
+// de sombra por luz puntual, los reflejos (si están activos) y la imagen final. Devuelve la carpeta
+// donde quedaron
+pub fn capture_frame(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    context: &CaptureContext,
+    camera: Camera3D,
+    background: Color,
+    size: (u32, u32),
+    mut reflections: Option<&mut Reflections>,
+) -> Result<String, String> {
+    let dir = capture_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir, e))?;
+    let mut target = rl.load_render_texture(thread, size.0, size.1).map_err(|e| e.to_string())?;
+
+    let forward = (camera.target - camera.position).normalized();
+    let depth_shade = |_: &SceneObject, vertex: &Vertex| {
+        // Profundidad lineal sobre el eje de la vista: blanco cerca, negro en el plano far
+        let depth = (vertex.position - camera.position).dot(forward) / context.far;
+        let value = ((1.0 - depth.clamp(0.0, 1.0)) * 255.0) as u8;
+        Color::new(value, value, value, 255)
+    };
+    let normal_shade = |_: &SceneObject, vertex: &Vertex| {
+        let channel = |c: f32| ((c * 0.5 + 0.5) * 255.0) as u8;
+        Color::new(channel(vertex.normal.x), channel(vertex.normal.y), channel(vertex.normal.z), 255)
+    };
+    // La misma reflectividad que usa el buffer de los reflejos, con el suelo mojado por la lluvia
+    let wetness = context.weather.wetness();
+    let reflectivity_shade = |object: &SceneObject, _: &Vertex| {
+        let value = (surface_reflectivity(object, wetness) * 255.0) as u8;
+        Color::new(value, value, value, 255)
+    };
+    let passes: [(&str, &str, Color, VertexShade); 3] = [
+        ("01_profundidad", "Profundidad", Color::BLACK, &depth_shade),
+        ("02_normales", "Normales (mundo)", Color::new(128, 128, 128, 255), &normal_shade),
+        ("03_reflectividad", "Reflectividad", Color::BLACK, &reflectivity_shade),
+    ];
+
+    let mut saved = Vec::new();
+    for (file, text, clear, shade) in passes {
+        {
+            let mut texture = rl.begin_texture_mode(thread, &mut target);
+            texture.clear_background(clear);
+            let mut d3d = texture.begin_mode3D(camera);
+            renderer::draw_scene_with(
+                &mut d3d,
+                context.library,
+                context.scene,
+                context.transforms,
+                context.frustum,
+                shade,
+            );
+        }
+        let mut image = target.load_image().map_err(|e| e.to_string())?;
+        image.flip_vertical();
+        saved.push(export(&mut image, &dir, file, text));
+    }
+
+    // Los mapas de sombra son de la CPU: se arman directo como imagen
+    for shadow in context.lighting.shadows {
+        let mut image = shadow.depth_image(SHADOW_SCALE);
+        let file = format!("04_sombra_luz{}", shadow.light);
+        let text = format!("Sombra cubo, luz {}", shadow.light);
+        saved.push(export(&mut image, &dir, &file, &text));
+    }
+
+    // Solo lo que aportan los reflejos, sobre negro
+    if let Some(reflections) = reflections.as_deref_mut() {
+        {
+            let mut texture = rl.begin_texture_mode(thread, &mut target);
+            texture.clear_background(Color::BLACK);
+            reflections.draw(&mut texture);
+        }
+        let mut image = target.load_image().map_err(|e| e.to_string())?;
+        image.flip_vertical();
+        saved.push(export(&mut image, &dir, "05_reflejos", "Reflejos"));
+    }
+
+    // Imagen final de la escena, sin la interfaz
+    {
+        let mut texture = rl.begin_texture_mode(thread, &mut target);
+        texture.clear_background(background);
+        {
+            let mut d3d = texture.begin_mode3D(camera);
+            renderer::draw_scene(
+                &mut d3d,
+                context.library,
+                context.scene,
+                context.transforms,
+                context.frustum,
+                context.lighting,
+                &HashSet::new(),
+            );
+        }
+        if let Some(reflections) = reflections {
+            reflections.draw(&mut texture);
+        }
+        let mut d3d = texture.begin_mode3D(camera);
+        context.weather.draw(&mut d3d);
+    }
+    let mut image = target.load_image().map_err(|e| e.to_string())?;
+    image.flip_vertical();
+    saved.push(export(&mut image, &dir, "06_final", "Final"));
+
+    // export_image no avisa si falla; se comprueba que los archivos existan
+    if let Some(missing) = saved.iter().find(|path| !std::path::Path::new(path).exists()) {
+        return Err(format!("no se pudo escribir {}", missing));
+    }
+    Ok(dir)
+}
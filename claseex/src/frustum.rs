@@ -0,0 +1,85 @@
+use raylib::prelude::*;
+
+// Plano con la normal apuntando hacia el interior del frustum
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub normal: Vector3,
+    pub distance: f32,
+}
+
+impl Plane {
+    // Construye el plano que pasa por tres puntos orientado hacia `inside`
+    fn from_points(a: Vector3, b: Vector3, c: Vector3, inside: Vector3) -> Self {
+        let mut normal = (b - a).cross(c - a).normalized();
+        if normal.dot(inside - a) < 0.0 {
+            normal = -normal;
+        }
+        Plane {
+            normal,
+            distance: -normal.dot(a),
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vector3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+// Volumen visible de una cámara entre los planos near y far: una pirámide truncada en perspectiva o
+// una caja en ortográfica
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub apex: Vector3,
+    pub near_corners: [Vector3; 4], // Orden: arriba-izq, arriba-der, abajo-der, abajo-izq
+    pub far_corners: [Vector3; 4],
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub fn from_camera(camera: &Camera3D, aspect: f32, near: f32, far: f32) -> Self {
+        let forward = (camera.target - camera.position).normalized();
+        let right = forward.cross(camera.up).normalized();
+        let up = right.cross(forward);
+        let tan_half = (camera.fovy.to_radians() * 0.5).tan();
+        // En ortográfica fovy es el alto de la vista, igual a cualquier distancia
+        let orthographic = camera.camera_type() == CameraProjection::CAMERA_ORTHOGRAPHIC;
+
+        let corners_at = |distance: f32| {
+            let center = camera.position + forward * distance;
+            let half_h = if orthographic { camera.fovy * 0.5 } else { tan_half * distance };
+            let half_w = half_h * aspect;
+            [
+                center + up * half_h - right * half_w,
+                center + up * half_h + right * half_w,
+                center - up * half_h + right * half_w,
+                center - up * half_h - right * half_w,
+            ]
+        };
+        let n = corners_at(near);
+        let f = corners_at(far);
+        let inside = camera.position + forward * ((near + far) * 0.5);
+
+        let planes = [
+            Plane::from_points(n[0], n[1], n[2], inside), // Near
+            Plane::from_points(f[0], f[1], f[2], inside), // Far
+            Plane::from_points(n[0], f[0], f[3], inside), // Izquierda
+            Plane::from_points(n[1], f[1], f[2], inside), // Derecha
+            Plane::from_points(n[0], f[0], f[1], inside), // Arriba
+            Plane::from_points(n[3], f[3], f[2], inside), // Abajo
+        ];
+
+        Frustum {
+            apex: camera.position,
+            near_corners: n,
+            far_corners: f,
+            planes,
+        }
+    }
+
+    // Prueba de culling conservadora con una esfera envolvente
+    pub fn contains_sphere(&self, center: Vector3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(center) >= -radius)
+    }
+}
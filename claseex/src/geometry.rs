@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+// Vértice con posición y normal; cada tres vértices forman un triángulo
+#[derive(Clone, Copy, Debug)]
+pub struct Vertex {
+    pub position: Vector3,
+    pub normal: Vector3,
+}
+
+// Geometría local (centrada en el origen y de tamaño unitario) de una forma
+#[derive(Clone, Debug, Default)]
+pub struct Geometry {
+    pub vertices: Vec<Vertex>,
+}
+
+impl Geometry {
+    pub fn triangle_count(&self) -> usize {
+        self.vertices.len() / 3
+    }
+
+    // Agrega un triángulo asegurando el orden antihorario visto desde la normal
+    fn push_triangle(&mut self, a: Vertex, b: Vertex, c: Vertex) {
+        let face_normal = (b.position - a.position).cross(c.position - a.position);
+        let reference = a.normal + b.normal + c.normal;
+        if face_normal.dot(reference) >= 0.0 {
+            self.vertices.extend_from_slice(&[a, b, c]);
+        } else {
+            self.vertices.extend_from_slice(&[a, c, b]);
+        }
+    }
+
+    // Cara plana subdividida en una cuadrícula para que la iluminación por vértice se note
+    fn push_grid(&mut self, center: Vector3, u: Vector3, v: Vector3, normal: Vector3, subdivisions: usize) {
+        let step = 1.0 / subdivisions as f32;
+        let point = |i: usize, j: usize| Vertex {
+            position: center + u * (i as f32 * step - 0.5) + v * (j as f32 * step - 0.5),
+            normal,
+        };
+        for i in 0..subdivisions {
+            for j in 0..subdivisions {
+                self.push_triangle(point(i, j), point(i + 1, j), point(i + 1, j + 1));
+                self.push_triangle(point(i, j), point(i + 1, j + 1), point(i, j + 1));
+            }
+        }
+    }
+}
+
+// Cubo de lado 1 con cada cara subdividida
+pub fn cube(subdivisions: usize) -> Geometry {
+    let mut geometry = Geometry::default();
+    let axes = [
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    ];
+    for (index, axis) in axes.iter().enumerate() {
+        let u = axes[(index + 1) % 3];
+        let v = axes[(index + 2) % 3];
+        for sign in [1.0f32, -1.0] {
+            let normal = *axis * sign;
+            geometry.push_grid(normal * 0.5, u, v, normal, subdivisions);
+        }
+    }
+    geometry
+}
+
+// Plano de lado 1 sobre XZ con la normal hacia +Y
+pub fn plane(subdivisions: usize) -> Geometry {
+    let mut geometry = Geometry::default();
+    geometry.push_grid(
+        Vector3::zero(),
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        subdivisions,
+    );
+    geometry
+}
+
+// Esfera de diámetro 1 generada por anillos y sectores
+pub fn sphere(rings: usize, slices: usize) -> Geometry {
+    let mut geometry = Geometry::default();
+    let point = |ring: usize, slice: usize| {
+        let theta = ring as f32 / rings as f32 * std::f32::consts::PI;
+        let phi = slice as f32 / slices as f32 * std::f32::consts::TAU;
+        let normal = Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+        Vertex {
+            position: normal * 0.5,
+            normal,
+        }
+    };
+    for ring in 0..rings {
+        for slice in 0..slices {
+            let (a, b) = (point(ring, slice), point(ring + 1, slice));
+            let (c, d) = (point(ring + 1, slice + 1), point(ring, slice + 1));
+            if ring != 0 {
+                geometry.push_triangle(a, b, d);
+            }
+            if ring != rings - 1 {
+                geometry.push_triangle(b, c, d);
+            }
+        }
+    }
+    geometry
+}
+
+// Cilindro de diámetro 1 y alto 1 sobre el eje Y, con tapas
+pub fn cylinder(slices: usize) -> Geometry {
+    let mut geometry = Geometry::default();
+    let rim = |slice: usize| {
+        let phi = slice as f32 / slices as f32 * std::f32::consts::TAU;
+        Vector3::new(phi.cos(), 0.0, phi.sin())
+    };
+    for slice in 0..slices {
+        let (n0, n1) = (rim(slice), rim(slice + 1));
+        let side = |normal: Vector3, y: f32| Vertex {
+            position: normal * 0.5 + Vector3::new(0.0, y, 0.0),
+            normal,
+        };
+        geometry.push_triangle(side(n0, -0.5), side(n1, -0.5), side(n1, 0.5));
+        geometry.push_triangle(side(n0, -0.5), side(n1, 0.5), side(n0, 0.5));
+        for sign in [1.0f32, -1.0] {
+            let normal = Vector3::new(0.0, sign, 0.0);
+            let cap = |point: Vector3| Vertex {
+                position: point * 0.5 + normal * 0.5,
+                normal,
+            };
+            geometry.push_triangle(cap(Vector3::zero()), cap(n0), cap(n1));
+        }
+    }
+    geometry
+}
+
+// Cuadrica de error (Garland-Heckbert): suma de los planos de los triángulos que tocan un vértice,
+// guardada como la mitad superior de la matriz simétrica 4x4
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: Vector3, point: Vector3) -> Self {
+        let (a, b, c) = (normal.x as f64, normal.y as f64, normal.z as f64);
+        let d = -(a * point.x as f64 + b * point.y as f64 + c * point.z as f64);
+        Quadric([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d])
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        Quadric(std::array::from_fn(|i| self.0[i] + other.0[i]))
+    }
+
+    // Suma de las distancias al cuadrado del punto a los planos acumulados
+    fn error(&self, p: Vector3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x as f64, p.y as f64, p.z as f64);
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// Normales de vértice que difieren más que esto marcan una arista viva (como las del cubo)
+const FEATURE_COS: f32 = 0.9;
+
+// Reduce la geometría hasta `target_triangles` colapsando aristas, siempre la de menor error.
+// Los bordes abiertos y las aristas vivas quedan fijos, así que puede quedar por encima del objetivo
+pub fn simplify(geometry: &Geometry, target_triangles: usize) -> Geometry {
+    // Se sueldan los vértices que comparten posición; cada uno guarda las normales distintas que tenía
+    let mut positions: Vec<Vector3> = Vec::new();
+    let mut normals: Vec<Vec<Vector3>> = Vec::new();
+    let mut lookup: HashMap<(i64, i64, i64), usize> = HashMap::new();
+    let mut triangles: Vec<[usize; 3]> = Vec::new();
+    for triangle in geometry.vertices.chunks(3) {
+        let mut corners = [0; 3];
+        for (corner, vertex) in corners.iter_mut().zip(triangle) {
+            let quantize = |v: f32| (v * 10000.0).round() as i64;
+            let key = (quantize(vertex.position.x), quantize(vertex.position.y), quantize(vertex.position.z));
+            let index = *lookup.entry(key).or_insert_with(|| {
+                positions.push(vertex.position);
+                normals.push(Vec::new());
+                positions.len() - 1
+            });
+            if !normals[index].iter().any(|n| n.dot(vertex.normal) > 0.999) {
+                normals[index].push(vertex.normal);
+            }
+            *corner = index;
+        }
+        triangles.push(corners);
+    }
+
+    let face_normal = |t: &[usize; 3], positions: &[Vector3]| {
+        (positions[t[1]] - positions[t[0]]).cross(positions[t[2]] - positions[t[0]])
+    };
+
+    // Fijos: vértices en aristas vivas y en bordes abiertos (aristas con un solo triángulo)
+    let mut locked: Vec<bool> = normals
+        .iter()
+        .map(|list| list.iter().any(|a| list.iter().any(|b| a.dot(*b) < FEATURE_COS)))
+        .collect();
+    let mut edge_count: HashMap<(usize, usize), usize> = HashMap::new();
+    for t in &triangles {
+        for i in 0..3 {
+            let (a, b) = (t[i], t[(i + 1) % 3]);
+            *edge_count.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    for ((a, b), count) in edge_count {
+        if count == 1 {
+            locked[a] = true;
+            locked[b] = true;
+        }
+    }
+
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    for (index, t) in triangles.iter().enumerate() {
+        let normal = face_normal(t, &positions);
+        if normal.length() < 1e-12 {
+            continue;
+        }
+        let plane = Quadric::from_plane(normal.normalized(), positions[t[0]]);
+        for &v in t {
+            quadrics[v] = quadrics[v].add(&plane);
+            vertex_triangles[v].push(index);
+        }
+    }
+
+    let mut alive = vec![true; triangles.len()];
+    let mut remaining = triangles.len();
+    while remaining > target_triangles {
+        // Colapso de `from` sobre `to` con el menor error que no dé vuelta ningún triángulo
+        let mut best: Option<(f64, usize, usize)> = None;
+        for (index, t) in triangles.iter().enumerate() {
+            if !alive[index] {
+                continue;
+            }
+            for i in 0..3 {
+                let (from, to) = (t[i], t[(i + 1) % 3]);
+                for (from, to) in [(from, to), (to, from)] {
+                    if locked[from] {
+                        continue;
+                    }
+                    let cost = quadrics[from].add(&quadrics[to]).error(positions[to]);
+                    if best.is_some_and(|(best_cost, _, _)| cost >= best_cost) {
+                        continue;
+                    }
+                    let keeps_orientation = vertex_triangles[from].iter().all(|&other| {
+                        let t = triangles[other];
+                        if !alive[other] || t.contains(&to) {
+                            return true;
+                        }
+                        let before = face_normal(&t, &positions);
+                        let moved = t.map(|v| if v == from { to } else { v });
+                        let after = face_normal(&moved, &positions);
+                        after.length() > 1e-9 && after.normalized().dot(before.normalized()) > 0.3
+                    });
+                    if keeps_orientation {
+                        best = Some((cost, from, to));
+                    }
+                }
+            }
+        }
+        let Some((_, from, to)) = best else {
+            break;
+        };
+
+        for other in std::mem::take(&mut vertex_triangles[from]) {
+            if !alive[other] {
+                continue;
+            }
+            if triangles[other].contains(&to) {
+                // Los triángulos que compartían la arista desaparecen
+                alive[other] = false;
+                remaining -= 1;
+            } else {
+                for v in triangles[other].iter_mut() {
+                    if *v == from {
+                        *v = to;
+                    }
+                }
+                vertex_triangles[to].push(other);
+            }
+        }
+        quadrics[to] = quadrics[to].add(&quadrics[from]);
+    }
+
+    // En cada esquina se usa la normal original del vértice que más se parece a la de la cara
+    let mut simplified = Geometry::default();
+    for (index, t) in triangles.iter().enumerate() {
+        if !alive[index] {
+            continue;
+        }
+        let face = face_normal(t, &positions).normalized();
+        for &v in t {
+            let normal = normals[v]
+                .iter()
+                .copied()
+                .max_by(|a, b| a.dot(face).total_cmp(&b.dot(face)))
+                .unwrap_or(face);
+            simplified.vertices.push(Vertex {
+                position: positions[v],
+                normal,
+            });
+        }
+    }
+    simplified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Aristas (con las posiciones soldadas) que no comparten exactamente dos triángulos
+    fn open_edges(geometry: &Geometry) -> usize {
+        let key = |v: Vector3| ((v.x * 10000.0).round() as i64, (v.y * 10000.0).round() as i64, (v.z * 10000.0).round() as i64);
+        let mut edges: HashMap<_, usize> = HashMap::new();
+        for triangle in geometry.vertices.chunks(3) {
+            for i in 0..3 {
+                let (a, b) = (key(triangle[i].position), key(triangle[(i + 1) % 3].position));
+                *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+            }
+        }
+        edges.values().filter(|count| **count != 2).count()
+    }
+
+    #[test]
+    fn simplify_keeps_closed_meshes_closed() {
+        for (name, geometry) in [("cubo", cube(4)), ("esfera", sphere(12, 18)), ("cilindro", cylinder(24))] {
+            assert_eq!(open_edges(&geometry), 0, "{} abierto antes de simplificar", name);
+            let simplified = simplify(&geometry, geometry.triangle_count() / 4);
+            assert!(simplified.triangle_count() < geometry.triangle_count(), "{} no se redujo", name);
+            assert_eq!(open_edges(&simplified), 0, "{} quedó abierto", name);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use raylib::ffi;
+use raylib::prelude::*;
+
+use crate::picking::{self, PickHit};
+use crate::renderer::{self, ShapeLibrary};
+use crate::scene::Scene;
+
+// Lo que hace falta para dibujar los ids de la escena
+pub struct IdContext<'a> {
+    pub library: &'a ShapeLibrary,
+    pub scene: &'a Scene,
+    pub transforms: &'a HashMap<u32, Matrix>,
+}
+
+// Id del objeto codificado en el color (el 0 queda para el fondo)
+fn id_color(id: u32) -> Color {
+    let value = id + 1;
+    Color::new((value >> 16) as u8, (value >> 8) as u8, value as u8, 255)
+}
+
+fn color_id(color: Color) -> Option<u32> {
+    let value = (color.r as u32) << 16 | (color.g as u32) << 8 | color.b as u32;
+    (color.a == 255 && value > 0).then(|| value - 1)
+}
+
+// Proyección de la cámara recortada al pixel del cursor: ese pixel ocupa todo el buffer de 1x1
+fn pixel_projection(camera: &Camera3D, screen: Vector2, pixel: Vector2) -> Matrix {
+    let aspect = screen.x / screen.y;
+    let (near, far) = (ffi::RL_CULL_DISTANCE_NEAR as f32, ffi::RL_CULL_DISTANCE_FAR as f32);
+    let projection = if camera.camera_type() == CameraProjection::CAMERA_ORTHOGRAPHIC {
+        let top = camera.fovy * 0.5;
+        Matrix::ortho(-top * aspect, top * aspect, -top, top, near, far)
+    } else {
+        Matrix::perspective(camera.fovy.to_radians(), aspect, near, far)
+    };
+    // Centro del pixel en coordenadas normalizadas (y hacia arriba)
+    let center_x = (pixel.x.floor() + 0.5) / screen.x * 2.0 - 1.0;
+    let center_y = 1.0 - (pixel.y.floor() + 0.5) / screen.y * 2.0;
+    let mut zoom = Matrix::identity();
+    zoom.m0 = screen.x;
+    zoom.m5 = screen.y;
+    zoom.m12 = -center_x * screen.x;
+    zoom.m13 = -center_y * screen.y;
+    projection * zoom
+}
+
+// Selección en la GPU: cada objeto se dibuja con su id como color y se lee el pixel del cursor
+#[derive(Default)]
+pub struct IdBuffer {
+    target: Option<RenderTexture2D>,
+}
+
+impl IdBuffer {
+    pub fn pick(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        context: &IdContext,
+        camera: Camera3D,
+        mouse: Vector2,
+    ) -> Result<Option<PickHit>, String> {
+        let IdContext {
+            library,
+            scene,
+            transforms,
+        } = *context;
+        let screen = Vector2::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        if mouse.x < 0.0 || mouse.y < 0.0 || mouse.x >= screen.x || mouse.y >= screen.y {
+            return Ok(None);
+        }
+        let mut target = match self.target.take() {
+            Some(target) => target,
+            None => rl.load_render_texture(thread, 1, 1).map_err(|e| e.to_string())?,
+        };
+        {
+            let mut texture_mode = rl.begin_texture_mode(thread, &mut target);
+            texture_mode.clear_background(Color::BLANK);
+            let mut d3d = texture_mode.begin_mode3D(camera);
+            unsafe {
+                ffi::rlSetMatrixProjection(pixel_projection(&camera, screen, mouse).into());
+            }
+            for object in &scene.objects {
+                if !scene.layer_visible(&object.layer) {
+                    continue;
+                }
+                let Some(model) = transforms.get(&object.id) else {
+                    continue;
+                };
+                // Color plano, sin iluminación ni niebla, para que el id llegue intacto
+                let color = id_color(object.id);
+                let flat: Vec<_> = renderer::world_vertices(renderer::object_geometry(library, object, 0.0), *model)
+                    .into_iter()
+                    .map(|vertex| (vertex.position, vertex.normal, color))
+                    .collect();
+                renderer::draw_colored_triangles(&mut d3d, &flat);
+            }
+        }
+        let image = target.load_image();
+        self.target = Some(target);
+
+        let Some(id) = color_id(image.map_err(|e| e.to_string())?.get_color(0, 0)) else {
+            return Ok(None);
+        };
+        let Some(object) = scene.object(id) else {
+            return Ok(None);
+        };
+        // El buffer dice qué objeto es; el punto exacto sale del rayo contra ese objeto
+        let ray = rl.get_screen_to_world_ray(mouse, camera);
+        Ok(picking::hit_on_object(transforms, object, ray))
+    }
+}
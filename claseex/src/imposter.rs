@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+
+use raylib::prelude::*;
+
+use crate::frustum::Frustum;
+use crate::lighting::LightingParams;
+use crate::renderer::{self, ShapeLibrary};
+use crate::scene::Scene;
+
+// A partir de esta distancia un grupo de objetos se dibuja como imposter
+pub const IMPOSTER_DISTANCE: f32 = 14.0;
+// Ángulo de vista (en grados) que se tolera antes de volver a hornear la imagen
+const REBAKE_ANGLE: f32 = 8.0;
+// Cambio relativo de la iluminación que obliga a volver a hornear
+const REBAKE_LIGHTING: f32 = 0.03;
+const TEXTURE_SIZE: u32 = 128;
+// Horneados por frame como máximo, para repartir el costo cuando muchos cambian a la vez
+const MAX_BAKES_PER_FRAME: usize = 4;
+
+// Imagen de un subárbol vista desde una dirección, lista para dibujarse como billboard
+struct Imposter {
+    texture: RenderTexture2D,
+    direction: Vector3, // Del centro hacia la cámara al momento de hornear
+    center: Vector3,
+    radius: f32,
+    lighting_key: f32,
+}
+
+// Lo que hace falta para dibujar la escena al hornear
+pub struct BakeContext<'a> {
+    pub library: &'a ShapeLibrary,
+    pub scene: &'a Scene,
+    pub transforms: &'a HashMap<u32, Matrix>,
+    pub lighting: &'a LightingParams<'a>,
+}
+
+// Cache de imposters por raíz de subárbol
+#[derive(Default)]
+pub struct ImposterCache {
+    imposters: HashMap<u32, Imposter>,
+    active: Vec<u32>, // Raíces que este frame se dibujan como imposter
+    baked_this_frame: usize,
+}
+
+// Esfera que envuelve todo el subárbol visible de una raíz
+fn subtree_bounds(scene: &Scene, transforms: &HashMap<u32, Matrix>, root: u32) -> Option<(Vector3, f32)> {
+    let center = Vector3::zero().transform_with(*transforms.get(&root)?);
+    let radius = scene
+        .subtree(root)
+        .iter()
+        .filter_map(|id| scene.object(*id))
+        .filter(|object| scene.layer_visible(&object.layer))
+        .map(|object| {
+            let position = Vector3::zero().transform_with(transforms[&object.id]);
+            position.distance_to(center) + object.bounding_radius()
+        })
+        .fold(0.0, f32::max);
+    (radius > 0.0).then_some((center, radius))
+}
+
+// Resumen de la iluminación del frame: si cambia mucho la imagen horneada ya no coincide
+fn lighting_key(lighting: &LightingParams) -> f32 {
+    lighting.ambient_intensity
+        + lighting.diffuse_intensity * lighting.lights.iter().map(|light| light.intensity).sum::<f32>()
+}
+
+impl ImposterCache {
+    // Elige qué grupos lejanos van como imposter y hornea los que quedaron viejos.
+    // Devuelve los ids de todos los objetos que no hay que dibujar con su geometría
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        context: &BakeContext,
+        camera: Camera3D,
+    ) -> HashSet<u32> {
+        let BakeContext { scene, transforms, .. } = *context;
+        self.active.clear();
+        self.baked_this_frame = 0;
+        let key = lighting_key(context.lighting);
+        let mut hidden = HashSet::new();
+
+        // Solo los grupos (raíces con hijos) valen la pena: un objeto suelto ya es un solo dibujo
+        let roots: Vec<u32> = scene
+            .objects
+            .iter()
+            .filter(|object| object.parent.is_none())
+            .filter(|object| scene.objects.iter().any(|child| child.parent == Some(object.id)))
+            .map(|object| object.id)
+            .collect();
+
+        for root in roots {
+            let Some((center, radius)) = subtree_bounds(scene, transforms, root) else {
+                continue;
+            };
+            let to_camera = camera.position - center;
+            if to_camera.length() - radius < IMPOSTER_DISTANCE {
+                continue;
+            }
+            let direction = to_camera.normalized();
+
+            let stale = self.imposters.get(&root).is_none_or(|imposter| {
+                let angle = imposter.direction.dot(direction).clamp(-1.0, 1.0).acos().to_degrees();
+                angle > REBAKE_ANGLE
+                    || imposter.center.distance_to(center) > radius * 0.02
+                    || (imposter.radius - radius).abs() > radius * 0.02
+                    || (imposter.lighting_key - key).abs() > key.abs() * REBAKE_LIGHTING
+            });
+            if stale {
+                // Sin presupuesto este frame: se dibuja con su geometría (o la imagen vieja si no hay)
+                if self.baked_this_frame >= MAX_BAKES_PER_FRAME {
+                    if !self.imposters.contains_key(&root) {
+                        continue;
+                    }
+                } else {
+                    match self.bake(rl, thread, context, root, (center, radius), direction) {
+                        Ok(imposter) => {
+                            self.imposters.insert(root, imposter);
+                            self.baked_this_frame += 1;
+                        }
+                        Err(error) => {
+                            eprintln!("No se pudo hornear el imposter {}: {}", root, error);
+                            continue;
+                        }
+                    }
+                }
+            }
+            self.active.push(root);
+            hidden.extend(scene.subtree(root));
+        }
+
+        // Las imágenes de grupos que ya no existen se liberan
+        self.imposters.retain(|root, _| scene.object(*root).is_some());
+        hidden
+    }
+
+    // Dibuja el subárbol con una cámara ortográfica que lo encuadra justo desde la dirección pedida
+    fn bake(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        context: &BakeContext,
+        root: u32,
+        (center, radius): (Vector3, f32),
+        direction: Vector3,
+    ) -> Result<Imposter, String> {
+        // Se reutiliza la textura anterior del mismo grupo si la hay
+        let mut texture = match self.imposters.remove(&root) {
+            Some(old) => old.texture,
+            None => rl
+                .load_render_texture(thread, TEXTURE_SIZE, TEXTURE_SIZE)
+                .map_err(|e| e.to_string())?,
+        };
+        let up = if direction.y.abs() > 0.99 { Vector3::new(0.0, 0.0, 1.0) } else { Vector3::new(0.0, 1.0, 0.0) };
+        let BakeContext {
+            library,
+            scene,
+            transforms,
+            lighting,
+        } = *context;
+        let bake_camera = Camera3D::orthographic(center + direction * radius * 3.0, center, up, radius * 2.0);
+        {
+            let mut target = rl.begin_texture_mode(thread, &mut texture);
+            target.clear_background(Color::BLANK);
+            let mut d3d = target.begin_mode3D(bake_camera);
+            for id in scene.subtree(root) {
+                if let Some(object) = scene.object(id)
+                    && scene.layer_visible(&object.layer)
+                {
+                    renderer::draw_object(
+                        &mut d3d,
+                        renderer::object_geometry(library, object, 0.0),
+                        object,
+                        transforms[&id],
+                        scene.object_color(object),
+                        lighting,
+                    );
+                }
+            }
+        }
+        Ok(Imposter {
+            texture,
+            direction,
+            center,
+            radius,
+            lighting_key: lighting_key(lighting),
+        })
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    // Billboards de los grupos lejanos, orientados hacia la cámara
+    pub fn draw(&self, d3d: &mut impl RaylibDraw3D, camera: Camera3D, frustum: &Frustum) {
+        let forward = (camera.target - camera.position).normalized();
+        let right = forward.cross(camera.up).normalized();
+        let up = right.cross(forward);
+        for root in &self.active {
+            let Some(imposter) = self.imposters.get(root) else {
+                continue;
+            };
+            if !frustum.contains_sphere(imposter.center, imposter.radius) {
+                continue;
+            }
+            // La textura de render queda invertida en Y
+            let source = Rectangle::new(0.0, 0.0, TEXTURE_SIZE as f32, -(TEXTURE_SIZE as f32));
+            let size = Vector2::new(imposter.radius * 2.0, imposter.radius * 2.0);
+            d3d.draw_billboard_pro(
+                camera,
+                imposter.texture.texture,
+                source,
+                imposter.center,
+                up,
+                size,
+                size * 0.5,
+                0.0,
+                Color::WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::picking;
+use crate::scene::Scene;
+
+pub const LABEL_FONT_SIZE: i32 = 16;
+// Distancias entre las que una etiqueta se desvanece por lejanía
+const FADE_START: f32 = 10.0;
+const FADE_END: f32 = 22.0;
+// Opacidad máxima de una etiqueta tapada por otro objeto
+const OCCLUDED_ALPHA: f32 = 0.15;
+// Velocidad de la transición de opacidad, en unidades de alpha por segundo
+const FADE_SPEED: f32 = 4.0;
+const PADDING: f32 = 4.0;
+
+// Origen de una etiqueta: un objeto con texto propio o una nota de la escena
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum LabelSource {
+    Object(u32),
+    Annotation(usize),
+}
+
+// Etiqueta lista para dibujar en coordenadas de pantalla
+#[derive(Clone, Debug)]
+pub struct ScreenLabel {
+    pub text: String,
+    pub bounds: Rectangle,
+    pub alpha: f32,
+}
+
+// Conserva la opacidad de cada etiqueta para que aparezcan y desaparezcan suavemente
+#[derive(Default)]
+pub struct LabelFader {
+    alphas: HashMap<LabelSource, f32>,
+}
+
+impl LabelFader {
+    pub fn update(
+        &mut self,
+        rl: &RaylibHandle,
+        scene: &Scene,
+        transforms: &HashMap<u32, Matrix>,
+        camera: Camera3D,
+        dt: f32,
+    ) -> Vec<ScreenLabel> {
+        // Punto de anclaje de cada etiqueta: encima del objeto o en la posición de la nota
+        let mut anchors: Vec<(LabelSource, &str, Vector3)> = Vec::new();
+        for object in &scene.objects {
+            if let (Some(text), Some(model)) = (&object.label, transforms.get(&object.id))
+                && scene.layer_visible(&object.layer)
+            {
+                let center = Vector3::zero().transform_with(*model);
+                let top = center + Vector3::new(0.0, object.bounding_radius(), 0.0);
+                anchors.push((LabelSource::Object(object.id), text, top));
+            }
+        }
+        for (index, annotation) in scene.annotations.iter().enumerate() {
+            anchors.push((LabelSource::Annotation(index), &annotation.text, annotation.position));
+        }
+
+        // Las más cercanas se ubican primero y tienen prioridad cuando se enciman
+        let forward = (camera.target - camera.position).normalized();
+        anchors.retain(|(_, _, anchor)| (*anchor - camera.position).dot(forward) > 0.0);
+        anchors.sort_by(|a, b| {
+            let da = a.2.distance_to(camera.position);
+            let db = b.2.distance_to(camera.position);
+            da.total_cmp(&db)
+        });
+
+        // Las etiquetas que dejan de estar a la vista empiezan desde cero al volver
+        self.alphas.retain(|source, _| anchors.iter().any(|(other, _, _)| other == source));
+
+        let mut placed: Vec<Rectangle> = Vec::new();
+        let mut labels = Vec::new();
+        for (source, text, anchor) in anchors {
+            let distance = anchor.distance_to(camera.position);
+            let mut target = 1.0 - ((distance - FADE_START) / (FADE_END - FADE_START)).clamp(0.0, 1.0);
+
+            // Oclusión: un rayo desde la cámara que choca antes con otro objeto
+            let ray = Ray::new(camera.position, (anchor - camera.position).normalized());
+            if let Some(hit) = picking::pick(scene, transforms, ray)
+                && hit.distance < distance - 0.05
+                && !matches!(source, LabelSource::Object(id) if scene.subtree(id).contains(&hit.id))
+            {
+                target = target.min(OCCLUDED_ALPHA);
+            }
+
+            let screen = rl.get_world_to_screen(anchor, camera);
+            let width = rl.measure_text(text, LABEL_FONT_SIZE) as f32 + PADDING * 2.0;
+            let height = LABEL_FONT_SIZE as f32 + PADDING * 2.0;
+            let bounds = Rectangle::new(screen.x - width * 0.5, screen.y - height - 6.0, width, height);
+
+            // Una etiqueta encimada sobre otra más cercana se oculta
+            if placed.iter().any(|other| other.check_collision_recs(&bounds)) {
+                target = 0.0;
+            } else if target > OCCLUDED_ALPHA {
+                placed.push(bounds);
+            }
+
+            let alpha = self.alphas.entry(source).or_insert(0.0);
+            let step = FADE_SPEED * dt;
+            *alpha += (target - *alpha).clamp(-step, step);
+            if *alpha > 0.01 {
+                labels.push(ScreenLabel {
+                    text: text.to_string(),
+                    bounds,
+                    alpha: *alpha,
+                });
+            }
+        }
+        labels
+    }
+}
@@ -0,0 +1,277 @@
+use serde_json::Value;
+
+use crate::scene::Scene;
+
+pub const LESSON_DIR: &str = "lessons";
+// Primer tramo de los nombres de variable que entienden las condiciones
+const VARIABLES: [&str; 10] = [
+    "ambient_intensity",
+    "diffuse_intensity",
+    "weather",
+    "projection",
+    "playing",
+    "time_scale",
+    "lights",
+    "selected",
+    "light",
+    "layer",
+];
+
+// Valor de una variable o del lado derecho de una condición
+#[derive(Clone, Debug, PartialEq)]
+pub enum LessonValue {
+    Number(f32),
+    Bool(bool),
+    Text(String),
+}
+
+impl LessonValue {
+    fn parse(word: &str) -> LessonValue {
+        match word {
+            "true" => LessonValue::Bool(true),
+            "false" => LessonValue::Bool(false),
+            _ => word
+                .parse::<f32>()
+                .map_or_else(|_| LessonValue::Text(word.to_string()), LessonValue::Number),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+// Condición de un paso: `<variable> <comparación> <valor>`, por ejemplo "diffuse_intensity >= 1.0",
+// "projection == orthographic", "light.0.shadows == true" o "layer.debug == false"
+#[derive(Clone, Debug)]
+pub struct Condition {
+    variable: String,
+    comparison: Comparison,
+    value: LessonValue,
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let [variable, comparison, value] = words.as_slice() else {
+            return Err(format!("condicion invalida '{}': se espera <variable> <comparacion> <valor>", text));
+        };
+        let root = variable.split('.').next().unwrap_or_default();
+        if !VARIABLES.contains(&root) {
+            return Err(format!("variable desconocida '{}'", variable));
+        }
+        let comparison = match *comparison {
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterEqual,
+            other => return Err(format!("comparacion desconocida '{}'", other)),
+        };
+        Ok(Condition {
+            variable: variable.to_string(),
+            comparison,
+            value: LessonValue::parse(value),
+        })
+    }
+
+    // Una variable que no existe (una luz o capa que no está) no cumple ninguna condición
+    pub fn holds(&self, state: &LessonState) -> bool {
+        let Some(current) = state.value(&self.variable) else {
+            return false;
+        };
+        match (&current, &self.value) {
+            (LessonValue::Number(current), LessonValue::Number(expected)) => {
+                // Tolerancia para los valores que salen de sliders y multiplicaciones
+                let equal = (current - expected).abs() < 1e-3;
+                match self.comparison {
+                    Comparison::Equal => equal,
+                    Comparison::NotEqual => !equal,
+                    Comparison::Less => current < expected && !equal,
+                    Comparison::LessEqual => current < expected || equal,
+                    Comparison::Greater => current > expected && !equal,
+                    Comparison::GreaterEqual => current > expected || equal,
+                }
+            }
+            _ => match self.comparison {
+                Comparison::Equal => current == self.value,
+                Comparison::NotEqual => current != self.value,
+                _ => false,
+            },
+        }
+    }
+}
+
+// Lo que pueden mirar las condiciones: la escena y el estado del editor
+pub struct LessonState<'a> {
+    pub scene: &'a Scene,
+    pub orthographic: bool,
+    pub playing: bool,
+    pub selected: Option<u32>,
+    pub time_scale: f32,
+}
+
+impl LessonState<'_> {
+    fn value(&self, variable: &str) -> Option<LessonValue> {
+        let parts: Vec<&str> = variable.split('.').collect();
+        let value = match parts.as_slice() {
+            ["ambient_intensity"] => LessonValue::Number(self.scene.ambient_intensity),
+            ["diffuse_intensity"] => LessonValue::Number(self.scene.diffuse_intensity),
+            ["weather"] => LessonValue::Text(self.scene.weather.name().to_string()),
+            ["projection"] => {
+                LessonValue::Text(if self.orthographic { "orthographic" } else { "perspective" }.to_string())
+            }
+            ["playing"] => LessonValue::Bool(self.playing),
+            ["time_scale"] => LessonValue::Number(self.time_scale),
+            ["lights"] => LessonValue::Number(self.scene.lights.len() as f32),
+            ["selected"] => LessonValue::Text(
+                self.selected
+                    .and_then(|id| self.scene.object(id))
+                    .map(|object| object.name.clone())
+                    .unwrap_or_default(),
+            ),
+            ["light", index, property] => {
+                let light = self.scene.lights.get(index.parse::<usize>().ok()?)?;
+                match *property {
+                    "intensity" => LessonValue::Number(light.intensity),
+                    "range" => LessonValue::Number(light.range),
+                    "shadows" => LessonValue::Bool(light.cast_shadows),
+                    _ => return None,
+                }
+            }
+            ["layer", name] => LessonValue::Bool(self.scene.layer(name)?.visible),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+// Paso de una lección: la consigna que se muestra y la condición que lo da por cumplido
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub text: String,
+    pub hint: Option<String>,
+    pub condition: Condition,
+    pub run: Vec<String>, // Comandos de consola que se ejecutan al empezar el paso
+}
+
+// Tutorial guiado sobre el diorama, guardado en `lessons/<nombre>.json`
+#[derive(Clone, Debug)]
+pub struct Lesson {
+    pub title: String,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl Lesson {
+    pub fn path(name: &str) -> String {
+        format!("{}/{}.json", LESSON_DIR, name)
+    }
+
+    // Nombres de las lecciones disponibles, en orden alfabético
+    pub fn available() -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(LESSON_DIR)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn load(name: &str) -> Result<Lesson, String> {
+        let path = Lesson::path(name);
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let mut checkpoints = Vec::new();
+        for (index, checkpoint) in value["checkpoints"].as_array().into_iter().flatten().enumerate() {
+            let condition = Condition::parse(checkpoint["when"].as_str().unwrap_or_default())
+                .map_err(|e| format!("{}: paso {}: {}", path, index + 1, e))?;
+            checkpoints.push(Checkpoint {
+                text: checkpoint["text"].as_str().unwrap_or_default().to_string(),
+                hint: checkpoint["hint"].as_str().map(str::to_string),
+                condition,
+                run: checkpoint["run"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|command| command.as_str().map(str::to_string))
+                    .collect(),
+            });
+        }
+        if checkpoints.is_empty() {
+            return Err(format!("{}: la leccion no tiene pasos", path));
+        }
+        Ok(Lesson {
+            title: value["title"].as_str().unwrap_or(name).to_string(),
+            checkpoints,
+        })
+    }
+}
+
+// Recorre los pasos de la lección activa a medida que se cumplen sus condiciones
+#[derive(Default)]
+pub struct LessonPlayer {
+    lesson: Option<Lesson>,
+    step: usize,
+    pending: Vec<String>, // Comandos del paso que empieza, para ejecutar en el próximo frame
+}
+
+impl LessonPlayer {
+    pub fn start(&mut self, lesson: Lesson) {
+        self.pending = lesson.checkpoints[0].run.clone();
+        self.lesson = Some(lesson);
+        self.step = 0;
+    }
+
+    pub fn stop(&mut self) {
+        self.lesson = None;
+        self.pending.clear();
+    }
+
+    pub fn lesson(&self) -> Option<&Lesson> {
+        self.lesson.as_ref()
+    }
+
+    // Índice y paso actual; None sin lección o con todos los pasos cumplidos
+    pub fn current(&self) -> Option<(usize, &Checkpoint)> {
+        let checkpoint = self.lesson.as_ref()?.checkpoints.get(self.step)?;
+        Some((self.step, checkpoint))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.lesson.is_some() && self.current().is_none()
+    }
+
+    // Da el paso actual por cumplido y prepara los comandos del siguiente
+    pub fn skip(&mut self) {
+        let Some(lesson) = &self.lesson else {
+            return;
+        };
+        if self.step < lesson.checkpoints.len() {
+            self.step += 1;
+        }
+        if let Some(next) = lesson.checkpoints.get(self.step) {
+            self.pending.extend(next.run.iter().cloned());
+        }
+    }
+
+    // Revisa la condición del paso actual y devuelve los comandos a ejecutar en este frame. Con
+    // comandos pendientes la condición se mira recién en el frame siguiente, ya con sus efectos
+    pub fn update(&mut self, state: &LessonState) -> Vec<String> {
+        if self.pending.is_empty()
+            && self.current().is_some_and(|(_, checkpoint)| checkpoint.condition.holds(state)) {
+            self.skip();
+        }
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::lighting::Light;
+use crate::scene::{Scene, SceneObject};
+
+// Juegos de luces listos para usar: reemplazan las luces de la escena y el ambiente de una vez
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightRig {
+    ThreePoint, // Principal, relleno y contraluz alrededor del objeto elegido
+    Studio,
+    Sunset,
+    Night,
+}
+
+// Luces y luz ambiente que arma un juego
+pub struct RigSetup {
+    pub lights: Vec<Light>,
+    pub ambient_intensity: f32,
+}
+
+impl LightRig {
+    pub const ALL: [LightRig; 4] = [LightRig::ThreePoint, LightRig::Studio, LightRig::Sunset, LightRig::Night];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LightRig::ThreePoint => "tres_puntos",
+            LightRig::Studio => "estudio",
+            LightRig::Sunset => "atardecer",
+            LightRig::Night => "noche",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LightRig> {
+        LightRig::ALL.into_iter().find(|rig| rig.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LightRig::ThreePoint => "Tres puntos",
+            LightRig::Studio => "Estudio",
+            LightRig::Sunset => "Atardecer",
+            LightRig::Night => "Noche",
+        }
+    }
+
+    // Ubica las luces alrededor de una esfera (centro y radio) vista desde `eye`: la principal
+    // queda de frente y a un costado, el relleno del otro lado y el contraluz detrás
+    pub fn build(self, center: Vector3, radius: f32, eye: Vector3) -> RigSetup {
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        // Dirección de la vista en el plano horizontal
+        let mut forward = center - eye;
+        forward.y = 0.0;
+        let forward = if forward.length() > 1e-3 { forward.normalized() } else { Vector3::new(0.0, 0.0, -1.0) };
+        let right = forward.cross(up).normalized();
+        let distance = (radius * 3.0).max(2.5);
+        // Posición relativa al centro: atrás/adelante, a la derecha y arriba, en múltiplos de la distancia
+        let around = |back: f32, side: f32, height: f32| {
+            center + (forward * -back + right * side + up * height) * distance
+        };
+
+        match self {
+            LightRig::ThreePoint => {
+                let key = Light::spot(
+                    around(0.8, 0.7, 0.8),
+                    center,
+                    Color::new(255, 244, 225, 255),
+                    1.0,
+                    distance * 3.0,
+                    35.0,
+                );
+                let fill = Light {
+                    cast_shadows: false,
+                    ..Light::point(around(1.0, -0.9, 0.3), Color::new(200, 215, 255, 255), 0.4, distance * 2.5)
+                };
+                let rim = Light {
+                    cast_shadows: false,
+                    ..Light::spot(around(-1.0, 0.0, 0.9), center, Color::WHITE, 0.8, distance * 3.0, 30.0)
+                };
+                RigSetup {
+                    lights: vec![key, fill, rim],
+                    ambient_intensity: 0.15,
+                }
+            }
+            // Luz pareja y neutra, con sombras suaves de arriba
+            LightRig::Studio => RigSetup {
+                lights: vec![
+                    Light::spot(around(0.9, 0.6, 1.0), center, Color::WHITE, 0.9, distance * 3.0, 45.0),
+                    Light {
+                        cast_shadows: false,
+                        ..Light::point(around(0.9, -0.8, 0.5), Color::new(240, 240, 250, 255), 0.5, distance * 3.0)
+                    },
+                    Light::point(center + up * distance * 1.2, Color::WHITE, 0.4, distance * 2.5),
+                ],
+                ambient_intensity: 0.35,
+            },
+            // Sol bajo y anaranjado desde el fondo, con el cielo violeta como relleno
+            LightRig::Sunset => RigSetup {
+                lights: vec![
+                    Light::spot(
+                        around(-1.2, 0.8, 0.35),
+                        center,
+                        Color::new(255, 150, 80, 255),
+                        1.1,
+                        distance * 4.0,
+                        50.0,
+                    ),
+                    Light {
+                        cast_shadows: false,
+                        ..Light::point(around(0.5, -0.5, 1.2), Color::new(130, 115, 200, 255), 0.35, distance * 3.0)
+                    },
+                ],
+                ambient_intensity: 0.2,
+            },
+            // Luna fría desde arriba y una luz cálida y corta cerca del centro, como un farol
+            LightRig::Night => RigSetup {
+                lights: vec![
+                    Light::spot(
+                        around(0.3, -1.0, 1.4),
+                        center,
+                        Color::new(150, 170, 255, 255),
+                        0.5,
+                        distance * 4.0,
+                        50.0,
+                    ),
+                    Light::point(
+                        center + up * (radius * 0.3).max(0.5),
+                        Color::new(255, 170, 90, 255),
+                        0.6,
+                        (radius * 1.2).max(3.0),
+                    ),
+                ],
+                ambient_intensity: 0.08,
+            },
+        }
+    }
+}
+
+// Esfera que envuelve a los objetos dados
+fn bounds<'a>(
+    objects: impl Iterator<Item = &'a SceneObject>,
+    transforms: &HashMap<u32, Matrix>,
+) -> Option<(Vector3, f32)> {
+    let spheres: Vec<(Vector3, f32)> = objects
+        .filter_map(|object| {
+            let model = transforms.get(&object.id)?;
+            Some((Vector3::zero().transform_with(*model), object.bounding_radius()))
+        })
+        .collect();
+    if spheres.is_empty() {
+        return None;
+    }
+    let center = spheres.iter().fold(Vector3::zero(), |sum, (position, _)| sum + *position) / spheres.len() as f32;
+    let radius = spheres
+        .iter()
+        .map(|(position, radius)| position.distance_to(center) + radius)
+        .fold(0.5, f32::max);
+    Some((center, radius))
+}
+
+impl Scene {
+    // Reemplaza las luces y el ambiente por los del juego. Los tres puntos iluminan el objeto `focus`
+    // con sus hijos (sin objeto, el punto que mira la cámara); los demás juegos, toda la escena visible
+    pub fn apply_light_rig(&mut self, rig: LightRig, focus: Option<u32>, camera: &Camera3D) {
+        let transforms = self.world_transforms();
+        let target = match (rig, focus) {
+            (LightRig::ThreePoint, Some(id)) => {
+                let subtree = self.subtree(id);
+                bounds(self.objects.iter().filter(|object| subtree.contains(&object.id)), &transforms)
+            }
+            (LightRig::ThreePoint, None) => None,
+            _ => bounds(
+                self.objects
+                    .iter()
+                    .filter(|object| !object.pooled && self.layer_visible(&object.layer)),
+                &transforms,
+            ),
+        };
+        let (center, radius) = target.unwrap_or((camera.target, 1.0));
+        let setup = rig.build(center, radius, camera.position);
+        self.lights = setup.lights;
+        self.ambient_intensity = setup.ambient_intensity;
+        self.day_ambient = None;
+    }
+}
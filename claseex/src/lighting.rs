@@ -0,0 +1,218 @@
+use raylib::prelude::*;
+
+use crate::displacement::DisplacementShader;
+use crate::scene::Scene;
+use crate::shadow::ShadowCubeMap;
+
+// Tipos de luz que soporta el diorama
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    Point,
+    Spot,
+}
+
+// Luz de la escena con su radio de alcance para la atenuación
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub kind: LightKind,
+    pub position: Vector3,
+    pub direction: Vector3, // Solo se usa en luces spot
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,      // Distancia a la que la luz deja de aportar
+    pub cone_angle: f32, // Semiángulo del cono en grados (spot)
+    pub cast_shadows: bool, // Si entra en el presupuesto de sombras
+    pub day_intensity: Option<f32>, // Intensidad de día mientras dura el modo noche
+}
+
+impl Light {
+    pub fn point(position: Vector3, color: Color, intensity: f32, range: f32) -> Self {
+        Light {
+            kind: LightKind::Point,
+            position,
+            direction: Vector3::new(0.0, -1.0, 0.0),
+            color,
+            intensity,
+            range,
+            cone_angle: 0.0,
+            cast_shadows: true,
+            day_intensity: None,
+        }
+    }
+
+    pub fn spot(
+        position: Vector3,
+        target: Vector3,
+        color: Color,
+        intensity: f32,
+        range: f32,
+        cone_angle: f32,
+    ) -> Self {
+        Light {
+            kind: LightKind::Spot,
+            position,
+            direction: (target - position).normalized(),
+            color,
+            intensity,
+            range,
+            cone_angle,
+            cast_shadows: true,
+            day_intensity: None,
+        }
+    }
+
+    // Factor de atenuación entre 0 y 1 para un punto de la superficie
+    pub fn attenuation(&self, surface_position: Vector3) -> f32 {
+        let to_surface = surface_position - self.position;
+        let distance = to_surface.length();
+        if distance >= self.range {
+            return 0.0;
+        }
+
+        // Caída suave que llega a cero exactamente en el radio de alcance
+        let ratio = distance / self.range;
+        let falloff = (1.0 - ratio * ratio).max(0.0);
+        let mut factor = falloff * falloff;
+
+        if self.kind == LightKind::Spot {
+            // Borde del cono suavizado entre el 80% y el 100% del ángulo
+            let cos_angle = to_surface.normalized().dot(self.direction);
+            let cos_outer = self.cone_angle.to_radians().cos();
+            let cos_inner = (self.cone_angle * 0.8).to_radians().cos();
+            let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer).max(0.0001)).clamp(0.0, 1.0);
+            factor *= t * t * (3.0 - 2.0 * t);
+        }
+
+        factor
+    }
+}
+
+// Niebla exponencial: mezcla el color con el de la niebla según la distancia al ojo
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub color: Color,
+    pub density: f32,
+    pub eye: Vector3,
+}
+
+impl Fog {
+    pub fn apply(&self, color: Color, position: Vector3) -> Color {
+        let visibility = (-self.density * position.distance_to(self.eye)).exp();
+        let mix = |surface: u8, fog: u8| (fog as f32 + (surface as f32 - fog as f32) * visibility) as u8;
+        Color::new(
+            mix(color.r, self.color.r),
+            mix(color.g, self.color.g),
+            mix(color.b, self.color.b),
+            color.a,
+        )
+    }
+}
+
+// Parámetros de iluminación compartidos por todos los objetos de un frame
+#[derive(Clone, Copy, Debug)]
+pub struct LightingParams<'a> {
+    pub lights: &'a [Light],
+    pub ambient_intensity: f32,
+    pub diffuse_intensity: f32,
+    pub fog: Option<Fog>,
+    pub shadows: &'a [ShadowCubeMap], // Sombras de las luces puntuales que tienen mapa
+    pub shadowed: &'a [usize],        // Luces que proyectan sombra este frame, con mapa o plana
+    pub displacement_shader: Option<&'a DisplacementShader>, // Sin él el desplazamiento se hace en la CPU
+}
+
+// Luz que llega a un punto por canal (ambiente más el aporte difuso de cada luz), sin saturar.
+// Las luces con mapa de sombra en `shadows` no aportan donde algo las tapa
+pub fn irradiance(
+    surface_position: Vector3,
+    surface_normal: Vector3,
+    lights: &[Light],
+    shadows: &[ShadowCubeMap],
+    ambient_intensity: f32,
+    diffuse_intensity: f32,
+) -> [f32; 3] {
+    let mut lighting = [ambient_intensity; 3];
+
+    for (index, light) in lights.iter().enumerate() {
+        let attenuation = light.attenuation(surface_position);
+        if attenuation <= 0.0 {
+            continue;
+        }
+
+        // Vector de la superficie hacia la luz
+        let light_direction = (light.position - surface_position).normalized();
+
+        // Calcular el producto punto entre la normal de la superficie y la dirección de la luz
+        let dot_product = surface_normal.dot(light_direction).max(0.0);
+        if dot_product <= 0.0 {
+            continue;
+        }
+        let visibility = shadows
+            .iter()
+            .find(|shadow| shadow.light == index)
+            .map_or(1.0, |shadow| shadow.visibility(surface_position, surface_normal));
+
+        let amount = diffuse_intensity * light.intensity * attenuation * dot_product * visibility;
+        lighting[0] += amount * light.color.r as f32 / 255.0;
+        lighting[1] += amount * light.color.g as f32 / 255.0;
+        lighting[2] += amount * light.color.b as f32 / 255.0;
+    }
+
+    lighting
+}
+
+// Función para calcular iluminación difusa sumando el aporte de cada luz
+pub fn calculate_diffuse_lighting(
+    surface_position: Vector3,
+    surface_normal: Vector3,
+    lights: &[Light],
+    shadows: &[ShadowCubeMap],
+    base_color: Color,
+    ambient_intensity: f32,
+    diffuse_intensity: f32,
+) -> Color {
+    let lighting = irradiance(
+        surface_position,
+        surface_normal,
+        lights,
+        shadows,
+        ambient_intensity,
+        diffuse_intensity,
+    );
+
+    // Aplicar la iluminación al color base
+    Color::new(
+        (base_color.r as f32 * lighting[0].min(1.0)) as u8,
+        (base_color.g as f32 * lighting[1].min(1.0)) as u8,
+        (base_color.b as f32 * lighting[2].min(1.0)) as u8,
+        base_color.a,
+    )
+}
+
+// Cuánto quedan la luz de cada fuente y la ambiente en el modo noche respecto del día
+const NIGHT_LIGHT_SCALE: f32 = 0.35;
+const NIGHT_AMBIENT_SCALE: f32 = 0.4;
+
+impl Scene {
+    // Modo noche de los scripts "noche" y "dia". Cada luz recuerda su intensidad de día, así repetir
+    // la noche no oscurece más y las luces que se agregan después (un esquema de iluminación, un
+    // parche) se oscurecen con la próxima noche sin que el día les pise la intensidad
+    pub fn set_night(&mut self, night: bool) {
+        if night {
+            for light in self.lights.iter_mut() {
+                let day = *light.day_intensity.get_or_insert(light.intensity);
+                light.intensity = day * NIGHT_LIGHT_SCALE;
+            }
+            let day = *self.day_ambient.get_or_insert(self.ambient_intensity);
+            self.ambient_intensity = day * NIGHT_AMBIENT_SCALE;
+        } else {
+            for light in self.lights.iter_mut() {
+                if let Some(day) = light.day_intensity.take() {
+                    light.intensity = day;
+                }
+            }
+            if let Some(day) = self.day_ambient.take() {
+                self.ambient_intensity = day;
+            }
+        }
+    }
+}
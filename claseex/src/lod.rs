@@ -0,0 +1,55 @@
+use serde_json::{json, Map, Value};
+
+use crate::scene::{f32_to_json, Shape};
+
+// Versión reducida de una forma que se usa desde cierta distancia a la cámara
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodLevel {
+    pub triangles: usize, // Triángulos objetivo de la simplificación
+    pub distance: f32,    // Desde esta distancia se dibuja esta versión
+}
+
+// Niveles de detalle configurados para una forma, ordenados por distancia
+#[derive(Clone, Debug, PartialEq)]
+pub struct LodSettings {
+    pub shape: Shape,
+    pub levels: Vec<LodLevel>,
+}
+
+// Formato del archivo: { "sphere": [{ "triangles": 160, "distance": 10.0 }, ...], ... }
+pub fn lod_from_json(value: &Value) -> Result<Vec<LodSettings>, String> {
+    let Some(shapes) = value.as_object() else {
+        return Ok(Vec::new());
+    };
+    let mut settings = Vec::new();
+    for (name, levels) in shapes {
+        let shape = Shape::from_name(name).ok_or_else(|| format!("LOD para una forma desconocida: {}", name))?;
+        let mut levels: Vec<LodLevel> = levels
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|level| {
+                Ok(LodLevel {
+                    triangles: level["triangles"].as_u64().ok_or("nivel de LOD sin 'triangles'")? as usize,
+                    distance: level["distance"].as_f64().ok_or("nivel de LOD sin 'distance'")? as f32,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        levels.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        settings.push(LodSettings { shape, levels });
+    }
+    Ok(settings)
+}
+
+pub fn lod_to_json(settings: &[LodSettings]) -> Value {
+    let mut shapes = Map::new();
+    for setting in settings {
+        let levels = setting
+            .levels
+            .iter()
+            .map(|level| json!({ "triangles": level.triangles, "distance": f32_to_json(level.distance) }))
+            .collect();
+        shapes.insert(setting.shape.name().to_string(), Value::Array(levels));
+    }
+    Value::Object(shapes)
+}
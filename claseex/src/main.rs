@@ -1,63 +1,86 @@
+use std::collections::{HashMap, HashSet};
+
 use raylib::prelude::*;
 
-// Función para rotar un vector en 3D
-fn rotate_vector(v: Vector3, angle_x: f32, angle_y: f32, angle_z: f32) -> Vector3 {
-    let mut result = v;
-    
-    // Rotación alrededor del eje Y
-    let cos_y = angle_y.cos();
-    let sin_y = angle_y.sin();
-    let temp_x = result.x * cos_y - result.z * sin_y;
-    let temp_z = result.x * sin_y + result.z * cos_y;
-    result.x = temp_x;
-    result.z = temp_z;
-    
-    // Rotación alrededor del eje X
-    let cos_x = angle_x.cos();
-    let sin_x = angle_x.sin();
-    let temp_y = result.y * cos_x - result.z * sin_x;
-    let temp_z2 = result.y * sin_x + result.z * cos_x;
-    result.y = temp_y;
-    result.z = temp_z2;
-    
-    // Rotación alrededor del eje Z
-    let cos_z = angle_z.cos();
-    let sin_z = angle_z.sin();
-    let temp_x2 = result.x * cos_z - result.y * sin_z;
-    let temp_y2 = result.x * sin_z + result.y * cos_z;
-    result.x = temp_x2;
-    result.y = temp_y2;
-    
-    result
-}
+mod audio;
+mod asset_browser;
+mod behavior;
+mod catalog;
+mod console;
+mod csg;
+mod debug_draw;
+mod displacement;
+mod exposure;
+mod frame_capture;
+mod frustum;
+mod geometry;
+mod id_buffer;
+mod imposter;
+mod labels;
+mod lesson;
+mod light_rig;
+mod lighting;
+mod lod;
+mod measure;
+mod nav_cube;
+mod navigation;
+mod net;
+mod patch;
+mod path;
+mod photo;
+mod picking;
+mod pool;
+mod prefab;
+mod presentation;
+mod reflections;
+mod render_scale;
+mod renderer;
+mod rng;
+mod scatter;
+mod scene;
+mod session;
+mod shadow;
+mod spatial;
+mod thumbnails;
+mod trigger;
+mod ui;
+mod weather;
 
-// Función para calcular iluminación difusa
-fn calculate_diffuse_lighting(
-    surface_position: Vector3,
-    surface_normal: Vector3,
-    light_position: Vector3,
-    base_color: Color,
-    ambient_intensity: f32,
-    diffuse_intensity: f32,
-) -> Color {
-    // Vector de la superficie hacia la luz
-    let light_direction = (light_position - surface_position).normalized();
-    
-    // Calcular el producto punto entre la normal de la superficie y la dirección de la luz
-    let dot_product = surface_normal.dot(light_direction).max(0.0);
-    
-    // Calcular la iluminación total (ambiente + difusa)
-    let lighting = ambient_intensity + (diffuse_intensity * dot_product);
-    let lighting = lighting.min(1.0);
-    
-    // Aplicar la iluminación al color base
-    Color::new(
-        (base_color.r as f32 * lighting) as u8,
-        (base_color.g as f32 * lighting) as u8,
-        (base_color.b as f32 * lighting) as u8,
-        base_color.a,
-    )
-}
+use asset_browser::AssetBrowser;
+use audio::AudioEngine;
+use behavior::Behavior;
+use console::{Console, ConsoleContext};
+use displacement::DisplacementShader;
+use exposure::AutoExposure;
+use frame_capture::CaptureContext;
+use frustum::Frustum;
+use id_buffer::{IdBuffer, IdContext};
+use imposter::{BakeContext, ImposterCache};
+use labels::LabelFader;
+use lesson::{Lesson, LessonPlayer, LessonState};
+use lighting::LightingParams;
+use measure::MeasureTool;
+use nav_cube::NavCube;
+use net::{Client, Host, SharedView};
+use photo::PhotoRenderer;
+use picking::PickBackend;
+use pool::ObjectPool;
+use prefab::Prefab;
+use presentation::PresentationPlayer;
+use reflections::{ReflectionContext, Reflections};
+use render_scale::{SceneTarget, MAX_RENDER_SCALE, MIN_RENDER_SCALE, RENDER_SCALE_STEP};
+use renderer::ShapeLibrary;
+use scene::{PrefabInstance, Scene, DEFAULT_SCENE_PATH};
+use session::{Session, BOOKMARK_SLOTS, SESSION_PATH};
+use shadow::MAX_SHADOW_BUDGET;
+use weather::Weather;
+
+// Distancias del frustum usadas para el culling y la visualización de depuración
+const NEAR_PLANE: f32 = 0.1;
+const MIN_VIEW_DISTANCE: f32 = 2.0;
+const MAX_VIEW_DISTANCE: f32 = 60.0;
+// Altura del panel de iluminación, debajo de las líneas de ayuda y estado
+const RIG_PANEL_Y: f32 = 210.0;
 
 fn main() {
     // Configuración inicial de la ventana
@@ -66,136 +89,1262 @@ fn main() {
         .title("Diorama del Cubo Simple - Rotación y Cámara")
         .build();
 
+    // Sesión anterior: cámara, marcadores, paneles y vistas de depuración. --no-session arranca de cero
+    let restore_session = !std::env::args().any(|arg| arg == "--no-session");
+    let session = if restore_session && std::path::Path::new(SESSION_PATH).exists() {
+        Session::load(SESSION_PATH).unwrap_or_else(|error| {
+            eprintln!("No se pudo restaurar la sesión ({}), se empieza de cero", error);
+            Session::default()
+        })
+    } else {
+        Session::default()
+    };
+
     // Configurar la cámara 3D
-    let mut camera = Camera3D::perspective(
-        Vector3::new(5.0, 5.0, 5.0),  // Posición de la cámara
-        Vector3::new(0.0, 0.0, 0.0),  // Punto al que mira
-        Vector3::new(0.0, 1.0, 0.0),  // Vector up
-        45.0,                         // FOV
+    let mut camera = session.camera;
+    let mut bookmarks = session.bookmarks;
+
+    // Segunda cámara fija para observar el frustum de la principal
+    let debug_camera = Camera3D::perspective(
+        Vector3::new(14.0, 11.0, -12.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+        50.0,
+    );
+    let mut view_from_debug = session.view_from_debug;
+    let mut free_camera = session.free_camera;
+    let mut orthographic = session.orthographic;
+    if free_camera {
+        rl.disable_cursor();
+    }
+    let mut nav_cube = NavCube::default();
+    nav_cube.holding = session.holding_view;
+    let mut use_nav_cube = true;
+    let mut show_light_volumes = session.show_light_volumes;
+    let mut view_distance = session.view_distance; // Plano far usado para el culling
+
+    // Cargar la escena (capas, objetos y luces) desde el archivo
+    let mut scene = Scene::load(DEFAULT_SCENE_PATH).unwrap_or_else(|error| {
+        eprintln!("No se pudo cargar la escena ({}), se usa la escena por defecto", error);
+        Scene::fallback()
+    });
+    let library = ShapeLibrary::with_lods(&scene.lod);
+
+    // --render-catalog dibuja las escenas de prueba en una grilla y termina
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--render-catalog") {
+        match catalog::render_catalog(&mut rl, &thread, &library) {
+            Ok(path) => println!("Catálogo guardado en {}", path),
+            Err(error) => eprintln!("No se pudo generar el catálogo: {}", error),
+        }
+        return;
+    }
+    // --render-thumbnails guarda una vuelta completa de cada prefab en thumbnails y termina
+    if args.iter().any(|arg| arg == "--render-thumbnails") {
+        match thumbnails::render_thumbnails(&mut rl, &thread, &library) {
+            Ok(paths) => println!("{} miniaturas guardadas en {}", paths.len(), thumbnails::THUMBNAIL_DIR),
+            Err(error) => eprintln!("No se pudieron generar las miniaturas: {}", error),
+        }
+        return;
+    }
+
+    // Sesión compartida: --host [puerto] transmite la vista, --connect dir:puerto la sigue en vivo
+    let arg_after = |flag: &str| {
+        let index = args.iter().position(|arg| arg == flag)?;
+        Some(args.get(index + 1).cloned().unwrap_or_default())
+    };
+    let mut host = arg_after("--host").and_then(|port| {
+        let port = port.parse().unwrap_or(net::DEFAULT_PORT);
+        Host::bind(port)
+            .inspect(|_| println!("Compartiendo la vista en el puerto {}", port))
+            .inspect_err(|error| eprintln!("No se pudo abrir el puerto {}: {}", port, error))
+            .ok()
+    });
+    let mut client = arg_after("--connect").and_then(|address| {
+        Client::connect(&address)
+            .inspect_err(|error| eprintln!("No se pudo conectar a '{}': {}", address, error))
+            .ok()
+    });
+    // Modo lección: --lesson <nombre> arranca con un tutorial de lessons
+    let mut lesson = LessonPlayer::default();
+    if let Some(name) = arg_after("--lesson") {
+        match Lesson::load(&name) {
+            Ok(loaded) => lesson.start(loaded),
+            Err(error) => eprintln!("No se pudo cargar la leccion: {}", error),
+        }
+    }
+    let mut show_layer_panel = session.show_layer_panel;
+    let mut show_rig_panel = session.show_rig_panel;
+    let mut show_asset_browser = session.show_asset_browser;
+    let mut asset_browser = AssetBrowser::new(&mut rl, &thread);
+    let mut status_message = String::new();
+
+    // Selección con el mouse y prefab activo para instanciar
+    let mut selected: Option<u32> = None;
+    let mut active_prefab: Option<Prefab> = None;
+    let mut next_palette_slot = session.next_palette_slot;
+
+    // Edición de curvas: punto de control elegido (curva, punto) y arrastre
+    let mut editing_paths = false;
+    let mut selected_point: Option<(usize, usize)> = None;
+    let mut dragging_point = false;
+
+    // Consola de comandos con los scripts que pueden llamar los triggers
+    let mut console = Console::new();
+    let mut show_triggers = session.show_triggers;
+
+    // Herramienta de medición y notas
+    let mut measure = MeasureTool::default();
+    let mut label_fader = LabelFader::default();
+    let mut show_labels = session.show_labels;
+
+    // Sonidos de la escena; sin dispositivo de audio el diorama sigue sin sonido
+    let mut audio = AudioEngine::new();
+
+    // Clima: la escena guarda el elegido y el controlador hace la transición
+    let mut weather = Weather::new(scene.weather);
+    console.register(
+        "noche",
+        Box::new(|context| {
+            context.scene.set_night(true);
+            "Modo noche".to_string()
+        }),
+    );
+    console.register(
+        "dia",
+        Box::new(|context| {
+            context.scene.set_night(false);
+            "Modo día".to_string()
+        }),
     );
 
-    // Crear una textura procedural para el cubo
-    let image = Image::gen_image_checked(64, 64, 8, 8, Color::WHITE, Color::GRAY);
-    let _cube_texture = rl.load_texture_from_image(&thread, &image);
+    // Modo foto: oculta la interfaz y dibuja en texturas para la profundidad de campo y las capturas
+    let mut photo = session.photo;
+    let mut photo_renderer = PhotoRenderer::default();
+    let mut capture_requested = false;
+    let mut frame_capture_requested = false;
+    let mut auto_exposure = AutoExposure::new();
+
+    // Play: la cámara de presentación de la escena reemplaza a la del editor y la edición se bloquea
+    let mut player = PresentationPlayer::default();
+    let mut play_requested = false;
+
+    // Grupos de objetos lejanos dibujados como imágenes horneadas
+    let mut imposters = ImposterCache::default();
+    let mut use_imposters = session.use_imposters;
+    let mut cube_shadows = session.cube_shadows;
+    let mut shadow_budget = session.shadow_budget;
+
+    // Reflejos en espacio de pantalla sobre el suelo mojado y las superficies reflejantes
+    let mut use_reflections = session.reflections;
+    let mut reflections = Reflections::default();
 
-    // Variables para la animación del cubo
-    let mut rotation_x = 0.0f32;
-    let mut rotation_y = 0.0f32;
-    let mut rotation_z = 0.0f32;
+    // Desplazamiento por ruido en un shader de vértices; si no compila, o con --cpu-displacement, se
+    // desplaza en la CPU como antes
+    let displacement_shader = if args.iter().any(|arg| arg == "--cpu-displacement") {
+        None
+    } else {
+        DisplacementShader::load(&mut rl, &thread)
+            .inspect_err(|error| eprintln!("Desplazamiento en la CPU: {}", error))
+            .ok()
+    };
 
-    // Variables para la luz difusa
-    let light_position = Vector3::new(3.0, 4.0, 2.0); // Posición fija de la luz
-    let ambient_intensity = 0.3; // Intensidad de luz ambiente
-    let diffuse_intensity = 0.7; // Intensidad de luz difusa
+    // Escala de render: resolución interna de la escena 3D respecto de la ventana
+    let mut render_scale = session.render_scale;
+    let mut scene_target = SceneTarget::default();
+
+    // Objetos de vida corta (escombros, cubos lanzados por scripts) que se reciclan en vez de liberarse
+    let mut pool = ObjectPool::new(&mut scene);
+
+    // Escala de tiempo de la simulación (0 la pausa); el anfitrión la comparte con los espectadores
+    let mut time_scale = session.time_scale;
+
+    // Selección con el mouse: rayo en la CPU o buffer de ids en la GPU
+    let mut pick_backend = session.pick_backend;
+    let mut id_buffer = IdBuffer::default();
+
+    let mut selected_light = session.selected_light;
 
     // Configurar FPS
     rl.set_target_fps(60);
 
     // Loop principal
     while !rl.window_should_close() {
-        // Actualizar rotaciones del cubo
-        rotation_x += 20.0 * rl.get_frame_time(); // Rotación en X
-        rotation_y += 30.0 * rl.get_frame_time(); // Rotación en Y
-        rotation_z += 25.0 * rl.get_frame_time(); // Rotación en Z
+        let dt = rl.get_frame_time();
+        let sim_dt = dt * time_scale; // La cámara y la interfaz siguen usando el tiempo real
 
-        // Control de cámara: acercar/alejar con rueda del mouse, rotar con mouse
-        rl.update_camera(&mut camera, CameraMode::CAMERA_ORBITAL);
+        // Comportamientos (giro, vaivén, órbita), ondulación de los materiales y objetos que recorren curvas
+        scene.update_behaviors(sim_dt);
+        scene.update_displacements(sim_dt);
+        scene.update_followers(sim_dt);
+        pool.update(&mut scene, sim_dt);
 
-        // Control adicional de zoom con teclas
-        if rl.is_key_down(KeyboardKey::KEY_UP) {
-            let direction = (camera.target - camera.position).normalized();
-            camera.position = camera.position + direction * 2.0 * rl.get_frame_time();
+        // Espectador: la cámara, la selección y las posiciones las decide el anfitrión
+        if let Some(viewer) = client.as_mut()
+            && let Some(view) = viewer.poll()
+        {
+            camera = view.camera;
+            time_scale = view.time_scale;
+            selected = view.selected;
+            view.apply(&mut scene);
         }
-        if rl.is_key_down(KeyboardKey::KEY_DOWN) {
-            let direction = (camera.target - camera.position).normalized();
-            camera.position = camera.position - direction * 2.0 * rl.get_frame_time();
+        let spectating = client.as_ref().is_some_and(|viewer| viewer.connected);
+
+        // Consola: la tecla ` la abre o la cierra; mientras está abierta el teclado escribe en ella
+        if rl.is_key_pressed(KeyboardKey::KEY_GRAVE) {
+            console.open = !console.open;
+        }
+        if console.open {
+            while let Some(character) = rl.get_char_pressed() {
+                if character != '`' {
+                    console.input.push(character);
+                }
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                console.input.pop();
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                console.submit(&mut ConsoleContext {
+                    scene: &mut scene,
+                    camera: &mut camera,
+                    pool: &mut pool,
+                    lesson: &mut lesson,
+                });
+            }
         }
+        let shortcuts = !console.open;
 
-        // Inicio del renderizado
-        let mut d = rl.begin_drawing(&thread);
-        d.clear_background(Color::new(50, 50, 50, 255)); // Fondo gris oscuro
+        // P o el botón Play entran a la presentación; de nuevo vuelven al editor donde se había dejado
+        if shortcuts && !rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) && rl.is_key_pressed(KeyboardKey::KEY_P) {
+            play_requested = true;
+        }
+        if play_requested {
+            play_requested = false;
+            if player.active {
+                player.stop();
+                if free_camera {
+                    rl.disable_cursor();
+                }
+                status_message = "Editor".to_string();
+            } else {
+                player.start();
+                photo.active = false;
+                selected = None;
+                editing_paths = false;
+                selected_point = None;
+                dragging_point = false;
+                measure.active = false;
+                measure.clear();
+                asset_browser.cancel();
+                rl.enable_cursor();
+                status_message = if scene.presentation.is_empty() && bookmarks.iter().all(Option::is_none) {
+                    "La escena no define camara de presentacion; se usa la vista del editor".to_string()
+                } else {
+                    "Presentacion".to_string()
+                };
+            }
+        }
+        let playing = player.active;
+        let editing = !playing;
+        if shortcuts && playing && rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+            player.next_shot();
+        }
+        let presentation_camera = if playing {
+            player.update(&scene.presentation, &scene.paths, &bookmarks, dt)
+        } else {
+            None
+        };
+
+        // F alterna entre la cámara orbital y la cámara libre (WASD, flechas y mouse)
+        if shortcuts && editing && rl.is_key_pressed(KeyboardKey::KEY_F) {
+            free_camera = !free_camera;
+            if free_camera {
+                rl.disable_cursor();
+            } else {
+                rl.enable_cursor();
+            }
+        }
+        // F6 entra o sale del modo foto; ahí la cámara se mueve libre mientras se mantiene el clic derecho
+        if shortcuts && editing && rl.is_key_pressed(KeyboardKey::KEY_F6) {
+            if photo.active {
+                photo.active = false;
+            } else {
+                photo.enter(&camera);
+                free_camera = false;
+                rl.enable_cursor();
+            }
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F7) {
+            use_imposters = !use_imposters;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F8) {
+            cube_shadows = !cube_shadows;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F9) {
+            pick_backend = pick_backend.toggled();
+        }
+        // F12 captura la escena: en modo foto con la lente y la resolución del panel, si no a la escala de render
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F12) {
+            capture_requested = true;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_COMMA) {
+            render_scale = (render_scale - RENDER_SCALE_STEP).max(MIN_RENDER_SCALE);
+            status_message = format!("Escala de render: {:.0}%", render_scale * 100.0);
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+            render_scale = (render_scale + RENDER_SCALE_STEP).min(MAX_RENDER_SCALE);
+            status_message = format!("Escala de render: {:.0}%", render_scale * 100.0);
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F11) {
+            use_reflections = !use_reflections;
+        }
+        // F10 guarda las pasadas intermedias del frame para depurar sombras y luces
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F10) {
+            frame_capture_requested = true;
+        }
+        // Cubo de navegación: un clic en una cara, arista o esquina alinea la vista con los ejes
+        let mut show_nav_cube = use_nav_cube && editing && !photo.active && !free_camera && !spectating;
+        let over_nav_cube = show_nav_cube && nav_cube.update(&rl, &mut camera, dt);
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_O) {
+            nav_cube.holding = false;
+        }
+
+        // Marcadores de cámara: Ctrl+número guarda la vista actual, el número solo vuelve a ella
+        let bookmark_keys: [KeyboardKey; BOOKMARK_SLOTS] = [
+            KeyboardKey::KEY_ONE,
+            KeyboardKey::KEY_TWO,
+            KeyboardKey::KEY_THREE,
+            KeyboardKey::KEY_FOUR,
+        ];
+        for (slot, key) in bookmark_keys.into_iter().enumerate() {
+            if !shortcuts || spectating || playing || !rl.is_key_pressed(key) {
+                continue;
+            }
+            if rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) {
+                bookmarks[slot] = Some(camera);
+                status_message = format!("Vista guardada en el marcador {}", slot + 1);
+            } else if let Some(bookmark) = bookmarks[slot] {
+                camera = bookmark;
+                nav_cube.holding = true;
+                status_message = format!("Marcador {}", slot + 1);
+            }
+        }
+        if spectating || playing {
+            // La cámara sigue a la del anfitrión; en la presentación el editor queda donde estaba
+        } else if photo.active {
+            if shortcuts && rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT) {
+                rl.update_camera(&mut camera, CameraMode::CAMERA_FREE);
+            }
+        } else if free_camera {
+            if shortcuts {
+                rl.update_camera(&mut camera, CameraMode::CAMERA_FREE);
+            }
+        } else {
+            // Control de cámara: acercar/alejar con rueda del mouse, rotar con mouse.
+            // Con una vista elegida en el cubo de navegación la órbita queda quieta
+            if !nav_cube.holds_view() {
+                rl.update_camera(&mut camera, CameraMode::CAMERA_ORBITAL);
+            }
 
+            // Control adicional de zoom con teclas
+            if shortcuts && rl.is_key_down(KeyboardKey::KEY_UP) {
+                let direction = (camera.target - camera.position).normalized();
+                camera.position += direction * 2.0 * dt;
+            }
+            if shortcuts && rl.is_key_down(KeyboardKey::KEY_DOWN) {
+                let direction = (camera.target - camera.position).normalized();
+                camera.position -= direction * 2.0 * dt;
+            }
+        }
+
+        // Controles de depuración: vista externa, volúmenes de luz y radios
+        if shortcuts && editing && rl.is_key_pressed(KeyboardKey::KEY_V) {
+            orthographic = !orthographic;
+            status_message = format!("Proyeccion {}", if orthographic { "ortografica" } else { "perspectiva" });
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_C) {
+            view_from_debug = !view_from_debug;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_L) {
+            show_light_volumes = !show_light_volumes;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_TAB) && !scene.lights.is_empty() {
+            selected_light = (selected_light + 1) % scene.lights.len();
+        }
+        // H activa o quita la sombra de la luz elegida; B cambia cuántas luces pueden tener sombra
+        if shortcuts
+            && rl.is_key_pressed(KeyboardKey::KEY_H)
+            && let Some(light) = scene.lights.get_mut(selected_light)
         {
-            // Modo 3D
-            let mut d3d = d.begin_mode3D(camera);
-
-            // Dibujar un plano como base (suelo)
-            d3d.draw_plane(
-                Vector3::new(0.0, -2.0, 0.0),
-                Vector2::new(10.0, 10.0),
-                Color::new(100, 100, 100, 255), // Gris
+            light.cast_shadows = !light.cast_shadows;
+            status_message = format!(
+                "Luz {}: {}",
+                selected_light,
+                if light.cast_shadows { "con sombra" } else { "sin sombra" }
             );
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_B) {
+            shadow_budget = (shadow_budget + 1) % (MAX_SHADOW_BUDGET + 1);
+            status_message = format!("Presupuesto de sombras: {} luces", shadow_budget);
+        }
+        if editing && let Some(light) = scene.lights.get_mut(selected_light) {
+            if shortcuts && rl.is_key_down(KeyboardKey::KEY_RIGHT_BRACKET) {
+                light.range += 4.0 * dt;
+            }
+            if shortcuts && rl.is_key_down(KeyboardKey::KEY_LEFT_BRACKET) {
+                light.range = (light.range - 4.0 * dt).max(0.5);
+            }
+        }
+        if shortcuts && rl.is_key_down(KeyboardKey::KEY_X) {
+            view_distance = (view_distance + 10.0 * dt).min(MAX_VIEW_DISTANCE);
+        }
+        if shortcuts && rl.is_key_down(KeyboardKey::KEY_Z) {
+            view_distance = (view_distance - 10.0 * dt).max(MIN_VIEW_DISTANCE);
+        }
 
-            // === CUBO PRINCIPAL CON ROTACIÓN E ILUMINACIÓN DIFUSA ===
-            
-            // Aplicar transformaciones manuales para la rotación
-            // Primero dibujamos la sombra del cubo en el plano
-            d3d.draw_cube(
-                Vector3::new(0.5, -1.99, 0.5), // Sombra ligeramente desplazada
-                2.2, 0.01, 2.2,
-                Color::new(20, 20, 20, 180), // Sombra oscura semi-transparente
-            );
+        // Panel de capas y guardado de la escena
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F1) {
+            show_layer_panel = !show_layer_panel;
+        }
+        // I muestra los juegos de luces (Ctrl+I instancia el prefab)
+        if shortcuts
+            && editing
+            && !rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            && rl.is_key_pressed(KeyboardKey::KEY_I)
+        {
+            show_rig_panel = !show_rig_panel;
+        }
+        if shortcuts && editing && rl.is_key_pressed(KeyboardKey::KEY_R) {
+            show_asset_browser = !show_asset_browser;
+            asset_browser.cancel();
+        }
+        if shortcuts && editing && rl.is_key_pressed(KeyboardKey::KEY_F2) {
+            editing_paths = !editing_paths;
+            selected_point = None;
+            dragging_point = false;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F3) {
+            show_triggers = !show_triggers;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F4) {
+            show_labels = !show_labels;
+        }
+        if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_F5) {
+            scene.weather = scene.weather.next();
+            status_message = format!("Clima: {}", scene.weather.name());
+        }
+        if shortcuts && !spectating && rl.is_key_pressed(KeyboardKey::KEY_MINUS) {
+            time_scale = (time_scale - 0.25).max(0.0);
+        }
+        if shortcuts && !spectating && rl.is_key_pressed(KeyboardKey::KEY_EQUAL) {
+            time_scale = (time_scale + 0.25).min(4.0);
+        }
+        if shortcuts && editing && rl.is_key_pressed(KeyboardKey::KEY_M) {
+            measure.active = !measure.active;
+            measure.clear();
+        }
+        if shortcuts
+            && rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            && rl.is_key_pressed(KeyboardKey::KEY_S)
+        {
+            status_message = match scene.save(DEFAULT_SCENE_PATH) {
+                Ok(()) => {
+                    // El archivo guardado pasa a ser la base de los próximos parches
+                    scene.baseline = Some(scene.to_json());
+                    format!("Escena guardada en {}", DEFAULT_SCENE_PATH)
+                }
+                Err(error) => format!("Error al guardar: {}", error),
+            };
+        }
+
+        // Selección de objetos con clic izquierdo (fuera del panel de capas y del inspector)
+        let transforms = scene.world_transforms();
+        let panel_x = rl.get_screen_width() as f32 - 310.0;
+        let panel_bounds = ui::layer_panel_bounds(scene.layers.len(), panel_x, 10.0);
+        // El inspector va debajo del panel de capas, o arriba si el panel está oculto
+        let inspector_y = if show_layer_panel { panel_bounds.y + panel_bounds.height + 10.0 } else { 10.0 };
+        let inspector_bounds = selected
+            .and_then(|id| scene.object(id))
+            .map(|object| ui::inspector_bounds(object, panel_x, inspector_y));
+        // Con la cámara libre el cursor está oculto y se elige por el centro de la pantalla
+        let screen_center = Vector2::new(rl.get_screen_width() as f32 * 0.5, rl.get_screen_height() as f32 * 0.5);
+        let mouse = if free_camera { screen_center } else { rl.get_mouse_position() };
+        let play_bounds = ui::play_button_bounds(rl.get_screen_width(), rl.get_screen_height());
+        let rig_bounds = ui::rig_panel_bounds(10.0, RIG_PANEL_Y);
+        // Los recursos van debajo del panel de iluminación, o en su lugar si está oculto
+        let browser_position =
+            Vector2::new(10.0, if show_rig_panel { rig_bounds.y + rig_bounds.height + 10.0 } else { RIG_PANEL_Y });
+        let browser_bounds = asset_browser.bounds(scene.palette.len(), browser_position.x, browser_position.y);
+        let mouse_over_ui = photo.active
+            || over_nav_cube
+            || play_bounds.check_collision_point_rec(mouse)
+            || (show_layer_panel && panel_bounds.check_collision_point_rec(mouse))
+            || (show_rig_panel && rig_bounds.check_collision_point_rec(mouse))
+            || (show_asset_browser && browser_bounds.check_collision_point_rec(mouse))
+            || inspector_bounds.is_some_and(|bounds| bounds.check_collision_point_rec(mouse));
+        let editor_camera = if orthographic { orthographic_view(&camera) } else { camera };
+        let view_camera = if photo.active {
+            photo.camera(camera)
+        } else if let Some(presentation) = presentation_camera {
+            presentation
+        } else if view_from_debug && editing {
+            debug_camera
+        } else {
+            editor_camera
+        };
+        let mouse_ray = rl.get_screen_to_world_ray(mouse, view_camera);
+        let hover = if mouse_over_ui || playing {
+            None
+        } else if pick_backend == PickBackend::IdBuffer {
+            let context = IdContext {
+                library: &library,
+                scene: &scene,
+                transforms: &transforms,
+            };
+            id_buffer.pick(&mut rl, &thread, &context, view_camera, mouse).unwrap_or_else(|error| {
+                // Si la GPU falla se vuelve al rayo
+                eprintln!("No se pudo leer el buffer de ids: {}", error);
+                pick_backend = PickBackend::Ray;
+                None
+            })
+        } else {
+            picking::pick(&scene, &transforms, mouse_ray)
+        };
+        // Con un recurso elegido el clic en la vista lo coloca en lugar de seleccionar
+        let placing = asset_browser.held.is_some();
+        if editing && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) && !mouse_over_ui && !placing {
+            // Al editar curvas los puntos de control tienen prioridad sobre los objetos
+            let point_hit = if editing_paths { path::pick_control_point(&scene.paths, mouse_ray) } else { None };
+            if point_hit.is_some() {
+                selected_point = point_hit;
+                dragging_point = true;
+            } else if measure.active {
+                if let Some(hit) = hover {
+                    measure.add_point(hit.point);
+                }
+            } else {
+                selected = hover.map(|hit| hit.id);
+            }
+        }
+        if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            dragging_point = false;
+        }
+        if measure.active && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+            measure.clear();
+        }
 
-            // Dibujar el cubo principal con color fijo azul
-            let cube_position = Vector3::new(0.0, 0.0, 0.0);
-            let base_cube_color = Color::new(100, 150, 255, 255); // Azul base
-            
-            // Calcular iluminación para diferentes caras del cubo (aplicando rotación a las normales)
-            // Cara frontal (normal hacia +Z)
-            let front_normal = rotate_vector(Vector3::new(0.0, 0.0, 1.0), rotation_x.to_radians(), rotation_y.to_radians(), rotation_z.to_radians());
-            let front_color = calculate_diffuse_lighting(
-                cube_position,
-                front_normal,
-                light_position,
-                base_cube_color,
-                ambient_intensity,
-                diffuse_intensity,
+        // Panel de recursos: clic o arrastre desde una entrada hasta la vista para colocarla donde
+        // apunta el mouse; clic derecho cancela la elegida
+        if editing && show_asset_browser {
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                asset_browser.cancel();
+            }
+            let dropped = asset_browser.handle_mouse(
+                mouse,
+                rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT),
+                rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT),
+                mouse_over_ui,
+                scene.palette.len(),
+                browser_position,
             );
-            
-            // Cara superior (normal hacia +Y)
-            let top_normal = rotate_vector(Vector3::new(0.0, 1.0, 0.0), rotation_x.to_radians(), rotation_y.to_radians(), rotation_z.to_radians());
-            let top_color = calculate_diffuse_lighting(
-                cube_position,
-                top_normal,
-                light_position,
-                base_cube_color,
-                ambient_intensity,
-                diffuse_intensity,
+            // Sin nada bajo el mouse se apoya sobre el plano y = 0
+            let ground = (mouse_ray.direction.y < -1e-4)
+                .then(|| mouse_ray.position + mouse_ray.direction * (-mouse_ray.position.y / mouse_ray.direction.y));
+            if let Some(asset) = dropped {
+                status_message = match hover.map(|hit| hit.point).or(ground) {
+                    Some(point) => {
+                        let palette_slot = matches!(asset, asset_browser::Asset::Prefab(_))
+                            .then(|| cycle_palette_slot(scene.palette.len(), &mut next_palette_slot))
+                            .flatten();
+                        match scene.place_asset(&asset, point, hover.map(|hit| hit.id), palette_slot) {
+                            Ok(id) => {
+                                selected = Some(id);
+                                format!("'{}' colocado", asset.label())
+                            }
+                            Err(error) => error,
+                        }
+                    }
+                    None => "No hay donde colocar el recurso".to_string(),
+                };
+            }
+        }
+
+        // N abre la consola con una nota en el último punto medido, con la medición como texto
+        if shortcuts
+            && measure.active
+            && rl.is_key_pressed(KeyboardKey::KEY_N)
+            && let Some(point) = measure.points.last()
+        {
+            console.open = true;
+            console.input = format!(
+                "nota {:.2} {:.2} {:.2} {}",
+                point.x,
+                point.y,
+                point.z,
+                measure.summary().unwrap_or_default()
             );
-            
-            // Cara derecha (normal hacia +X)
-            let right_normal = rotate_vector(Vector3::new(1.0, 0.0, 0.0), rotation_x.to_radians(), rotation_y.to_radians(), rotation_z.to_radians());
-            let right_color = calculate_diffuse_lighting(
-                cube_position,
-                right_normal,
-                light_position,
-                base_cube_color,
-                ambient_intensity,
-                diffuse_intensity,
+        }
+
+        // Arrastrar el punto elegido sobre el plano horizontal a su altura; RePág/AvPág lo suben o bajan
+        if editing_paths
+            && let Some((path_index, point_index)) = selected_point
+            && let Some(point) = scene.paths.get_mut(path_index).and_then(|p| p.points.get_mut(point_index))
+        {
+            if dragging_point && mouse_ray.direction.y.abs() > 1e-4 {
+                let t = (point.y - mouse_ray.position.y) / mouse_ray.direction.y;
+                if t > 0.0 {
+                    let target = mouse_ray.position + mouse_ray.direction * t;
+                    point.x = target.x;
+                    point.z = target.z;
+                }
+            }
+            if shortcuts && rl.is_key_down(KeyboardKey::KEY_PAGE_UP) {
+                point.y += 2.0 * dt;
+            }
+            if shortcuts && rl.is_key_down(KeyboardKey::KEY_PAGE_DOWN) {
+                point.y -= 2.0 * dt;
+            }
+        }
+
+        // Insert agrega un punto a mitad del tramo siguiente, Supr quita el punto elegido
+        if editing_paths
+            && let Some((path_index, point_index)) = selected_point
+            && let Some(spline) = scene.paths.get_mut(path_index)
+        {
+            if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_INSERT) && point_index < spline.points.len() {
+                let next = if point_index + 1 < spline.points.len() {
+                    spline.points[point_index + 1]
+                } else if spline.closed {
+                    spline.points[0]
+                } else {
+                    spline.points[point_index] + Vector3::new(1.0, 0.0, 0.0)
+                };
+                let midpoint = spline.points[point_index].lerp(next, 0.5);
+                spline.points.insert(point_index + 1, midpoint);
+                selected_point = Some((path_index, point_index + 1));
+            }
+            let removable = spline.points.len() > 2 && point_index < spline.points.len();
+            if shortcuts && rl.is_key_pressed(KeyboardKey::KEY_DELETE) && removable {
+                spline.points.remove(point_index);
+                selected_point = None;
+                dragging_point = false;
+            }
+        }
+
+        // Ctrl+P guarda el subárbol seleccionado como prefab, Ctrl+I lo instancia bajo el mouse
+        let ctrl_down = shortcuts && editing && rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL);
+        if ctrl_down
+            && rl.is_key_pressed(KeyboardKey::KEY_P)
+            && let Some(root) = selected.and_then(|id| scene.object(id))
+            && let Some(prefab) = Prefab::from_subtree(&scene, root.id, &root.name)
+        {
+            status_message = match prefab.save() {
+                Ok(()) => {
+                    asset_browser.refresh(&mut rl, &thread);
+                    format!("Prefab guardado en {}", Prefab::path(&prefab.name))
+                }
+                Err(error) => format!("Error al guardar el prefab: {}", error),
+            };
+            active_prefab = Some(prefab);
+        }
+        if ctrl_down
+            && rl.is_key_pressed(KeyboardKey::KEY_I)
+            && let (Some(prefab), Some(hit)) = (&active_prefab, hover)
+        {
+            let palette_slot = cycle_palette_slot(scene.palette.len(), &mut next_palette_slot);
+            let instance = PrefabInstance {
+                id: scene.allocate_id(),
+                prefab: prefab.name.clone(),
+                position: hit.point,
+                rotation: Vector3::zero(),
+                scale: 1.0,
+                palette_slot,
+                scatter: None,
+                parts: Vec::new(),
+            };
+            scene.instantiate(prefab, instance);
+            status_message = format!("Instancia de '{}' creada", prefab.name);
+        }
+        let transforms = scene.world_transforms();
+
+        // Los triggers y el sonido siguen a la cámara con la que se está mirando la escena
+        let listener = if playing { view_camera } else { camera };
+
+        // Triggers: ejecutar los comandos de los que detectaron una entrada o salida
+        for command in scene.update_triggers(&transforms, listener.position) {
+            let output = console.execute(
+                &command,
+                &mut ConsoleContext {
+                    scene: &mut scene,
+                    camera: &mut camera,
+                    pool: &mut pool,
+                    lesson: &mut lesson,
+                },
             );
-            
-            // Dibujar el cubo principal (usaremos el color promedio para simplicidad)
-            let avg_lighting = (front_color.r as f32 + top_color.r as f32 + right_color.r as f32) / (3.0 * 255.0);
-            let lit_cube_color = Color::new(
-                (base_cube_color.r as f32 * avg_lighting) as u8,
-                (base_cube_color.g as f32 * avg_lighting) as u8,
-                (base_cube_color.b as f32 * avg_lighting) as u8,
-                255,
+            console.print(&format!("[trigger] {}", output));
+            status_message = output;
+        }
+
+        // Lección: al cumplirse el paso actual se pasa al siguiente y se ejecutan sus comandos
+        let lesson_step = lesson.current().map(|(index, _)| index);
+        let lesson_commands = lesson.update(&LessonState {
+            scene: &scene,
+            orthographic,
+            playing,
+            selected,
+            time_scale,
+        });
+        if let Some(step) = lesson_step
+            && lesson.current().map(|(index, _)| index) != lesson_step
+        {
+            status_message =
+                if lesson.is_finished() { "Leccion completa".to_string() } else { format!("Paso {} cumplido", step + 1) };
+        }
+        for command in lesson_commands {
+            let output = console.execute(
+                &command,
+                &mut ConsoleContext {
+                    scene: &mut scene,
+                    camera: &mut camera,
+                    pool: &mut pool,
+                    lesson: &mut lesson,
+                },
             );
-            
-            d3d.draw_cube(
-                cube_position,
-                2.0, 2.0, 2.0,
-                lit_cube_color,
+            console.print(&format!("[leccion] {}", output));
+        }
+
+        // Anfitrión: enviar a los espectadores lo que se está viendo en este frame
+        if let Some(host) = host.as_mut() {
+            host.broadcast(&SharedView::capture(&scene, view_camera, time_scale, selected));
+        }
+
+        // Volumen y paneo de cada emisor según la posición de la cámara que escucha
+        if let Some(audio) = audio.as_mut() {
+            audio.update(&scene, &transforms, &listener);
+        }
+
+        // Clima: transición, partículas y relámpagos
+        weather.set_preset(scene.weather);
+        weather.update(sim_dt);
+        let weather_lights = weather.apply_to_lights(&scene.lights);
+        let fog = weather.fog(view_camera.position);
+        // Solo las luces más importantes para esta vista proyectan sombra, hasta el presupuesto.
+        // Las puntuales elegidas la hacen en todas direcciones; las demás usan la sombra plana
+        let shadowed_lights = shadow::shadowed_lights(&weather_lights, view_camera.position, shadow_budget);
+        let shadow_maps = if cube_shadows {
+            shadow::build_point_shadows(&weather_lights, &shadowed_lights, &scene, &transforms)
+        } else {
+            Vec::new()
+        };
+        let scene_lighting = LightingParams {
+            lights: &weather_lights,
+            ambient_intensity: weather.ambient(scene.ambient_intensity),
+            diffuse_intensity: scene.diffuse_intensity,
+            fog,
+            shadows: &shadow_maps,
+            shadowed: &shadowed_lights,
+            displacement_shader: displacement_shader.as_ref(),
+        };
+
+        // Exposición automática según la luz de lo que se ve; en modo foto se puede fijar a mano.
+        // Escala toda la luz antes de saturar el color
+        auto_exposure.measure(&rl, &scene, &transforms, &scene_lighting, view_camera);
+        auto_exposure.update(dt);
+        if photo.active && photo.auto_exposure {
+            photo.exposure = auto_exposure.exposure;
+        }
+        let exposure = if photo.active { photo.exposure } else { auto_exposure.exposure };
+        let lighting = LightingParams {
+            ambient_intensity: scene_lighting.ambient_intensity * exposure,
+            diffuse_intensity: scene_lighting.diffuse_intensity * exposure,
+            ..scene_lighting
+        };
+
+        // Frustum de la cámara principal para decidir qué se dibuja (en modo foto o en la presentación, el de la vista)
+        let aspect = rl.get_screen_width() as f32 / rl.get_screen_height() as f32;
+        let frustum_camera = if photo.active || playing { view_camera } else { editor_camera };
+        let frustum = Frustum::from_camera(&frustum_camera, aspect, NEAR_PLANE, view_distance);
+
+        // Etiquetas de objetos y notas proyectadas a pantalla, atenuadas si están tapadas o lejos
+        let screen_labels = if show_labels {
+            label_fader.update(&rl, &scene, &transforms, view_camera, dt)
+        } else {
+            Vec::new()
+        };
+
+        // Grupos lejanos como imposters; en modo foto siempre se usa la geometría completa
+        let draw_imposters = use_imposters && !photo.active;
+        let imposter_objects = if draw_imposters {
+            let context = BakeContext {
+                library: &library,
+                scene: &scene,
+                transforms: &transforms,
+                lighting: &lighting,
+            };
+            imposters.update(&mut rl, &thread, &context, view_camera)
+        } else {
+            HashSet::new()
+        };
+
+        // Fondo gris oscuro, teñido por la niebla y los relámpagos
+        let background = weather.background(Color::new(50, 50, 50, 255));
+
+        // Modo foto: se promedian las vistas de la lente y las capturas se hacen a mayor resolución
+        let lens_cameras = photo.lens_cameras(view_camera);
+        let screen_size = (rl.get_screen_width() as u32, rl.get_screen_height() as u32);
+        if capture_requested {
+            capture_requested = false;
+            let (size, cameras) = if photo.active {
+                let scale = photo.supersample as u32;
+                ((screen_size.0 * scale, screen_size.1 * scale), lens_cameras.as_slice())
+            } else {
+                (render_scale::scaled_size(screen_size, render_scale), std::slice::from_ref(&view_camera))
+            };
+            let result = photo_renderer
+                .render(&mut rl, &thread, size, cameras, background, &mut |d3d| {
+                    draw_photo_world(d3d, &library, &scene, &transforms, &frustum, &lighting, &weather)
+                })
+                .and_then(photo::save_capture);
+            let message = match result {
+                Ok(path) => format!("Captura de {}x{} guardada en {}", size.0, size.1, path),
+                Err(error) => format!("Error en la captura: {}", error),
+            };
+            console.print(&message);
+            status_message = message;
+        }
+        // La escena 3D va a una textura interna cuando la escala no es 100%
+        if let Err(error) = scene_target.prepare(&mut rl, &thread, screen_size, render_scale) {
+            eprintln!("No se pudo crear la textura interna: {}", error);
+            render_scale = 1.0;
+        }
+        let scene_size = if scene_target.active() {
+            render_scale::scaled_size(screen_size, render_scale)
+        } else {
+            screen_size
+        };
+
+        // Color, normales y profundidad para los reflejos; en modo foto se dibuja sin ellos
+        let draw_reflections = use_reflections && !photo.active;
+        if draw_reflections {
+            let context = ReflectionContext {
+                library: &library,
+                scene: &scene,
+                transforms: &transforms,
+                frustum: &frustum,
+                lighting: &lighting,
+                wetness: weather.wetness(),
+            };
+            if let Err(error) = reflections.render(&mut rl, &thread, &context, view_camera, background, scene_size) {
+                // Sin shaders o framebuffers se sigue sin reflejos
+                eprintln!("No se pudieron dibujar los reflejos: {}", error);
+                use_reflections = false;
+            }
+        }
+
+        if frame_capture_requested {
+            frame_capture_requested = false;
+            let context = CaptureContext {
+                library: &library,
+                scene: &scene,
+                transforms: &transforms,
+                frustum: &frustum,
+                lighting: &lighting,
+                weather: &weather,
+                far: view_distance,
+            };
+            let reflections = (draw_reflections && use_reflections).then_some(&mut reflections);
+            let result =
+                frame_capture::capture_frame(&mut rl, &thread, &context, view_camera, background, scene_size, reflections);
+            let message = match result {
+                Ok(dir) => format!("Pasadas del frame guardadas en {}", dir),
+                Err(error) => format!("Error al capturar las pasadas: {}", error),
+            };
+            console.print(&message);
+            status_message = message;
+        }
+        let photo_frame = if photo.active {
+            photo_renderer
+                .render(&mut rl, &thread, scene_size, &lens_cameras, background, &mut |d3d| {
+                    draw_photo_world(d3d, &library, &scene, &transforms, &frustum, &lighting, &weather)
+                })
+                .inspect_err(|error| eprintln!("Modo foto: {}", error))
+                .ok()
+        } else {
+            None
+        };
+
+        if show_nav_cube && let Err(error) = nav_cube.render(&mut rl, &thread, &camera) {
+            // Sin framebuffer para el cubo se sigue sin él
+            eprintln!("Cubo de navegacion: {}", error);
+            use_nav_cube = false;
+            show_nav_cube = false;
+        }
+
+        // Pasada 3D del editor, en la textura interna si la escala de render no es 100%
+        let world = WorldPass {
+            library: &library,
+            scene: &scene,
+            transforms: &transforms,
+            frustum: &frustum,
+            lighting: &lighting,
+            camera: view_camera,
+            imposter_objects: &imposter_objects,
+            imposters: draw_imposters.then_some(&imposters),
+            weather: &weather,
+            measure: &measure,
+            editing,
+            editing_paths,
+            view_from_debug,
+            show_triggers,
+            show_light_volumes,
+            selected,
+            selected_point,
+            selected_light,
+        };
+        let mut world_reflections = (draw_reflections && use_reflections).then_some(&mut reflections);
+        let mut culled_objects = 0;
+        let scene_textured = photo_frame.is_none()
+            && scene_target.render(&mut rl, &thread, background, |texture| {
+                culled_objects = draw_world(texture, &world, world_reflections.take());
+            });
+
+        // Inicio del renderizado
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(background);
+
+        if let Some(frame) = photo_frame {
+            let source = Rectangle::new(0.0, 0.0, scene_size.0 as f32, -(scene_size.1 as f32));
+            let destination = Rectangle::new(0.0, 0.0, screen_size.0 as f32, screen_size.1 as f32);
+            d.draw_texture_pro(frame.texture(), source, destination, Vector2::zero(), 0.0, Color::WHITE);
+        } else {
+            if scene_textured {
+                scene_target.present(&mut d);
+            } else {
+                culled_objects = draw_world(&mut d, &world, world_reflections);
+            }
+        }
+
+        if photo.active {
+            // Sin interfaz: solo las guías de composición y el panel de la foto
+            if photo.show_guides {
+                let (width, height) = (d.get_screen_width(), d.get_screen_height());
+                photo::draw_thirds(&mut d, width, height);
+            }
+            capture_requested |= photo.draw_panel(&mut d);
+        } else if playing {
+            // Presentación: sin ayudas de edición, solo las etiquetas, la toma actual y el botón para volver
+            ui::draw_labels(&mut d, &screen_labels);
+            let shot = player
+                .shot_info(&scene.presentation, &bookmarks)
+                .map(|(index, count)| format!("   Toma {}/{}", index + 1, count))
+                .unwrap_or_default();
+            d.draw_text(
+                &format!("Presentacion{}   P: volver al editor  Derecha: siguiente toma", shot),
+                10,
+                10,
+                18,
+                Color::RAYWHITE,
             );
+            if !status_message.is_empty() {
+                d.draw_text(&status_message, 10, d.get_screen_height() - 28, 18, Color::LIGHTGRAY);
+            }
+            play_requested |= ui::draw_play_button(&mut d, true);
+        } else {
+            ui::draw_labels(&mut d, &screen_labels);
+            if show_nav_cube {
+                nav_cube.draw(&mut d, &camera);
+            }
+            if free_camera {
+                // Mira en el centro: es el punto que usan la selección y la medición
+                let (cx, cy) = (screen_center.x as i32, screen_center.y as i32);
+                d.draw_line(cx - 8, cy, cx + 8, cy, Color::RAYWHITE);
+                d.draw_line(cx, cy - 8, cx, cy + 8, Color::RAYWHITE);
+            }
 
-            // Dibujar las aristas del cubo para mayor definición
-            d3d.draw_cube_wires(
-                cube_position,
-                2.0, 2.0, 2.0,
-                Color::BLACK,
+            // Ayuda en pantalla con los controles de depuración
+            d.draw_text("C: vista externa  F: camara libre  O: reanudar orbita  L: volumenes de luz y sonido  TAB: elegir luz  H: sombra de la luz  B: presupuesto de sombras", 10, 10, 18, Color::RAYWHITE);
+            d.draw_text("[ / ]: radio de la luz  Z / X: distancia de vista  F5: clima  - / =: velocidad del tiempo  F10: capturar pasadas  F11: reflejos  , / .: escala de render", 10, 32, 18, Color::RAYWHITE);
+            d.draw_text("F1: capas  F2: curvas  F3: triggers  F4: etiquetas  F6: foto  F7: imposters  F8: sombras  F9: seleccion  M: medir  V: orto  I: iluminacion  R: recursos  P: play  `: consola", 10, 54, 18, Color::RAYWHITE);
+            d.draw_text(
+                &format!(
+                    "Clic: seleccionar ({})  Ctrl+P: guardar prefab  Ctrl+I: instanciar  Ctrl+S: guardar  Ctrl+1..4: marcador  F12: captura",
+                    pick_backend.label()
+                ),
+                10,
+                98,
+                18,
+                Color::RAYWHITE,
+            );
+            if let Some(object) = selected.and_then(|id| scene.object(id)) {
+                d.draw_text(&format!("Seleccionado: {}", object.name), 10, 120, 18, Color::YELLOW);
+            }
+            if measure.active {
+                let result = measure.summary().unwrap_or_else(|| "elige puntos con clic".to_string());
+                d.draw_text(
+                    &format!("Medir: {}   Clic der: limpiar  N: fijar nota", result),
+                    10,
+                    164,
+                    18,
+                    Color::MAGENTA,
+                );
+            }
+            if editing_paths {
+                d.draw_text(
+                    "Arrastrar: mover punto  RePag/AvPag: altura  Insert: agregar  Supr: quitar",
+                    10,
+                    142,
+                    18,
+                    Color::SKYBLUE,
+                );
+            }
+            let light_info = scene
+                .lights
+                .get(selected_light)
+                .map(|light| {
+                    let shadow = if light.cast_shadows { "con sombra" } else { "sin sombra" };
+                    format!("Luz {}: radio {:.1}, {}   ", selected_light, light.range, shadow)
+                })
+                .unwrap_or_default();
+            d.draw_text(
+                &format!(
+                    "{}Distancia de vista: {:.1}   Descartados: {}   Dinamicos: {}   Imposters: {}   Sombras: {}/{} ({} cubo)   Tiempo: x{:.2}   Exposicion: {:.2}   Escala: {:.0}%",
+                    light_info,
+                    view_distance,
+                    culled_objects,
+                    pool.active_count(),
+                    if draw_imposters { imposters.active_count() } else { 0 },
+                    shadowed_lights.len(),
+                    shadow_budget,
+                    shadow_maps.len(),
+                    time_scale,
+                    exposure,
+                    render_scale * 100.0,
+                ),
+                10,
+                76,
+                18,
+                Color::YELLOW,
             );
+            let session = match (&host, &client) {
+                (Some(host), _) => Some(format!("Anfitrion: {} espectadores", host.viewer_count())),
+                (_, Some(viewer)) if viewer.connected => Some(format!("Espectador de {}", viewer.address)),
+                (_, Some(viewer)) => Some(format!("Conexion perdida con {}", viewer.address)),
+                _ => None,
+            };
+            if let Some(session) = session {
+                d.draw_text(&session, 10, 186, 18, Color::LIME);
+            }
+            if !status_message.is_empty() {
+                d.draw_text(&status_message, 10, d.get_screen_height() - 28, 18, Color::LIGHTGRAY);
+            }
+
+            play_requested |= ui::draw_play_button(&mut d, false);
+
+            // Juegos de luces: reemplazan las luces de la escena con un clic
+            if show_rig_panel && let Some(rig) = ui::draw_rig_panel(&mut d, 10.0, RIG_PANEL_Y, selected.is_some()) {
+                scene.apply_light_rig(rig, selected, &camera);
+                selected_light = 0;
+                status_message = format!("Iluminacion '{}': {} luces", rig.label(), scene.lights.len());
+            }
+            if show_asset_browser {
+                ui::draw_asset_browser(&mut d, &asset_browser, &scene.palette, browser_position.x, browser_position.y);
+            }
+
+            if show_layer_panel {
+                ui::draw_layer_panel(&mut d, &mut scene.layers, panel_x, 10.0);
+            }
+            if let Some(object) = selected.and_then(|id| scene.object_mut(id)) {
+                ui::draw_inspector(&mut d, object, panel_x, inspector_y);
+            }
+        }
+        if !photo.active {
+            ui::draw_lesson(&mut d, &lesson);
+        }
+        if console.open {
+            ui::draw_console(&mut d, &console);
+        }
+    }
+
+    // Al cerrar se guarda la sesión para retomarla en el próximo arranque
+    let session = Session {
+        camera,
+        free_camera,
+        orthographic,
+        holding_view: nav_cube.holding,
+        bookmarks,
+        show_layer_panel,
+        show_rig_panel,
+        show_asset_browser,
+        show_triggers,
+        show_labels,
+        show_light_volumes,
+        view_from_debug,
+        view_distance,
+        use_imposters,
+        cube_shadows,
+        shadow_budget,
+        reflections: use_reflections,
+        render_scale,
+        pick_backend,
+        selected_light,
+        next_palette_slot,
+        time_scale,
+        photo,
+    };
+    if let Err(error) = session.save(SESSION_PATH) {
+        eprintln!("No se pudo guardar la sesión: {}", error);
+    }
+}
+
+// La misma vista en proyección ortográfica. El alto visible es el que abarca la perspectiva a la
+// distancia del punto que se mira, así el zoom de la órbita sigue funcionando
+fn orthographic_view(camera: &Camera3D) -> Camera3D {
+    let height = 2.0 * camera.position.distance_to(camera.target) * (camera.fovy.to_radians() * 0.5).tan();
+    Camera3D::orthographic(camera.position, camera.target, camera.up, height)
+}
+
+// Cada instancia nueva toma el siguiente color de la paleta
+fn cycle_palette_slot(palette_len: usize, next: &mut usize) -> Option<usize> {
+    (palette_len > 0).then(|| {
+        *next = (*next + 1) % palette_len;
+        *next
+    })
+}
+
+// Lo que se ve en una foto: la escena iluminada y el clima, sin ayudas de edición
+fn draw_photo_world(
+    d3d: &mut impl RaylibDraw3D,
+    library: &ShapeLibrary,
+    scene: &Scene,
+    transforms: &HashMap<u32, Matrix>,
+    frustum: &Frustum,
+    lighting: &LightingParams,
+    weather: &Weather,
+) {
+    renderer::draw_scene(d3d, library, scene, transforms, frustum, lighting, &HashSet::new());
+    weather.draw(d3d);
+}
+
+// Lo que dibuja la pasada 3D del editor, sea en la ventana o en la textura interna
+struct WorldPass<'a> {
+    library: &'a ShapeLibrary,
+    scene: &'a Scene,
+    transforms: &'a HashMap<u32, Matrix>,
+    frustum: &'a Frustum,
+    lighting: &'a LightingParams<'a>,
+    camera: Camera3D,
+    imposter_objects: &'a HashSet<u32>,
+    imposters: Option<&'a ImposterCache>,
+    weather: &'a Weather,
+    measure: &'a MeasureTool,
+    editing: bool,
+    editing_paths: bool,
+    view_from_debug: bool,
+    show_triggers: bool,
+    show_light_volumes: bool,
+    selected: Option<u32>,
+    selected_point: Option<(usize, usize)>,
+    selected_light: usize,
+}
 
+// Escena, reflejos, clima y ayudas de edición; devuelve cuántos objetos descartó el frustum
+fn draw_world(d: &mut impl RaylibDraw, pass: &WorldPass, reflections: Option<&mut Reflections>) -> usize {
+    let scene = pass.scene;
+    let culled_objects;
+    {
+        let mut d3d = d.begin_mode3D(pass.camera);
+
+        // === OBJETOS DE LA ESCENA CON ILUMINACIÓN DIFUSA Y SOMBRAS PLANAS ===
+        let culled = renderer::draw_scene(
+            &mut d3d,
+            pass.library,
+            scene,
+            pass.transforms,
+            pass.frustum,
+            pass.lighting,
+            pass.imposter_objects,
+        );
+        culled_objects = culled.len();
+        if pass.view_from_debug && pass.editing {
+            // Marcar en rojo lo que la cámara principal está descartando
+            for (center, radius) in culled {
+                d3d.draw_sphere_wires(center, radius, 6, 10, Color::RED);
+            }
+        }
+        if let Some(imposters) = pass.imposters {
+            imposters.draw(&mut d3d, pass.camera, pass.frustum);
+        }
+    }
+
+    // Reflejos sobre la escena ya dibujada; la profundidad se conserva y las ayudas de edición que
+    // siguen se dibujan encima sin reflejarse
+    if let Some(reflections) = reflections {
+        reflections.draw(d);
+    }
+    let mut d3d = d.begin_mode3D(pass.camera);
+
+    // Ayudas de edición; en la presentación no se dibujan
+    if pass.editing {
+        // Curvas que recorren los objetos; los puntos de control solo al editar
+        for (index, spline) in scene.paths.iter().enumerate() {
+            let selected_index = pass
+                .selected_point
+                .filter(|(path_index, _)| *path_index == index)
+                .map(|(_, point)| point);
+            debug_draw::draw_path(&mut d3d, spline, Color::LIGHTGRAY, pass.editing_paths, selected_index);
+        }
+
+        // Puntos medidos y marcadores de las notas
+        pass.measure.draw(&mut d3d);
+        for annotation in &scene.annotations {
+            d3d.draw_sphere(annotation.position, 0.05, Color::GOLD);
+        }
+
+        // Resaltar el subárbol seleccionado
+        if let Some(id) = pass.selected {
+            for child in scene.subtree(id) {
+                if let Some(model) = pass.transforms.get(&child) {
+                    renderer::draw_box_edges(&mut d3d, *model, Color::YELLOW);
+                }
+            }
+        }
+    }
+
+    // Lluvia o nieve
+    pass.weather.draw(&mut d3d);
+
+    // === DEPURACIÓN: FRUSTUM Y VOLÚMENES DE LUZ ===
+    if pass.editing && scene.layer_visible("debug") {
+        if pass.view_from_debug {
+            debug_draw::draw_frustum(&mut d3d, pass.frustum, Color::YELLOW);
+        }
+        if pass.show_triggers {
+            for trigger in &scene.triggers {
+                let color = if trigger.occupied { Color::LIME } else { Color::GRAY };
+                let size = trigger.size;
+                d3d.draw_cube_wires(trigger.position, size.x, size.y, size.z, color);
+            }
+        }
+        // Caminos calculados por los agentes que van hacia un destino
+        for object in &scene.objects {
+            for behavior in &object.behaviors {
+                if let Behavior::Agent { route, .. } = behavior
+                    && !route.is_empty()
+                {
+                    debug_draw::draw_route(&mut d3d, object.position, route, Color::LIME);
+                }
+            }
+        }
+        if pass.show_light_volumes {
+            for (index, light) in scene.lights.iter().enumerate() {
+                let color = if index == pass.selected_light { Color::ORANGE } else { Color::SKYBLUE };
+                debug_draw::draw_light_volume(&mut d3d, light, color);
+            }
+            for emitter in &scene.emitters {
+                let position = scene.emitter_position(emitter, pass.transforms);
+                debug_draw::draw_audio_emitter(&mut d3d, emitter, position, Color::VIOLET);
+            }
         }
     }
+    culled_objects
 }
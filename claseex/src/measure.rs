@@ -0,0 +1,87 @@
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::scene::{vec3_from_json, vec3_to_json};
+
+// Nota de texto fijada en un punto del diorama
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    pub position: Vector3,
+    pub text: String,
+}
+
+// Herramienta de medición: dos puntos dan una distancia, tres un ángulo en el punto del medio
+#[derive(Clone, Debug, Default)]
+pub struct MeasureTool {
+    pub active: bool,
+    pub points: Vec<Vector3>,
+}
+
+impl MeasureTool {
+    // Agrega un punto; al cuarto clic empieza una medición nueva
+    pub fn add_point(&mut self, point: Vector3) {
+        if self.points.len() >= 3 {
+            self.points.clear();
+        }
+        self.points.push(point);
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub fn distance(&self) -> Option<f32> {
+        match self.points.as_slice() {
+            [a, b, ..] => Some(a.distance_to(*b)),
+            _ => None,
+        }
+    }
+
+    // Ángulo en grados entre los tramos que salen del segundo punto
+    pub fn angle(&self) -> Option<f32> {
+        let [a, b, c] = self.points.as_slice() else {
+            return None;
+        };
+        let (first, second) = (*a - *b, *c - *b);
+        if first.length() < 1e-5 || second.length() < 1e-5 {
+            return None;
+        }
+        let cosine = first.normalized().dot(second.normalized()).clamp(-1.0, 1.0);
+        Some(cosine.acos().to_degrees())
+    }
+
+    // Texto con el resultado de la medición actual
+    pub fn summary(&self) -> Option<String> {
+        match (self.distance(), self.angle()) {
+            (Some(_), Some(angle)) => {
+                let second = self.points[1].distance_to(self.points[2]);
+                Some(format!("{:.2} / {:.2} u, {:.1} grados", self.distance()?, second, angle))
+            }
+            (Some(distance), None) => Some(format!("{:.2} u", distance)),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, d3d: &mut impl RaylibDraw3D) {
+        for point in &self.points {
+            d3d.draw_sphere(*point, 0.06, Color::MAGENTA);
+        }
+        for pair in self.points.windows(2) {
+            d3d.draw_line_3D(pair[0], pair[1], Color::MAGENTA);
+        }
+    }
+}
+
+pub fn annotation_from_json(value: &Value) -> Option<Annotation> {
+    Some(Annotation {
+        position: vec3_from_json(&value["position"])?,
+        text: value["text"].as_str()?.to_string(),
+    })
+}
+
+pub fn annotation_to_json(annotation: &Annotation) -> Value {
+    json!({
+        "position": vec3_to_json(annotation.position),
+        "text": annotation.text,
+    })
+}
@@ -0,0 +1,201 @@
+use raylib::prelude::*;
+
+use crate::picking;
+use crate::scene::Shape;
+
+// Lado en pixeles del cubo de navegación en la esquina
+const SIZE: u32 = 120;
+const MARGIN: f32 = 10.0;
+// Duración de la transición hacia la vista elegida, en segundos
+const SNAP_TIME: f32 = 0.35;
+// Parte de cada coordenada del impacto que cuenta como cara; lo de afuera es arista o esquina
+const FACE_ZONE: f32 = 0.3;
+
+// Transición de la cámara alrededor de su objetivo, a distancia constante
+struct Transition {
+    from: Vector3,
+    to: Vector3,
+    distance: f32,
+    elapsed: f32,
+}
+
+// Cubo de orientación al estilo de los programas de CAD: gira con la cámara y al hacer clic
+// en una cara, arista o esquina lleva la vista a la dirección alineada con los ejes
+#[derive(Default)]
+pub struct NavCube {
+    texture: Option<RenderTexture2D>,
+    transition: Option<Transition>,
+    hovered: Option<Vector3>, // Dirección (componentes -1, 0 o 1) bajo el mouse
+    pub holding: bool,        // La vista elegida se mantiene hasta reanudar la órbita
+}
+
+// Área en pantalla del cubo, en la esquina inferior derecha
+pub fn bounds(screen_width: i32, screen_height: i32) -> Rectangle {
+    Rectangle::new(
+        screen_width as f32 - SIZE as f32 - MARGIN,
+        screen_height as f32 - SIZE as f32 - MARGIN,
+        SIZE as f32,
+        SIZE as f32,
+    )
+}
+
+// Cámara del cubo: mira al origen desde la misma dirección que la cámara principal
+fn cube_camera(camera: &Camera3D) -> Camera3D {
+    let direction = (camera.position - camera.target).normalized();
+    Camera3D::perspective(direction * 3.2, Vector3::zero(), camera.up, 30.0)
+}
+
+// Interpolación esférica entre dos direcciones unitarias
+fn slerp(from: Vector3, to: Vector3, t: f32) -> Vector3 {
+    let angle = from.dot(to).clamp(-1.0, 1.0).acos();
+    if angle < 1e-3 {
+        return to;
+    }
+    // Opuestas: se pasa por una dirección perpendicular cualquiera
+    if angle > std::f32::consts::PI - 1e-3 {
+        let side = from.cross(Vector3::new(0.0, 1.0, 0.0));
+        let side = if side.length() < 1e-3 { Vector3::new(1.0, 0.0, 0.0) } else { side.normalized() };
+        return if t < 0.5 { slerp(from, side, t * 2.0) } else { slerp(side, to, t * 2.0 - 1.0) };
+    }
+    (from * ((1.0 - t) * angle).sin() + to * (t * angle).sin()) / angle.sin()
+}
+
+// Cara (un eje), arista (dos) o esquina (tres) según dónde tocó el rayo al cubo de lado 1
+fn snap_direction(point: Vector3) -> Vector3 {
+    let snap = |c: f32| if c.abs() > FACE_ZONE { c.signum() } else { 0.0 };
+    Vector3::new(snap(point.x), snap(point.y), snap(point.z))
+}
+
+fn face_color(normal: Vector3) -> Color {
+    match (normal.x as i32, normal.y as i32, normal.z as i32) {
+        (1, _, _) => Color::new(200, 70, 70, 255),
+        (-1, _, _) => Color::new(120, 45, 45, 255),
+        (_, 1, _) => Color::new(70, 180, 80, 255),
+        (_, -1, _) => Color::new(40, 100, 50, 255),
+        (_, _, 1) => Color::new(70, 100, 210, 255),
+        _ => Color::new(40, 60, 120, 255),
+    }
+}
+
+const FACE_LABELS: [(Vector3, &str); 6] = [
+    (Vector3 { x: 1.0, y: 0.0, z: 0.0 }, "Der"),
+    (Vector3 { x: -1.0, y: 0.0, z: 0.0 }, "Izq"),
+    (Vector3 { x: 0.0, y: 1.0, z: 0.0 }, "Arriba"),
+    (Vector3 { x: 0.0, y: -1.0, z: 0.0 }, "Abajo"),
+    (Vector3 { x: 0.0, y: 0.0, z: 1.0 }, "Frente"),
+    (Vector3 { x: 0.0, y: 0.0, z: -1.0 }, "Atras"),
+];
+
+impl NavCube {
+    // Mientras dura la transición o se mantiene la vista, la órbita automática no mueve la cámara
+    pub fn holds_view(&self) -> bool {
+        self.transition.is_some() || self.holding
+    }
+
+    // Detecta la zona bajo el mouse, inicia la transición con un clic y la hace avanzar.
+    // Devuelve true si el mouse está sobre el cubo
+    pub fn update(&mut self, rl: &RaylibHandle, camera: &mut Camera3D, dt: f32) -> bool {
+        let area = bounds(rl.get_screen_width(), rl.get_screen_height());
+        let mouse = rl.get_mouse_position();
+        let over = area.check_collision_point_rec(mouse);
+
+        self.hovered = None;
+        if over {
+            let local = Vector2::new(mouse.x - area.x, mouse.y - area.y);
+            let ray = rl.get_screen_to_world_ray_ex(local, cube_camera(camera), SIZE as i32, SIZE as i32);
+            if let Some(t) = picking::intersect_local(Shape::Cube, ray.position, ray.direction) {
+                self.hovered = Some(snap_direction(ray.position + ray.direction * t));
+            }
+        }
+        if let Some(direction) = self.hovered
+            && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            // Arriba y abajo puros dejarían el vector up paralelo a la vista: se inclina apenas
+            let mut target = direction.normalized();
+            if target.x == 0.0 && target.z == 0.0 {
+                target = Vector3::new(0.0, target.y, 0.01).normalized();
+            }
+            self.transition = Some(Transition {
+                from: (camera.position - camera.target).normalized(),
+                to: target,
+                distance: camera.position.distance_to(camera.target),
+                elapsed: 0.0,
+            });
+            self.holding = true;
+        }
+
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += dt;
+            let t = (transition.elapsed / SNAP_TIME).min(1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+            camera.position = camera.target + slerp(transition.from, transition.to, eased) * transition.distance;
+            if t >= 1.0 {
+                self.transition = None;
+            }
+        }
+        over
+    }
+
+    // Dibuja el cubo orientado como la cámara en su textura; va antes de begin_drawing
+    pub fn render(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, camera: &Camera3D) -> Result<(), String> {
+        let mut texture = match self.texture.take() {
+            Some(texture) => texture,
+            None => rl.load_render_texture(thread, SIZE, SIZE).map_err(|e| e.to_string())?,
+        };
+        {
+            let mut target = rl.begin_texture_mode(thread, &mut texture);
+            target.clear_background(Color::BLANK);
+            let mut d3d = target.begin_mode3D(cube_camera(camera));
+            for (normal, _) in FACE_LABELS {
+                let center = normal * 0.5;
+                let size = Vector3::new(
+                    if normal.x != 0.0 { 0.02 } else { 0.98 },
+                    if normal.y != 0.0 { 0.02 } else { 0.98 },
+                    if normal.z != 0.0 { 0.02 } else { 0.98 },
+                );
+                d3d.draw_cube_v(center - normal * 0.01, size, face_color(normal));
+            }
+            d3d.draw_cube_wires(Vector3::zero(), 1.0, 1.0, 1.0, Color::RAYWHITE);
+            // Zona bajo el mouse: la cara, arista o esquina a la que se va a llevar la vista
+            if let Some(direction) = self.hovered {
+                d3d.draw_cube_v(direction * 0.5, Vector3::new(0.3, 0.3, 0.3), Color::YELLOW);
+            }
+        }
+        self.texture = Some(texture);
+        Ok(())
+    }
+
+    // Copia la textura a la esquina con el nombre de las caras que miran a la cámara
+    pub fn draw(&self, d: &mut RaylibDrawHandle, camera: &Camera3D) {
+        let Some(texture) = &self.texture else {
+            return;
+        };
+        let area = bounds(d.get_screen_width(), d.get_screen_height());
+        d.draw_circle(
+            (area.x + area.width * 0.5) as i32,
+            (area.y + area.height * 0.5) as i32,
+            area.width * 0.48,
+            Color::new(0, 0, 0, 90),
+        );
+        // La textura de render queda invertida en Y
+        let source = Rectangle::new(0.0, 0.0, SIZE as f32, -(SIZE as f32));
+        d.draw_texture_rec(texture, source, Vector2::new(area.x, area.y), Color::WHITE);
+
+        let cube = cube_camera(camera);
+        let view = (cube.position - cube.target).normalized();
+        for (normal, label) in FACE_LABELS {
+            if normal.dot(view) < 0.3 {
+                continue;
+            }
+            let position = d.get_world_to_screen_ex(normal * 0.5, cube, SIZE as i32, SIZE as i32);
+            let width = d.measure_text(label, 10);
+            d.draw_text(
+                label,
+                (area.x + position.x) as i32 - width / 2,
+                (area.y + position.y) as i32 - 5,
+                10,
+                Color::RAYWHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use raylib::prelude::*;
+
+use crate::scene::{Scene, Shape};
+use crate::spatial::SpatialIndex;
+
+// Lado de cada celda de navegación
+const CELL_SIZE: f32 = 0.25;
+// Tope de celdas por lado; en un suelo muy grande las celdas crecen
+const MAX_CELLS: usize = 200;
+// Costos de un paso recto y en diagonal (≈ 10·√2), en enteros para el montículo
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+// Grilla de celdas libres u ocupadas sobre el suelo, a la altura de un agente
+pub struct NavGrid {
+    origin: Vector3, // Esquina de la celda (0, 0)
+    cell: f32,
+    columns: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    // Cubre los planos horizontales sin padre que quedan por debajo de `height`; fuera de ellos no se
+    // camina. Se bloquean las celdas donde un agente de radio `radius` tocaría algún objeto a esa
+    // altura, salvo las partes del propio agente (`ignore` es su raíz)
+    pub fn build(scene: &Scene, index: &SpatialIndex, height: f32, radius: f32, ignore: u32) -> Option<NavGrid> {
+        let floors: Vec<(Vector3, Vector3)> = scene
+            .objects
+            .iter()
+            .filter(|object| {
+                object.shape == Shape::Plane
+                    && object.parent.is_none()
+                    && object.rotation == Vector3::zero()
+                    && object.position.y <= height
+                    && scene.layer_visible(&object.layer)
+            })
+            .map(|object| (object.position - object.size * 0.5, object.position + object.size * 0.5))
+            .collect();
+        let min = floors.iter().map(|(min, _)| *min).reduce(|a, b| Vector3::new(a.x.min(b.x), 0.0, a.z.min(b.z)))?;
+        let max = floors.iter().map(|(_, max)| *max).reduce(|a, b| Vector3::new(a.x.max(b.x), 0.0, a.z.max(b.z)))?;
+
+        let cell = CELL_SIZE.max((max.x - min.x).max(max.z - min.z) / MAX_CELLS as f32);
+        let columns = (((max.x - min.x) / cell).ceil() as usize).max(1);
+        let rows = (((max.z - min.z) / cell).ceil() as usize).max(1);
+        let mut grid = NavGrid {
+            origin: Vector3::new(min.x, height, min.z),
+            cell,
+            columns,
+            rows,
+            blocked: Vec::with_capacity(columns * rows),
+        };
+        for row in 0..rows {
+            for column in 0..columns {
+                let center = grid.center(column, row);
+                let on_floor = floors
+                    .iter()
+                    .any(|(min, max)| (min.x..=max.x).contains(&center.x) && (min.z..=max.z).contains(&center.z));
+                let occupied = index
+                    .query(center, radius)
+                    .iter()
+                    .any(|entry| entry.root != ignore && (entry.center.y - height).abs() <= entry.radius + radius);
+                grid.blocked.push(!on_floor || occupied);
+            }
+        }
+        Some(grid)
+    }
+
+    fn center(&self, column: usize, row: usize) -> Vector3 {
+        self.origin + Vector3::new((column as f32 + 0.5) * self.cell, 0.0, (row as f32 + 0.5) * self.cell)
+    }
+
+    fn is_free(&self, column: i64, row: i64) -> bool {
+        column >= 0
+            && row >= 0
+            && (column as usize) < self.columns
+            && (row as usize) < self.rows
+            && !self.blocked[row as usize * self.columns + column as usize]
+    }
+
+    fn cell_at(&self, point: Vector3) -> (i64, i64) {
+        (
+            ((point.x - self.origin.x) / self.cell).floor() as i64,
+            ((point.z - self.origin.z) / self.cell).floor() as i64,
+        )
+    }
+
+    // Celda libre más cercana al punto, por si quedó pegado a un obstáculo o fuera del suelo
+    fn nearest_free(&self, point: Vector3) -> Option<usize> {
+        let (column, row) = self.cell_at(point);
+        if self.is_free(column, row) {
+            return Some(row as usize * self.columns + column as usize);
+        }
+        (0..self.blocked.len())
+            .filter(|index| !self.blocked[*index])
+            .min_by(|a, b| {
+                let distance = |index: &usize| {
+                    self.center(index % self.columns, index / self.columns).distance_to(Vector3::new(point.x, self.origin.y, point.z))
+                };
+                distance(a).total_cmp(&distance(b))
+            })
+    }
+
+    // true si el segmento entre los dos puntos pasa solo por celdas libres
+    fn line_of_sight(&self, from: Vector3, to: Vector3) -> bool {
+        let steps = ((from.distance_to(to) / (self.cell * 0.5)).ceil() as usize).max(1);
+        (0..=steps).all(|step| {
+            let (column, row) = self.cell_at(from.lerp(to, step as f32 / steps as f32));
+            self.is_free(column, row)
+        })
+    }
+
+    // A* entre las celdas de los dos puntos, con pasos en diagonal que no cortan esquinas. Devuelve los
+    // puntos de paso ya simplificados (sin el de partida), o None si no hay camino
+    pub fn find_path(&self, start: Vector3, goal: Vector3) -> Option<Vec<Vector3>> {
+        let start_index = self.nearest_free(start)?;
+        let goal_index = self.nearest_free(goal)?;
+        let position = |index: usize| ((index % self.columns) as i64, (index / self.columns) as i64);
+        let (goal_column, goal_row) = position(goal_index);
+        // Distancia octil: lo justo con pasos rectos y diagonales
+        let heuristic = |index: usize| {
+            let (column, row) = position(index);
+            let (dx, dz) = ((column - goal_column).unsigned_abs() as u32, (row - goal_row).unsigned_abs() as u32);
+            STRAIGHT_COST * dx.max(dz) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dz)
+        };
+
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut came_from: HashMap<usize, usize> = HashMap::new();
+        let mut closed = vec![false; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[start_index] = 0;
+        open.push(Reverse((heuristic(start_index), start_index)));
+        while let Some(Reverse((_, current))) = open.pop() {
+            if current == goal_index {
+                break;
+            }
+            if closed[current] {
+                continue;
+            }
+            closed[current] = true;
+            let (column, row) = position(current);
+            for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let (next_column, next_row) = (column + dx, row + dz);
+                if !self.is_free(next_column, next_row) {
+                    continue;
+                }
+                let diagonal = dx != 0 && dz != 0;
+                if diagonal && !(self.is_free(column + dx, row) && self.is_free(column, row + dz)) {
+                    continue;
+                }
+                let next = next_row as usize * self.columns + next_column as usize;
+                let next_cost = cost[current] + if diagonal { DIAGONAL_COST } else { STRAIGHT_COST };
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    came_from.insert(next, current);
+                    open.push(Reverse((next_cost + heuristic(next), next)));
+                }
+            }
+        }
+        if cost[goal_index] == u32::MAX {
+            return None;
+        }
+
+        let mut cells = vec![goal_index];
+        while let Some(previous) = came_from.get(cells.last()?) {
+            cells.push(*previous);
+        }
+        cells.reverse();
+        let mut points: Vec<Vector3> = cells
+            .into_iter()
+            .map(|index| self.center(index % self.columns, index / self.columns))
+            .collect();
+        // Si el destino mismo está libre se termina justo ahí y no en el centro de su celda
+        let (column, row) = self.cell_at(goal);
+        if self.is_free(column, row)
+            && let Some(last) = points.last_mut()
+        {
+            *last = Vector3::new(goal.x, self.origin.y, goal.z);
+        }
+
+        // Se saltean los puntos intermedios que se ven en línea recta desde el último que quedó
+        let mut anchor = Vector3::new(start.x, self.origin.y, start.z);
+        let mut route = Vec::new();
+        let mut index = 0;
+        while index < points.len() {
+            let mut farthest = index;
+            while farthest + 1 < points.len() && self.line_of_sight(anchor, points[farthest + 1]) {
+                farthest += 1;
+            }
+            anchor = points[farthest];
+            route.push(anchor);
+            index = farthest + 1;
+        }
+        Some(route)
+    }
+}
+
+impl Scene {
+    // Puntos de paso para que el agente `id` llegue a `goal` rodeando los obstáculos, a su altura
+    pub fn plan_route(&self, id: u32, goal: Vector3) -> Option<Vec<Vector3>> {
+        let agent = self.object(id)?;
+        let index = SpatialIndex::build(self, &self.world_transforms());
+        let grid = NavGrid::build(self, &index, agent.position.y, agent.bounding_radius(), id)?;
+        grid.find_path(agent.position, goal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Grilla de celdas de 1 x 1 desde el origen; cada fila del mapa es una fila en z y '#' está ocupada
+    fn grid(map: &[&str]) -> NavGrid {
+        NavGrid {
+            origin: Vector3::zero(),
+            cell: 1.0,
+            columns: map[0].len(),
+            rows: map.len(),
+            blocked: map.iter().flat_map(|row| row.chars().map(|cell| cell == '#')).collect(),
+        }
+    }
+
+    #[test]
+    fn path_goes_around_the_wall() {
+        let grid = grid(&[
+            ".....", //
+            ".....",
+            "###.#",
+            ".....",
+            ".....",
+        ]);
+        let (start, goal) = (Vector3::new(0.5, 0.0, 0.5), Vector3::new(0.5, 0.0, 4.5));
+        let route = grid.find_path(start, goal).expect("hay un hueco en la pared");
+        assert_eq!(route.last(), Some(&goal));
+        // Cada tramo recto pasa solo por celdas libres: la única forma de cruzar es por el hueco
+        let mut from = start;
+        for point in &route {
+            assert!(grid.line_of_sight(from, *point), "{:?} -> {:?} atraviesa la pared", from, point);
+            from = *point;
+        }
+    }
+
+    #[test]
+    fn no_path_through_a_closed_wall() {
+        let grid = grid(&[
+            ".....", //
+            "#####",
+            ".....",
+        ]);
+        assert!(grid.find_path(Vector3::new(0.5, 0.0, 0.5), Vector3::new(4.5, 0.0, 2.5)).is_none());
+    }
+
+    #[test]
+    fn diagonal_steps_do_not_cut_corners() {
+        let grid = grid(&[
+            ".#", //
+            "#.",
+        ]);
+        assert!(grid.find_path(Vector3::new(0.5, 0.0, 0.5), Vector3::new(1.5, 0.0, 1.5)).is_none());
+    }
+}
@@ -0,0 +1,191 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::scene::{camera_from_json, camera_to_json, f32_to_json, vec3_from_json, vec3_to_json, Scene};
+use crate::weather::WeatherPreset;
+
+pub const DEFAULT_PORT: u16 = 7777;
+// Si un espectador acumula más datos sin leer que esto, se lo desconecta
+const MAX_PENDING_BYTES: usize = 256 * 1024;
+
+// Estado que el anfitrión comparte con los espectadores
+#[derive(Clone, Debug)]
+pub struct SharedView {
+    pub camera: Camera3D,
+    pub time_scale: f32,
+    pub selected: Option<u32>,
+    pub weather: WeatherPreset,
+    pub objects: Vec<(u32, Vector3, Vector3)>, // id, posición y rotación
+}
+
+impl SharedView {
+    pub fn capture(scene: &Scene, camera: Camera3D, time_scale: f32, selected: Option<u32>) -> Self {
+        SharedView {
+            camera,
+            time_scale,
+            selected,
+            weather: scene.weather,
+            objects: scene
+                .objects
+                .iter()
+                .map(|object| (object.id, object.position, object.rotation))
+                .collect(),
+        }
+    }
+
+    // Lleva a la escena local las posiciones y el clima del anfitrión
+    pub fn apply(&self, scene: &mut Scene) {
+        scene.weather = self.weather;
+        for (id, position, rotation) in &self.objects {
+            if let Some(object) = scene.object_mut(*id) {
+                object.position = *position;
+                object.rotation = *rotation;
+            }
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "camera": camera_to_json(&self.camera),
+            "time_scale": f32_to_json(self.time_scale),
+            "selected": self.selected,
+            "weather": self.weather.name(),
+            "objects": self.objects.iter().map(|(id, position, rotation)| json!([
+                id,
+                vec3_to_json(*position),
+                vec3_to_json(*rotation),
+            ])).collect::<Vec<_>>(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<SharedView> {
+        Some(SharedView {
+            camera: camera_from_json(&value["camera"])?,
+            time_scale: value["time_scale"].as_f64().unwrap_or(1.0) as f32,
+            selected: value["selected"].as_u64().map(|id| id as u32),
+            weather: value["weather"]
+                .as_str()
+                .and_then(WeatherPreset::from_name)
+                .unwrap_or(WeatherPreset::Clear),
+            objects: value["objects"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| {
+                    Some((
+                        entry[0].as_u64()? as u32,
+                        vec3_from_json(&entry[1])?,
+                        vec3_from_json(&entry[2])?,
+                    ))
+                })
+                .collect(),
+        })
+    }
+}
+
+// Espectador conectado con los bytes que todavía no se le pudieron enviar
+struct Viewer {
+    stream: TcpStream,
+    pending: Vec<u8>,
+}
+
+// Anfitrión: acepta espectadores y les envía el estado como líneas de JSON
+pub struct Host {
+    listener: TcpListener,
+    viewers: Vec<Viewer>,
+}
+
+impl Host {
+    pub fn bind(port: u16) -> std::io::Result<Host> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Host {
+            listener,
+            viewers: Vec::new(),
+        })
+    }
+
+    pub fn viewer_count(&self) -> usize {
+        self.viewers.len()
+    }
+
+    pub fn broadcast(&mut self, view: &SharedView) {
+        while let Ok((stream, address)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                println!("Espectador conectado desde {}", address);
+                let _ = stream.set_nodelay(true);
+                self.viewers.push(Viewer {
+                    stream,
+                    pending: Vec::new(),
+                });
+            }
+        }
+
+        let mut line = view.to_json().to_string();
+        line.push('\n');
+        self.viewers.retain_mut(|viewer| {
+            viewer.pending.extend_from_slice(line.as_bytes());
+            // Se escribe lo que acepte el socket sin bloquear; el resto queda para el próximo envío
+            while !viewer.pending.is_empty() {
+                match viewer.stream.write(&viewer.pending) {
+                    Ok(0) => return false,
+                    Ok(written) => {
+                        viewer.pending.drain(..written);
+                    }
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                    Err(_) => return false,
+                }
+            }
+            viewer.pending.len() < MAX_PENDING_BYTES
+        });
+    }
+}
+
+// Espectador: recibe las líneas del anfitrión y se queda con la más reciente
+pub struct Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+    pub address: String,
+    pub connected: bool,
+}
+
+impl Client {
+    pub fn connect(address: &str) -> std::io::Result<Client> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nonblocking(true)?;
+        Ok(Client {
+            stream,
+            buffer: Vec::new(),
+            address: address.to_string(),
+            connected: true,
+        })
+    }
+
+    pub fn poll(&mut self) -> Option<SharedView> {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    self.connected = false;
+                    break;
+                }
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+
+        // Solo interesa la última línea completa; las anteriores ya quedaron viejas
+        let end = self.buffer.iter().rposition(|byte| *byte == b'\n')?;
+        let lines: Vec<u8> = self.buffer.drain(..=end).collect();
+        let text = String::from_utf8_lossy(&lines);
+        let last = text.lines().rev().find(|line| !line.trim().is_empty())?;
+        serde_json::from_str(last).ok().as_ref().and_then(SharedView::from_json)
+    }
+}
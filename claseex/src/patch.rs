@@ -0,0 +1,553 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::scene::Scene;
+
+// Cómo se identifica cada elemento de una sección del archivo de escena
+#[derive(Clone, Copy)]
+enum SectionKey {
+    Field(&'static str), // Por un campo propio (id o nombre)
+    Index,               // Por su posición en el arreglo
+    Whole,               // Por su contenido completo; solo se agregan o se quitan
+}
+
+const SECTIONS: [(&str, SectionKey); 9] = [
+    ("objects", SectionKey::Field("id")),
+    ("layers", SectionKey::Field("name")),
+    ("paths", SectionKey::Field("name")),
+    ("triggers", SectionKey::Field("name")),
+    ("audio", SectionKey::Field("name")),
+    ("lights", SectionKey::Index),
+    ("palette", SectionKey::Index),
+    ("annotations", SectionKey::Whole),
+    ("prefabs", SectionKey::Whole),
+];
+
+// Valores sueltos del nivel superior; en el parche van en la sección "scene"
+const SCALARS: [&str; 5] = ["ambient_intensity", "diffuse_intensity", "weather", "lod", "presentation"];
+
+// Un cambio individual; `from` permite detectar si otra persona tocó el mismo valor
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Set {
+        section: String,
+        key: Value,
+        field: Option<String>,
+        from: Value,
+        to: Value,
+    },
+    Add {
+        section: String,
+        key: Value,
+        value: Value,
+    },
+    Remove {
+        section: String,
+        key: Value,
+        value: Value,
+    },
+}
+
+// Diferencia entre el archivo cargado y el estado actual de la escena
+#[derive(Clone, Debug, Default)]
+pub struct Patch {
+    pub changes: Vec<Change>,
+}
+
+fn entries(section: &Value, key: SectionKey) -> Vec<(Value, Value)> {
+    section
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, value)| {
+            let key = match key {
+                SectionKey::Field(field) => value[field].clone(),
+                SectionKey::Index => json!(index),
+                SectionKey::Whole => value.clone(),
+            };
+            (key, value.clone())
+        })
+        .collect()
+}
+
+// Campos que cambian solos cada frame (objetos que recorren curvas o con comportamientos)
+// y no cuentan como edición
+fn is_volatile(section: &str, entry: &Value, field: &str) -> bool {
+    let animated = entry.get("follow").is_some() || entry.get("behaviors").is_some();
+    section == "objects" && animated && (field == "position" || field == "rotation")
+}
+
+// Valor a comparar: del recorrido sobre la curva y de los comportamientos se ignora lo avanzado hasta ahora
+fn comparable(field: Option<&str>, value: &Value) -> Value {
+    let mut value = value.clone();
+    match field {
+        Some("follow") => {
+            if let Some(follow) = value.as_object_mut() {
+                follow.remove("distance");
+            }
+        }
+        Some("behaviors") => {
+            for behavior in value.as_array_mut().into_iter().flatten().filter_map(Value::as_object_mut) {
+                behavior.remove("time");
+                behavior.remove("angle");
+            }
+        }
+        _ => {}
+    }
+    value
+}
+
+// Elemento sin sus campos volátiles, para decidir si dos copias coinciden
+fn stable(section: &str, entry: &Value) -> Value {
+    let mut stable = entry.clone();
+    if let Some(fields) = stable.as_object_mut() {
+        for (name, value) in fields.iter_mut() {
+            *value = comparable(Some(name), value);
+        }
+        fields.retain(|name, _| !is_volatile(section, entry, name));
+    }
+    stable
+}
+
+fn diff_fields(section: &str, key: &Value, base: &Value, current: &Value, changes: &mut Vec<Change>) {
+    let (Some(base_fields), Some(current_fields)) = (base.as_object(), current.as_object()) else {
+        if base == current {
+            return;
+        }
+        changes.push(Change::Set {
+            section: section.to_string(),
+            key: key.clone(),
+            field: None,
+            from: base.clone(),
+            to: current.clone(),
+        });
+        return;
+    };
+
+    let mut names: Vec<&String> = base_fields.keys().chain(current_fields.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        if is_volatile(section, current, name) {
+            continue;
+        }
+        let from = base_fields.get(name).cloned().unwrap_or(Value::Null);
+        let to = current_fields.get(name).cloned().unwrap_or(Value::Null);
+        if comparable(Some(name), &from) != comparable(Some(name), &to) {
+            changes.push(Change::Set {
+                section: section.to_string(),
+                key: key.clone(),
+                field: Some(name.clone()),
+                from,
+                to,
+            });
+        }
+    }
+}
+
+impl Patch {
+    pub fn diff(base: &Value, current: &Value) -> Patch {
+        let mut changes = Vec::new();
+
+        for name in SCALARS {
+            if base[name] != current[name] {
+                changes.push(Change::Set {
+                    section: "scene".to_string(),
+                    key: Value::Null,
+                    field: Some(name.to_string()),
+                    from: base[name].clone(),
+                    to: current[name].clone(),
+                });
+            }
+        }
+
+        for (section, key) in SECTIONS {
+            let before = entries(&base[section], key);
+            let after = entries(&current[section], key);
+
+            for (entry_key, value) in &after {
+                match before.iter().find(|(other, _)| other == entry_key) {
+                    Some((_, old)) => diff_fields(section, entry_key, old, value, &mut changes),
+                    None => changes.push(Change::Add {
+                        section: section.to_string(),
+                        key: entry_key.clone(),
+                        value: value.clone(),
+                    }),
+                }
+            }
+            for (entry_key, value) in &before {
+                if !after.iter().any(|(other, _)| other == entry_key) {
+                    changes.push(Change::Remove {
+                        section: section.to_string(),
+                        key: entry_key.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+
+        Patch { changes }
+    }
+
+    // Aplica el parche sobre el JSON de una escena; devuelve los conflictos que no se aplicaron.
+    // Los objetos nuevos que chocan con otro de la escena reciben ids desde `first_free_id`
+    pub fn apply(&self, target: &mut Value, first_free_id: u64) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        let renamed = self.renamed_objects(target, first_free_id);
+
+        for change in &self.changes {
+            match change {
+                Change::Set {
+                    section,
+                    key,
+                    field,
+                    from,
+                    to,
+                } => {
+                    let Some(entry) = find_entry(target, section, key) else {
+                        conflicts.push(format!("{} {}: ya no existe", section, key));
+                        continue;
+                    };
+                    let slot = match field {
+                        Some(field) if entry.is_object() => &mut entry[field.as_str()],
+                        Some(_) => {
+                            conflicts.push(format!("{} {}: formato distinto", section, key));
+                            continue;
+                        }
+                        None => entry,
+                    };
+                    // Un objeto que pasa a colgar de uno agregado en este parche sigue a su id nuevo
+                    let to = match field.as_deref() {
+                        Some("parent") if section == "objects" => &renumbered(to, &renamed),
+                        _ => to,
+                    };
+                    // Solo se pisa el valor si nadie más lo cambió desde el archivo original
+                    let current = comparable(field.as_deref(), slot);
+                    if current == comparable(field.as_deref(), from) || current == comparable(field.as_deref(), to) {
+                        *slot = to.clone();
+                    } else {
+                        let field = field.as_deref().unwrap_or("valor");
+                        conflicts.push(format!("{} {} '{}': editado en ambas copias", section, key, field));
+                    }
+                }
+                Change::Add { section, key, value } => {
+                    let (key, value) = if section == "objects" {
+                        let mut value = value.clone();
+                        value["id"] = renumbered(&value["id"], &renamed);
+                        if value.get("parent").is_some() {
+                            value["parent"] = renumbered(&value["parent"], &renamed);
+                        }
+                        (renumbered(key, &renamed), value)
+                    } else {
+                        (key.clone(), value.clone())
+                    };
+                    let (key, value) = (&key, &value);
+                    match find_entry(target, section, key) {
+                        Some(existing) if stable(section, existing) != stable(section, value) => {
+                            conflicts.push(format!("{} {}: ya existe con otro contenido", section, key));
+                        }
+                        Some(_) => {}
+                        None => match target[section.as_str()].as_array_mut() {
+                            Some(array) => array.push(value.clone()),
+                            None => target[section.as_str()] = json!([value]),
+                        },
+                    }
+                }
+                Change::Remove { section, key, value } => {
+                    let Some(array) = target[section.as_str()].as_array_mut() else {
+                        continue;
+                    };
+                    let Some(index) = position(array, section, key) else {
+                        continue;
+                    };
+                    if stable(section, &array[index]) == stable(section, value) {
+                        array.remove(index);
+                    } else {
+                        conflicts.push(format!("{} {}: se borró pero fue editado en la otra copia", section, key));
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    // Ids nuevos para los objetos agregados cuyo id la escena ya usa para otro objeto: si dos personas
+    // agregan un objeto en su copia, a los dos les toca el mismo id siguiente y no son el mismo objeto.
+    // Si el contenido coincide es el mismo objeto (el parche ya estaba aplicado) y no se renombra
+    fn renamed_objects(&self, target: &Value, first_free_id: u64) -> HashMap<u64, u64> {
+        let existing = entries(&target["objects"], SectionKey::Field("id"));
+        let added: Vec<(&Value, &Value)> = self
+            .changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::Add { section, key, value } if section == "objects" => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+        let mut next = existing
+            .iter()
+            .map(|(key, _)| key)
+            .chain(added.iter().map(|(key, _)| *key))
+            .filter_map(Value::as_u64)
+            .map(|id| id + 1)
+            .fold(first_free_id, u64::max);
+
+        let mut renamed = HashMap::new();
+        for (key, value) in added {
+            let Some(id) = key.as_u64() else {
+                continue;
+            };
+            let collides = existing
+                .iter()
+                .any(|(other, current)| other == key && stable("objects", current) != stable("objects", value));
+            if collides {
+                renamed.insert(id, next);
+                next += 1;
+            }
+        }
+        renamed
+    }
+
+    pub fn load(path: &str) -> Result<Patch, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let changes = value["changes"]
+            .as_array()
+            .ok_or(format!("{}: parche sin cambios", path))?
+            .iter()
+            .map(change_from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Patch { changes })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let value = json!({ "changes": self.changes.iter().map(change_to_json).collect::<Vec<_>>() });
+        let text = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+// El id con el que quedó un objeto agregado por el parche; los demás valores pasan igual
+fn renumbered(id: &Value, renamed: &HashMap<u64, u64>) -> Value {
+    match id.as_u64().and_then(|id| renamed.get(&id)) {
+        Some(new_id) => json!(new_id),
+        None => id.clone(),
+    }
+}
+
+fn section_key(section: &str) -> SectionKey {
+    SECTIONS
+        .iter()
+        .find(|(name, _)| *name == section)
+        .map(|(_, key)| *key)
+        .unwrap_or(SectionKey::Whole)
+}
+
+fn position(array: &[Value], section: &str, key: &Value) -> Option<usize> {
+    match section_key(section) {
+        SectionKey::Field(field) => array.iter().position(|value| value[field] == *key),
+        SectionKey::Index => key.as_u64().map(|index| index as usize).filter(|index| *index < array.len()),
+        SectionKey::Whole => array.iter().position(|value| value == key),
+    }
+}
+
+fn find_entry<'a>(target: &'a mut Value, section: &str, key: &Value) -> Option<&'a mut Value> {
+    if section == "scene" {
+        return Some(target);
+    }
+    let array = target[section].as_array_mut()?;
+    let index = position(array, section, key)?;
+    array.get_mut(index)
+}
+
+fn change_to_json(change: &Change) -> Value {
+    let mut value = Map::new();
+    let (op, section, key) = match change {
+        Change::Set {
+            section,
+            key,
+            field,
+            from,
+            to,
+        } => {
+            if let Some(field) = field {
+                value.insert("field".to_string(), json!(field));
+            }
+            value.insert("from".to_string(), from.clone());
+            value.insert("to".to_string(), to.clone());
+            ("set", section, key)
+        }
+        Change::Add { section, key, value: added } => {
+            value.insert("value".to_string(), added.clone());
+            ("add", section, key)
+        }
+        Change::Remove { section, key, value: removed } => {
+            value.insert("value".to_string(), removed.clone());
+            ("remove", section, key)
+        }
+    };
+    value.insert("op".to_string(), json!(op));
+    value.insert("section".to_string(), json!(section));
+    value.insert("key".to_string(), key.clone());
+    Value::Object(value)
+}
+
+fn change_from_json(value: &Value) -> Result<Change, String> {
+    let section = value["section"].as_str().ok_or("cambio sin sección")?.to_string();
+    let key = value["key"].clone();
+    match value["op"].as_str() {
+        Some("set") => Ok(Change::Set {
+            section,
+            key,
+            field: value["field"].as_str().map(str::to_string),
+            from: value["from"].clone(),
+            to: value["to"].clone(),
+        }),
+        Some("add") => Ok(Change::Add {
+            section,
+            key,
+            value: value["value"].clone(),
+        }),
+        Some("remove") => Ok(Change::Remove {
+            section,
+            key,
+            value: value["value"].clone(),
+        }),
+        other => Err(format!("operación de parche desconocida: {:?}", other)),
+    }
+}
+
+impl Scene {
+    // Parche con lo que cambió desde que se cargó (o guardó) el archivo
+    pub fn export_patch(&self) -> Patch {
+        let base = self.baseline.clone().unwrap_or_else(|| json!({}));
+        Patch::diff(&base, &self.to_json())
+    }
+
+    // Aplica un parche y reconstruye la escena; el archivo base de referencia no cambia
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<Vec<String>, String> {
+        let mut merged = self.to_json();
+        let next_id = self.allocate_id();
+        let conflicts = patch.apply(&mut merged, next_id as u64);
+        let mut scene = Scene::from_json(&merged)?;
+        scene.baseline = self.baseline.take();
+        scene.reserve_ids(next_id);
+
+        // Lo que no está en el archivo pasa a la escena nueva: los objetos dinámicos (salvo si el
+        // parche ya usó su id; el pool los da por perdidos), los triggers ocupados, para no volver a
+        // dispararlos, y el modo noche
+        let pooled: Vec<_> = self
+            .objects
+            .iter()
+            .filter(|object| object.pooled && scene.object(object.id).is_none())
+            .cloned()
+            .collect();
+        scene.objects.extend(pooled);
+        for trigger in scene.triggers.iter_mut() {
+            trigger.occupied = self.triggers.iter().any(|old| old.name == trigger.name && old.occupied);
+        }
+        if self.day_ambient.is_some() {
+            scene.set_night(true);
+        }
+        *self = scene;
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Value {
+        json!({
+            "ambient_intensity": 0.3,
+            "objects": [
+                { "id": 1, "name": "mesa", "position": [0.0, 0.0, 0.0] },
+            ],
+            "layers": [{ "name": "props", "visible": true }],
+        })
+    }
+
+    fn object<'a>(scene: &'a Value, name: &str) -> &'a Value {
+        scene["objects"]
+            .as_array()
+            .and_then(|objects| objects.iter().find(|object| object["name"] == name))
+            .unwrap_or_else(|| panic!("falta el objeto '{}'", name))
+    }
+
+    #[test]
+    fn diff_and_apply_round_trip() {
+        let base = base();
+        let mut current = base.clone();
+        current["ambient_intensity"] = json!(0.5);
+        current["objects"][0]["position"] = json!([1.0, 0.0, 2.0]);
+        current["objects"].as_array_mut().unwrap().push(json!({ "id": 2, "name": "silla", "parent": 1 }));
+        current["layers"].as_array_mut().unwrap().clear();
+
+        let patch = Patch::diff(&base, &current);
+        let mut merged = base.clone();
+        assert!(patch.apply(&mut merged, 0).is_empty());
+        assert_eq!(merged, current);
+
+        // Lo mismo después de pasar por el formato del archivo
+        let saved: Vec<Value> = patch.changes.iter().map(change_to_json).collect();
+        let loaded: Vec<Change> = saved.iter().map(|change| change_from_json(change).unwrap()).collect();
+        assert_eq!(loaded, patch.changes);
+
+        // Aplicarlo otra vez no cambia nada ni choca con lo que ya agregó
+        assert!(patch.apply(&mut merged, 0).is_empty());
+        assert_eq!(merged, current);
+    }
+
+    #[test]
+    fn value_edited_in_both_copies_is_a_conflict() {
+        let base = base();
+        let mut mine = base.clone();
+        mine["objects"][0]["position"] = json!([1.0, 0.0, 0.0]);
+        let mut theirs = base.clone();
+        theirs["objects"][0]["position"] = json!([2.0, 0.0, 0.0]);
+
+        let conflicts = Patch::diff(&base, &mine).apply(&mut theirs, 0);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("editado en ambas copias"));
+        assert_eq!(theirs["objects"][0]["position"], json!([2.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn colliding_added_objects_get_new_ids() {
+        let base = base();
+        // Las dos copias agregan un objeto con el siguiente id libre, el 2
+        let mut mine = base.clone();
+        let objects = mine["objects"].as_array_mut().unwrap();
+        objects.push(json!({ "id": 2, "name": "lampara" }));
+        objects.push(json!({ "id": 3, "name": "pantalla", "parent": 2 }));
+        mine["objects"][0]["parent"] = json!(2);
+        let mut theirs = base.clone();
+        theirs["objects"].as_array_mut().unwrap().push(json!({ "id": 2, "name": "maceta" }));
+
+        let conflicts = Patch::diff(&base, &mine).apply(&mut theirs, 0);
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+        assert_eq!(theirs["objects"].as_array().unwrap().len(), 4);
+        assert_eq!(object(&theirs, "maceta")["id"], json!(2));
+        let lamp = object(&theirs, "lampara")["id"].clone();
+        assert_eq!(lamp, json!(4));
+        assert_eq!(object(&theirs, "pantalla")["parent"], lamp);
+        assert_eq!(object(&theirs, "mesa")["parent"], lamp);
+    }
+
+    #[test]
+    fn renamed_ids_start_at_the_first_free_id() {
+        let base = base();
+        let mut mine = base.clone();
+        mine["objects"].as_array_mut().unwrap().push(json!({ "id": 2, "name": "lampara" }));
+        let mut theirs = base.clone();
+        theirs["objects"].as_array_mut().unwrap().push(json!({ "id": 2, "name": "maceta" }));
+
+        // Ids que la escena ya repartió aunque no estén en el archivo (objetos dinámicos)
+        assert!(Patch::diff(&base, &mine).apply(&mut theirs, 10).is_empty());
+        assert_eq!(object(&theirs, "lampara")["id"], json!(10));
+    }
+}
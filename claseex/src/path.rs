@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::scene::{f32_to_json, vec3_from_json, vec3_to_json, Scene};
+
+// Muestras por tramo usadas para aproximar la longitud de arco
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+// Radio de los puntos de control al dibujarlos y elegirlos con el mouse
+pub const CONTROL_POINT_RADIUS: f32 = 0.15;
+
+// Curva Catmull-Rom que pasa por todos sus puntos de control
+#[derive(Clone, Debug)]
+pub struct SplinePath {
+    pub name: String,
+    pub points: Vec<Vector3>,
+    pub closed: bool,
+}
+
+// Configuración de un objeto que recorre una curva
+#[derive(Clone, Debug)]
+pub struct PathFollow {
+    pub path: String,
+    pub speed: f32,     // Unidades por segundo
+    pub looping: bool,  // Al llegar al final vuelve a empezar; si no, va y vuelve
+    pub orient: bool,   // Gira el objeto para que su eje +Z siga la tangente
+    pub distance: f32,  // Recorrido acumulado sobre la curva
+}
+
+// Curva discretizada con la distancia acumulada en cada muestra
+pub struct SampledPath {
+    samples: Vec<(Vector3, f32)>,
+}
+
+impl SplinePath {
+    fn control_point(&self, index: isize) -> Vector3 {
+        let count = self.points.len() as isize;
+        let index = if self.closed {
+            index.rem_euclid(count)
+        } else {
+            index.clamp(0, count - 1)
+        };
+        self.points[index as usize]
+    }
+
+    fn segment_count(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            count if self.closed => count,
+            count => count - 1,
+        }
+    }
+
+    // Punto del tramo `segment` para t entre 0 y 1
+    fn evaluate(&self, segment: usize, t: f32) -> Vector3 {
+        let i = segment as isize;
+        let (p0, p1) = (self.control_point(i - 1), self.control_point(i));
+        let (p2, p3) = (self.control_point(i + 1), self.control_point(i + 2));
+        let (t2, t3) = (t * t, t * t * t);
+        (p1 * 2.0
+            + (p2 - p0) * t
+            + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+            + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+            * 0.5
+    }
+
+    pub fn sample(&self) -> SampledPath {
+        let mut samples = Vec::new();
+        let mut length = 0.0;
+        for segment in 0..self.segment_count() {
+            for step in 0..SAMPLES_PER_SEGMENT {
+                let point = self.evaluate(segment, step as f32 / SAMPLES_PER_SEGMENT as f32);
+                if let Some((previous, _)) = samples.last() {
+                    length += point.distance_to(*previous);
+                }
+                samples.push((point, length));
+            }
+        }
+        // Cierra con el último punto (o el primero si la curva es cerrada)
+        if let Some((previous, _)) = samples.last().copied() {
+            let last = self.control_point(if self.closed { 0 } else { self.points.len() as isize - 1 });
+            samples.push((last, length + last.distance_to(previous)));
+        }
+        SampledPath { samples }
+    }
+}
+
+impl SampledPath {
+    pub fn length(&self) -> f32 {
+        self.samples.last().map(|(_, length)| *length).unwrap_or(0.0)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Vector3> + '_ {
+        self.samples.iter().map(|(point, _)| *point)
+    }
+
+    // Posición y tangente a cierta distancia desde el inicio
+    pub fn point_at(&self, distance: f32) -> Option<(Vector3, Vector3)> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+        let distance = distance.clamp(0.0, self.length());
+        let index = self
+            .samples
+            .partition_point(|(_, length)| *length < distance)
+            .clamp(1, self.samples.len() - 1);
+        let (a, start) = self.samples[index - 1];
+        let (b, end) = self.samples[index];
+        let t = if end > start { (distance - start) / (end - start) } else { 0.0 };
+        Some((a.lerp(b, t), (b - a).normalized()))
+    }
+}
+
+// Rotación en grados que alinea el eje +Z local con la dirección dada
+fn rotation_facing(direction: Vector3) -> Vector3 {
+    let pitch = -direction.y.clamp(-1.0, 1.0).asin();
+    let yaw = (-direction.x).atan2(direction.z);
+    Vector3::new(pitch.to_degrees(), yaw.to_degrees(), 0.0)
+}
+
+impl Scene {
+    // Avanza los objetos enganchados a una curva
+    pub fn update_followers(&mut self, dt: f32) {
+        let sampled: HashMap<String, SampledPath> =
+            self.paths.iter().map(|path| (path.name.clone(), path.sample())).collect();
+
+        for object in &mut self.objects {
+            let Some(follow) = object.follow.as_mut() else {
+                continue;
+            };
+            let Some(path) = sampled.get(&follow.path) else {
+                continue;
+            };
+            let length = path.length();
+            if length <= 0.0 {
+                continue;
+            }
+
+            // Sin loop el recorrido es de ida y vuelta, así que el ciclo mide el doble
+            let cycle = if follow.looping { length } else { length * 2.0 };
+            follow.distance = (follow.distance + follow.speed * dt).rem_euclid(cycle);
+            let (distance, backwards) = if follow.distance > length {
+                (cycle - follow.distance, true)
+            } else {
+                (follow.distance, false)
+            };
+
+            if let Some((position, tangent)) = path.point_at(distance) {
+                // La curva reemplaza la posición que dejaron los comportamientos; el vaivén y la órbita
+                // se vuelven a sumar encima
+                let offset = object
+                    .behaviors
+                    .iter()
+                    .fold(Vector3::zero(), |sum, behavior| sum + behavior.path_offset());
+                object.position = position + offset;
+                if follow.orient {
+                    object.rotation = rotation_facing(if backwards { tangent * -1.0 } else { tangent });
+                }
+            }
+        }
+    }
+}
+
+// Índices (curva, punto) del punto de control más cercano que toca el rayo
+pub fn pick_control_point(paths: &[SplinePath], ray: Ray) -> Option<(usize, usize)> {
+    let mut best: Option<((usize, usize), f32)> = None;
+    for (path_index, path) in paths.iter().enumerate() {
+        for (point_index, point) in path.points.iter().enumerate() {
+            let collision = get_ray_collision_sphere(ray, *point, CONTROL_POINT_RADIUS * 1.5);
+            if collision.hit && best.is_none_or(|(_, distance)| collision.distance < distance) {
+                best = Some(((path_index, point_index), collision.distance));
+            }
+        }
+    }
+    best.map(|(indices, _)| indices)
+}
+
+pub fn path_from_json(value: &Value) -> Result<SplinePath, String> {
+    Ok(SplinePath {
+        name: value["name"].as_str().ok_or("curva sin nombre")?.to_string(),
+        points: value["points"].as_array().into_iter().flatten().filter_map(vec3_from_json).collect(),
+        closed: value["closed"].as_bool().unwrap_or(false),
+    })
+}
+
+pub fn path_to_json(path: &SplinePath) -> Value {
+    json!({
+        "name": path.name,
+        "closed": path.closed,
+        "points": path.points.iter().map(|point| vec3_to_json(*point)).collect::<Vec<_>>(),
+    })
+}
+
+pub fn follow_from_json(value: &Value) -> Option<PathFollow> {
+    Some(PathFollow {
+        path: value["path"].as_str()?.to_string(),
+        speed: value["speed"].as_f64().unwrap_or(1.0) as f32,
+        looping: value["loop"].as_bool().unwrap_or(true),
+        orient: value["orient"].as_bool().unwrap_or(false),
+        distance: value["distance"].as_f64().unwrap_or(0.0) as f32,
+    })
+}
+
+pub fn follow_to_json(follow: &PathFollow) -> Value {
+    json!({
+        "path": follow.path,
+        "speed": f32_to_json(follow.speed),
+        "loop": follow.looping,
+        "orient": follow.orient,
+        "distance": f32_to_json(follow.distance),
+    })
+}
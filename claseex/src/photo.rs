@@ -0,0 +1,192 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use raylib::prelude::*;
+
+pub const CAPTURE_DIR: &str = "capturas";
+// Cantidad de vistas que se promedian para simular la profundidad de campo
+const DOF_SAMPLES: usize = 12;
+const PANEL_WIDTH: f32 = 300.0;
+const PANEL_HEIGHT: f32 = 270.0;
+
+// Modo foto: cámara con giro, guías de composición y ajustes de lente
+pub struct PhotoMode {
+    pub active: bool,
+    pub show_guides: bool,
+    pub roll: f32,           // Giro sobre el eje de la vista, en grados
+    pub fov: f32,
+    pub aperture: f32,       // Radio de la lente; 0 deja todo enfocado
+    pub focus_distance: f32, // Distancia al plano que queda nítido
+    pub exposure: f32,
+    pub auto_exposure: bool, // Sin marcar, la exposición del control manual reemplaza a la automática
+    pub supersample: f32, // Multiplicador de la resolución de las capturas
+}
+
+impl PhotoMode {
+    pub fn new() -> Self {
+        PhotoMode {
+            active: false,
+            show_guides: true,
+            roll: 0.0,
+            fov: 45.0,
+            aperture: 0.0,
+            focus_distance: 8.0,
+            exposure: 1.0,
+            auto_exposure: true,
+            supersample: 2.0,
+        }
+    }
+
+    // Al entrar se parte del campo visual y del punto de interés de la cámara actual
+    pub fn enter(&mut self, camera: &Camera3D) {
+        self.active = true;
+        self.roll = 0.0;
+        self.fov = camera.fovy;
+        self.focus_distance = camera.position.distance_to(camera.target);
+    }
+
+    // Cámara con el giro y el campo visual elegidos
+    pub fn camera(&self, base: Camera3D) -> Camera3D {
+        let forward = (base.target - base.position).normalized();
+        let up = base.up.rotate_by(Quaternion::from_axis_angle(forward, self.roll.to_radians()));
+        Camera3D::perspective(base.position, base.target, up, self.fov)
+    }
+
+    // Posiciones de la lente repartidas en un disco; todas miran al mismo punto del plano de foco
+    pub fn lens_cameras(&self, camera: Camera3D) -> Vec<Camera3D> {
+        if self.aperture <= 0.001 {
+            return vec![camera];
+        }
+        let forward = (camera.target - camera.position).normalized();
+        let right = forward.cross(camera.up).normalized();
+        let up = right.cross(forward);
+        let focus = camera.position + forward * self.focus_distance;
+        (0..DOF_SAMPLES)
+            .map(|index| {
+                // Espiral de Vogel: cubre el disco de forma pareja con pocas muestras
+                let radius = ((index as f32 + 0.5) / DOF_SAMPLES as f32).sqrt() * self.aperture;
+                let angle = index as f32 * 2.399_963;
+                let offset = right * (radius * angle.cos()) + up * (radius * angle.sin());
+                Camera3D::perspective(camera.position + offset, focus, up, camera.fovy)
+            })
+            .collect()
+    }
+
+    pub fn panel_bounds(&self, screen_width: f32) -> Rectangle {
+        Rectangle::new(screen_width - PANEL_WIDTH - 10.0, 10.0, PANEL_WIDTH, PANEL_HEIGHT)
+    }
+
+    // Panel con los ajustes de la foto; devuelve true si se pidió una captura
+    pub fn draw_panel(&mut self, d: &mut RaylibDrawHandle) -> bool {
+        let bounds = self.panel_bounds(d.get_screen_width() as f32);
+        let (x, y) = (bounds.x, bounds.y);
+        d.gui_panel(bounds, "Modo foto");
+
+        let row = |index: usize| Rectangle::new(x + 90.0, y + 34.0 + index as f32 * 28.0, 150.0, 18.0);
+        let sliders: [(&str, &mut f32, f32, f32); 6] = [
+            ("Giro", &mut self.roll, -45.0, 45.0),
+            ("Campo", &mut self.fov, 15.0, 100.0),
+            ("Apertura", &mut self.aperture, 0.0, 0.5),
+            ("Foco", &mut self.focus_distance, 0.5, 30.0),
+            ("Exposicion", &mut self.exposure, 0.25, 4.0),
+            ("Resolucion", &mut self.supersample, 1.0, 4.0),
+        ];
+        for (index, (name, value, min, max)) in sliders.into_iter().enumerate() {
+            let text = format!("{:.2}", value);
+            d.gui_slider_bar(row(index), name, &text, value, min, max);
+        }
+        self.supersample = self.supersample.round();
+
+        let help = Rectangle::new(x + 10.0, y + PANEL_HEIGHT - 66.0, PANEL_WIDTH - 20.0, 18.0);
+        d.gui_label(help, "Clic der: mover  F12: capturar  F6: salir");
+        let guides = Rectangle::new(x + 10.0, y + PANEL_HEIGHT - 34.0, 16.0, 16.0);
+        d.gui_check_box(guides, "Guias", &mut self.show_guides);
+        let auto = Rectangle::new(x + 80.0, y + PANEL_HEIGHT - 34.0, 16.0, 16.0);
+        d.gui_check_box(auto, "Auto expo", &mut self.auto_exposure);
+        d.gui_button(Rectangle::new(x + PANEL_WIDTH - 130.0, y + PANEL_HEIGHT - 38.0, 120.0, 26.0), "Capturar")
+    }
+}
+
+// Guías de la regla de los tercios sobre toda la pantalla
+pub fn draw_thirds(d: &mut impl RaylibDraw, width: i32, height: i32) {
+    let color = Color::RAYWHITE.alpha(0.45);
+    for step in 1..3 {
+        d.draw_line(width * step / 3, 0, width * step / 3, height, color);
+        d.draw_line(0, height * step / 3, width, height * step / 3, color);
+    }
+}
+
+// Ruta de la próxima captura, con la hora para no pisar las anteriores
+pub fn capture_path() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default();
+    format!("{}/foto_{}.png", CAPTURE_DIR, seconds)
+}
+
+// Guarda la imagen promediada como PNG y devuelve la ruta
+pub fn save_capture(target: &RenderTexture2D) -> Result<String, String> {
+    std::fs::create_dir_all(CAPTURE_DIR).map_err(|e| format!("{}: {}", CAPTURE_DIR, e))?;
+    let mut image = target.load_image().map_err(|e| e.to_string())?;
+    image.flip_vertical();
+    let path = capture_path();
+    image.export_image(&path);
+    Ok(path)
+}
+
+// Destinos de render del modo foto: una vista por muestra y el promedio acumulado
+#[derive(Default)]
+pub struct PhotoRenderer {
+    sample: Option<RenderTexture2D>,
+    accumulated: Option<RenderTexture2D>,
+}
+
+impl PhotoRenderer {
+    // Dibuja la escena desde cada cámara de la lente y promedia los resultados
+    pub fn render(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        size: (u32, u32),
+        cameras: &[Camera3D],
+        background: Color,
+        draw: &mut dyn FnMut(&mut RaylibMode3D<RaylibTextureMode<RaylibHandle>>),
+    ) -> Result<&RenderTexture2D, String> {
+        let (width, height) = size;
+        for target in [&mut self.sample, &mut self.accumulated] {
+            let outdated = target
+                .as_ref()
+                .is_none_or(|texture| texture.texture.width != width as i32 || texture.texture.height != height as i32);
+            if outdated {
+                let texture = rl.load_render_texture(thread, width, height).map_err(|e| e.to_string())?;
+                // La vista previa se estira a la ventana según la escala de render
+                texture.texture().set_texture_filter(thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
+                *target = Some(texture);
+            }
+        }
+        let sample = self.sample.as_mut().ok_or("sin textura de render")?;
+        let accumulated = self.accumulated.as_mut().ok_or("sin textura de render")?;
+
+        // Las texturas de render quedan invertidas en Y; se copian con alto negativo
+        let source = Rectangle::new(0.0, 0.0, width as f32, -(height as f32));
+        for (index, camera) in cameras.iter().enumerate() {
+            {
+                let mut texture = rl.begin_texture_mode(thread, sample);
+                texture.clear_background(background);
+                let mut d3d = texture.begin_mode3D(*camera);
+                draw(&mut d3d);
+            }
+            // Promedio incremental: la muestra n pesa 1/n sobre lo acumulado. Con alfa premultiplicado
+            // el canal alfa del resultado se mantiene opaco
+            let mut texture = rl.begin_texture_mode(thread, accumulated);
+            if index == 0 {
+                texture.clear_background(background);
+            }
+            let weight = (255.0 / (index + 1) as f32).round() as u8;
+            let mut blend = texture.begin_blend_mode(BlendMode::BLEND_ALPHA_PREMULTIPLY);
+            let tint = Color::new(weight, weight, weight, weight);
+            blend.draw_texture_rec(sample.texture(), source, Vector2::zero(), tint);
+        }
+        Ok(accumulated)
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::geometry::Geometry;
+use crate::renderer::transform_direction;
+use crate::scene::{Scene, SceneObject, Shape};
+
+// Medio grosor local con el que se considera un plano al elegir con el mouse
+const PLANE_HALF_THICKNESS: f32 = 0.01;
+
+// Resultado de lanzar un rayo contra la escena
+#[derive(Clone, Copy, Debug)]
+pub struct PickHit {
+    pub id: u32,
+    pub distance: f32,
+    pub point: Vector3,
+}
+
+// Intersección del rayo con una caja alineada en espacio local (método de slabs)
+fn intersect_box(origin: Vector3, direction: Vector3, half: Vector3) -> Option<f32> {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    let origin = origin.to_array();
+    let direction = direction.to_array();
+    let half = half.to_array();
+
+    for axis in 0..3 {
+        if direction[axis].abs() < 1e-8 {
+            if origin[axis].abs() > half[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (-half[axis] - origin[axis]) / direction[axis];
+        let t2 = (half[axis] - origin[axis]) / direction[axis];
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min > t_max {
+            return None;
+        }
+    }
+
+    (t_max >= 0.0 && t_min >= 0.0).then_some(t_min)
+}
+
+// Intersección con la esfera local de radio 0.5
+fn intersect_sphere(origin: Vector3, direction: Vector3) -> Option<f32> {
+    let a = direction.dot(direction);
+    let b = 2.0 * origin.dot(direction);
+    let c = origin.dot(origin) - 0.25;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (t >= 0.0).then_some(t)
+}
+
+// Intersección con la forma en su espacio local; t se mide en unidades de `direction`
+pub fn intersect_local(shape: Shape, origin: Vector3, direction: Vector3) -> Option<f32> {
+    match shape {
+        Shape::Cube => intersect_box(origin, direction, Vector3::new(0.5, 0.5, 0.5)),
+        Shape::Plane => intersect_box(origin, direction, Vector3::new(0.5, PLANE_HALF_THICKNESS, 0.5)),
+        Shape::Sphere => intersect_sphere(origin, direction),
+    }
+}
+
+// Intersección con los triángulos de una geometría local (Möller–Trumbore), de ambos lados
+fn intersect_geometry(geometry: &Geometry, origin: Vector3, direction: Vector3) -> Option<f32> {
+    geometry
+        .vertices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let (a, b, c) = (triangle[0].position, triangle[1].position, triangle[2].position);
+            let (edge1, edge2) = (b - a, c - a);
+            let p = direction.cross(edge2);
+            let determinant = edge1.dot(p);
+            if determinant.abs() < 1e-8 {
+                return None;
+            }
+            let inverse = 1.0 / determinant;
+            let offset = origin - a;
+            let u = offset.dot(p) * inverse;
+            if !(0.0..=1.0).contains(&u) {
+                return None;
+            }
+            let q = offset.cross(edge1);
+            let v = direction.dot(q) * inverse;
+            if v < 0.0 || u + v > 1.0 {
+                return None;
+            }
+            let t = edge2.dot(q) * inverse;
+            (t >= 0.0).then_some(t)
+        })
+        .reduce(f32::min)
+}
+
+// Intersección con lo que se ve del objeto en su espacio local: el sólido CSG tallado si lo tiene
+// (así se puede elegir a través de un hueco), o su forma base
+pub fn intersect_solid(object: &SceneObject, origin: Vector3, direction: Vector3) -> Option<f32> {
+    match &object.csg {
+        Some(csg) => intersect_geometry(&csg.geometry, origin, direction),
+        None => intersect_local(object.shape, origin, direction),
+    }
+}
+
+// Distancia sobre el rayo hasta el objeto, llevando el rayo a su espacio local
+fn intersect_object(world: &Matrix, object: &SceneObject, ray: Ray) -> Option<f32> {
+    // Sin normalizar la dirección para conservar el parámetro t
+    let inverse = world.inverted();
+    let origin = ray.position.transform_with(inverse);
+    let direction = transform_direction(&inverse, ray.direction);
+    intersect_solid(object, origin, direction)
+}
+
+// Lanza el rayo contra los objetos visibles y devuelve el impacto más cercano
+pub fn pick(scene: &Scene, transforms: &HashMap<u32, Matrix>, ray: Ray) -> Option<PickHit> {
+    let mut best: Option<PickHit> = None;
+
+    for object in &scene.objects {
+        if !scene.layer_visible(&object.layer) {
+            continue;
+        }
+        let Some(world) = transforms.get(&object.id) else {
+            continue;
+        };
+
+        if let Some(t) = intersect_object(world, object, ray)
+            && best.is_none_or(|best| t < best.distance)
+        {
+            best = Some(PickHit {
+                id: object.id,
+                distance: t,
+                point: ray.position + ray.direction * t,
+            });
+        }
+    }
+
+    best
+}
+
+// Impacto del rayo con un objeto que ya se sabe que está bajo el cursor.
+// Si el rayo lo roza sin tocar su forma, se toma el punto del rayo más cercano a su centro
+pub fn hit_on_object(transforms: &HashMap<u32, Matrix>, object: &SceneObject, ray: Ray) -> Option<PickHit> {
+    let id = object.id;
+    let world = transforms.get(&id)?;
+    let t = intersect_object(world, object, ray).unwrap_or_else(|| {
+        let center = Vector3::zero().transform_with(*world);
+        (center - ray.position).dot(ray.direction).max(0.0)
+    });
+    Some(PickHit {
+        id,
+        distance: t,
+        point: ray.position + ray.direction * t,
+    })
+}
+
+// Cómo se decide qué objeto está bajo el cursor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PickBackend {
+    Ray,      // Rayo contra las formas en la CPU
+    IdBuffer, // Ids dibujados en un buffer fuera de pantalla, exacto al pixel
+}
+
+impl PickBackend {
+    pub fn toggled(self) -> Self {
+        match self {
+            PickBackend::Ray => PickBackend::IdBuffer,
+            PickBackend::IdBuffer => PickBackend::Ray,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PickBackend::Ray => "rayo",
+            PickBackend::IdBuffer => "buffer de ids",
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::behavior::Behavior;
+use crate::rng::Rng;
+use crate::scene::{Scene, SceneObject, Shape};
+
+// Objetos que se reservan de entrada; si se usan todos el pool crece solo
+const INITIAL_CAPACITY: usize = 128;
+// Tope de objetos vivos a la vez: al pasarlo se recicla el más viejo
+const MAX_ACTIVE: usize = 256;
+const GRAVITY: f32 = 9.8;
+// Velocidad que conserva un objeto al rebotar contra el suelo
+const BOUNCE: f32 = 0.4;
+const FRICTION: f32 = 0.8;
+// Por debajo de esta altura el objeto se cayó de la escena y se devuelve al pool
+const KILL_HEIGHT: f32 = -30.0;
+const LAYER: &str = "props";
+
+// Lo que hace falta para crear un objeto dinámico
+#[derive(Clone, Copy, Debug)]
+pub struct SpawnRequest {
+    pub shape: Shape,
+    pub position: Vector3,
+    pub velocity: Vector3,
+    pub spin: Vector3, // Grados por segundo
+    pub size: Vector3,
+    pub color: Color,
+    pub lifetime: f32, // Segundos hasta volver al pool
+    pub gravity: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Active {
+    id: u32,
+    velocity: Vector3,
+    remaining: f32,
+    gravity: bool,
+}
+
+// Objetos de vida corta (escombros, chispas, cubos creados por scripts). Los que se despawnean no se
+// liberan: quedan en la lista libre y el próximo spawn reutiliza su memoria (nombre, comportamientos)
+pub struct ObjectPool {
+    free: Vec<SceneObject>,
+    active: VecDeque<Active>, // En orden de creación
+    rng: Rng,
+}
+
+impl ObjectPool {
+    pub fn new(scene: &mut Scene) -> Self {
+        // Lugar en la escena para los objetos vivos, así los spawns no realocan la lista
+        scene.objects.reserve(MAX_ACTIVE);
+        ObjectPool {
+            free: (0..INITIAL_CAPACITY).map(|_| blank_object()).collect(),
+            active: VecDeque::with_capacity(MAX_ACTIVE),
+            rng: Rng::new(0x5eed),
+        }
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+
+    // Agrega el objeto a la escena y devuelve su id
+    pub fn spawn(&mut self, scene: &mut Scene, request: SpawnRequest) -> u32 {
+        if self.active.len() >= MAX_ACTIVE
+            && let Some(oldest) = self.active.front().map(|active| active.id)
+        {
+            self.despawn(scene, oldest);
+        }
+        let mut object = self.free.pop().unwrap_or_else(blank_object);
+        let id = scene.allocate_id();
+        object.id = id;
+        object.name.clear();
+        object.name.push_str(request.shape.name());
+        object.shape = request.shape;
+        object.position = request.position;
+        object.rotation = Vector3::zero();
+        object.size = request.size;
+        object.color = request.color;
+        object.layer.clear();
+        object.layer.push_str(LAYER);
+        object.outline = request.shape == Shape::Cube;
+        object.behaviors.clear();
+        if request.spin != Vector3::zero() {
+            object.behaviors.push(Behavior::Spinner { speed: request.spin });
+        }
+        scene.objects.push(object);
+        self.active.push_back(Active {
+            id,
+            velocity: request.velocity,
+            remaining: request.lifetime,
+            gravity: request.gravity,
+        });
+        id
+    }
+
+    // Saca el objeto de la escena y lo guarda para reutilizarlo. Devuelve false si no era del pool
+    pub fn despawn(&mut self, scene: &mut Scene, id: u32) -> bool {
+        let Some(index) = self.active.iter().position(|active| active.id == id) else {
+            return false;
+        };
+        self.active.remove(index);
+        // Los dinámicos están al final de la lista, así que se busca desde atrás
+        if let Some(index) = scene.objects.iter().rposition(|object| object.pooled && object.id == id) {
+            self.free.push(scene.objects.remove(index));
+        }
+        true
+    }
+
+    pub fn clear(&mut self, scene: &mut Scene) -> usize {
+        let count = self.active.len();
+        while let Some(active) = self.active.front() {
+            let id = active.id;
+            self.despawn(scene, id);
+        }
+        count
+    }
+
+    // Ráfaga de escombros que salen despedidos desde un punto
+    pub fn burst(&mut self, scene: &mut Scene, shape: Shape, origin: Vector3, count: usize) {
+        for _ in 0..count {
+            let angle = self.rng.range(0.0, std::f32::consts::TAU);
+            let speed = self.rng.range(1.0, 3.5);
+            let side = self.rng.range(0.15, 0.35);
+            let shade = self.rng.range(0.6, 1.0);
+            let request = SpawnRequest {
+                shape,
+                position: origin,
+                velocity: Vector3::new(angle.cos() * speed, self.rng.range(3.0, 6.0), angle.sin() * speed),
+                spin: Vector3::new(self.rng.range(-180.0, 180.0), self.rng.range(-180.0, 180.0), 0.0),
+                size: Vector3::new(side, side, side),
+                color: Color::new((230.0 * shade) as u8, (170.0 * shade) as u8, (90.0 * shade) as u8, 255),
+                lifetime: self.rng.range(4.0, 7.0),
+                gravity: true,
+            };
+            self.spawn(scene, request);
+        }
+    }
+
+    // Mueve los objetos vivos, los hace rebotar contra los planos horizontales y devuelve al pool
+    // los que se vencieron o se cayeron de la escena
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        if self.active.is_empty() || dt <= 0.0 {
+            return;
+        }
+        // Planos sin padre ni rotación: el suelo y los charcos
+        let floors: Vec<(Vector3, Vector3)> = scene
+            .objects
+            .iter()
+            .filter(|object| object.shape == Shape::Plane && object.parent.is_none() && object.rotation == Vector3::zero())
+            .map(|object| (object.position, object.size * 0.5))
+            .collect();
+
+        let mut expired = Vec::new();
+        for active in self.active.iter_mut() {
+            active.remaining -= dt;
+            // Si el objeto ya no está (un parche usó su id) la entrada se libera igual
+            let Some(object) = scene.objects.iter_mut().rev().find(|object| object.pooled && object.id == active.id) else {
+                expired.push(active.id);
+                continue;
+            };
+            if active.gravity {
+                active.velocity.y -= GRAVITY * dt;
+            }
+            let half_height = object.size.y * 0.5;
+            let previous = object.position.y - half_height;
+            object.position += active.velocity * dt;
+            let bottom = object.position.y - half_height;
+            let floor = floors
+                .iter()
+                .filter(|(center, half)| {
+                    (object.position.x - center.x).abs() <= half.x
+                        && (object.position.z - center.z).abs() <= half.z
+                        && previous >= center.y
+                        && bottom < center.y
+                })
+                .map(|(center, _)| center.y)
+                .reduce(f32::max);
+            if let Some(floor) = floor {
+                object.position.y = floor + half_height;
+                active.velocity.y = -active.velocity.y * BOUNCE;
+                active.velocity.x *= FRICTION;
+                active.velocity.z *= FRICTION;
+                // Ya quieto sobre el suelo deja de girar
+                if active.velocity.y < 0.5 {
+                    active.velocity.y = 0.0;
+                    object.behaviors.clear();
+                }
+            }
+            if active.remaining <= 0.0 || object.position.y < KILL_HEIGHT {
+                expired.push(active.id);
+            }
+        }
+        for id in expired {
+            self.despawn(scene, id);
+        }
+    }
+}
+
+// Objeto vacío listo para que un spawn lo complete
+fn blank_object() -> SceneObject {
+    SceneObject {
+        id: 0,
+        name: String::with_capacity(16),
+        shape: Shape::Cube,
+        position: Vector3::zero(),
+        rotation: Vector3::zero(),
+        size: Vector3::one(),
+        color: Color::WHITE,
+        layer: String::with_capacity(16),
+        outline: false,
+        label: None,
+        parent: None,
+        palette_slot: None,
+        instance: None,
+        follow: None,
+        behaviors: Vec::with_capacity(1),
+        csg: None,
+        displacement: None,
+        reflectivity: 0.0,
+        pooled: true,
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::scene::{object_from_json, object_to_json, PrefabInstance, Scene, SceneObject};
+
+// Carpeta donde se guardan los prefabs
+pub const PREFAB_DIR: &str = "prefabs";
+
+// Conjunto reutilizable de objetos; ids locales y una única raíz en el origen
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub name: String,
+    pub objects: Vec<SceneObject>,
+}
+
+impl Prefab {
+    pub fn path(name: &str) -> String {
+        format!("{}/{}.json", PREFAB_DIR, Prefab::file_name(name))
+    }
+
+    // Nombre apto para archivo: solo letras, dígitos, '-' y '_'; lo demás (barras, puntos, espacios)
+    // pasa a '_' para que el nombre de un objeto no pueda salir de la carpeta de prefabs
+    pub fn file_name(name: &str) -> String {
+        let clean: String = name
+            .trim()
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        if clean.is_empty() { "prefab".to_string() } else { clean }
+    }
+
+    // Nombres de los prefabs guardados, en orden alfabético
+    pub fn available() -> Result<Vec<String>, String> {
+        let entries = std::fs::read_dir(PREFAB_DIR).map_err(|e| format!("{}: {}", PREFAB_DIR, e))?;
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(name: &str) -> Result<Prefab, String> {
+        let path = Prefab::path(name);
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let mut objects = Vec::new();
+        for (index, object) in value["objects"].as_array().into_iter().flatten().enumerate() {
+            objects.push(object_from_json(object, index as u32 + 1)?);
+        }
+        if objects.iter().filter(|object| object.parent.is_none()).count() != 1 {
+            return Err(format!("{}: el prefab debe tener exactamente una raíz", path));
+        }
+        Ok(Prefab {
+            name: name.to_string(),
+            objects,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(PREFAB_DIR).map_err(|e| e.to_string())?;
+        let value = json!({
+            "name": self.name,
+            "objects": self.objects.iter().map(object_to_json).collect::<Vec<_>>(),
+        });
+        let text = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+        let path = Prefab::path(&self.name);
+        std::fs::write(&path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Copia el subárbol seleccionado dejando la raíz en el origen
+    pub fn from_subtree(scene: &Scene, root: u32, name: &str) -> Option<Prefab> {
+        let ids = scene.subtree(root);
+        let local_ids: HashMap<u32, u32> = ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index as u32 + 1))
+            .collect();
+
+        let mut objects = Vec::new();
+        for id in &ids {
+            let mut object = scene.object(*id)?.clone();
+            object.id = local_ids[id];
+            object.instance = None;
+            if *id == root {
+                object.parent = None;
+                object.position = Vector3::zero();
+            } else {
+                object.parent = object.parent.and_then(|parent| local_ids.get(&parent).copied());
+            }
+            objects.push(object);
+        }
+
+        Some(Prefab {
+            name: Prefab::file_name(name),
+            objects,
+        })
+    }
+}
+
+impl Scene {
+    // Crea los objetos del prefab aplicando los valores sobrescritos de la instancia
+    pub fn instantiate(&mut self, prefab: &Prefab, mut instance: PrefabInstance) {
+        let scene_ids: HashMap<u32, u32> = prefab
+            .objects
+            .iter()
+            .map(|object| (object.id, self.allocate_id()))
+            .collect();
+
+        for source in &prefab.objects {
+            let mut object = source.clone();
+            object.id = scene_ids[&source.id];
+            object.instance = Some(instance.id);
+            object.size *= instance.scale;
+            match source.parent {
+                Some(parent) => {
+                    object.parent = scene_ids.get(&parent).copied();
+                    object.position *= instance.scale;
+                }
+                None => {
+                    // El desplazamiento guardado en la raíz (por ejemplo para apoyarse en el suelo) se suma
+                    object.position = instance.position + source.position * instance.scale;
+                    object.rotation += instance.rotation;
+                }
+            }
+            // Solo las partes que ya usaban la paleta cambian de slot
+            if object.palette_slot.is_some() && instance.palette_slot.is_some() {
+                object.palette_slot = instance.palette_slot;
+            }
+            self.ensure_layer(&object.layer);
+            instance.parts.push((object.id, object_to_json(&object)));
+            self.objects.push(object);
+        }
+
+        self.instances.push(instance);
+    }
+
+    // Vuelve a aplicar sobre una instancia recién creada las partes editadas y borradas que se
+    // guardaron con ella. Id, padre e instancia siguen siendo los de la parte nueva
+    pub fn apply_part_overrides(&mut self, id: u32, value: &Value) -> Result<(), String> {
+        let Some(instance) = self.instances.iter().find(|instance| instance.id == id) else {
+            return Ok(());
+        };
+        let parts: Vec<u32> = instance.parts.iter().map(|(part, _)| *part).collect();
+        let part_at = |entry: &Value| entry.as_u64().and_then(|index| parts.get(index as usize)).copied();
+        for entry in value["overrides"].as_array().into_iter().flatten() {
+            let Some(part) = part_at(&entry["part"]) else {
+                continue;
+            };
+            let edited = object_from_json(&entry["object"], part)?;
+            self.ensure_layer(&edited.layer);
+            if let Some(object) = self.object_mut(part) {
+                *object = SceneObject {
+                    id: part,
+                    parent: object.parent,
+                    instance: object.instance,
+                    ..edited
+                };
+            }
+        }
+        for entry in value["removed"].as_array().into_iter().flatten() {
+            if let Some(part) = part_at(entry) {
+                self.objects.retain(|object| object.id != part);
+            }
+        }
+        Ok(())
+    }
+
+    // Quita la instancia y los objetos que creó
+    pub fn remove_instance(&mut self, id: u32) {
+        self.objects.retain(|object| object.instance != Some(id));
+        self.instances.retain(|instance| instance.id != id);
+    }
+}
@@ -0,0 +1,188 @@
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::path::SplinePath;
+use crate::scene::{f32_to_json, vec3_from_json, vec3_to_json};
+
+// Duración de las tomas que salen de los marcadores de cámara
+pub const DEFAULT_SHOT_DURATION: f32 = 5.0;
+// Segundos del final de cada toma en que la cámara se desliza hacia la siguiente
+const BLEND_TIME: f32 = 1.5;
+
+// Toma fija de la cámara de presentación
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shot {
+    pub position: Vector3,
+    pub target: Vector3,
+    pub duration: f32, // Segundos hasta pasar a la siguiente
+}
+
+// Cámara de presentación definida en la escena: recorre una curva mirando a un punto o va pasando
+// por tomas fijas. Sin ninguna de las dos se usan los marcadores de la sesión
+#[derive(Clone, Debug, PartialEq)]
+pub struct Presentation {
+    pub fovy: f32,
+    pub shots: Vec<Shot>,
+    pub path: Option<String>, // Curva que recorre la cámara; tiene prioridad sobre las tomas
+    pub speed: f32,           // Unidades por segundo sobre la curva
+    pub look_at: Vector3,     // Punto al que mira mientras recorre la curva
+}
+
+impl Default for Presentation {
+    fn default() -> Self {
+        Presentation {
+            fovy: 45.0,
+            shots: Vec::new(),
+            path: None,
+            speed: 1.5,
+            look_at: Vector3::zero(),
+        }
+    }
+}
+
+impl Presentation {
+    pub fn is_empty(&self) -> bool {
+        self.shots.is_empty() && self.path.is_none()
+    }
+}
+
+// Reproduce la cámara de presentación. Mientras está activa la edición queda bloqueada
+#[derive(Default)]
+pub struct PresentationPlayer {
+    pub active: bool,
+    shot: usize,
+    time: f32,     // Tiempo dentro de la toma actual
+    distance: f32, // Recorrido sobre la curva
+}
+
+impl PresentationPlayer {
+    pub fn start(&mut self) {
+        *self = PresentationPlayer {
+            active: true,
+            ..Default::default()
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    // Salta a la siguiente toma
+    pub fn next_shot(&mut self) {
+        self.shot += 1;
+        self.time = 0.0;
+    }
+
+    // Índice de la toma actual y cantidad de tomas, para mostrar en pantalla
+    pub fn shot_info(&self, presentation: &Presentation, bookmarks: &[Option<Camera3D>]) -> Option<(usize, usize)> {
+        if presentation.path.is_some() {
+            return None;
+        }
+        let count = if presentation.shots.is_empty() {
+            bookmarks.iter().flatten().count()
+        } else {
+            presentation.shots.len()
+        };
+        (count > 0).then(|| (self.shot % count, count))
+    }
+
+    // Avanza la presentación y devuelve la cámara de este frame; None si la escena no define ninguna
+    // toma ni curva y no hay marcadores guardados
+    pub fn update(
+        &mut self,
+        presentation: &Presentation,
+        paths: &[SplinePath],
+        bookmarks: &[Option<Camera3D>],
+        dt: f32,
+    ) -> Option<Camera3D> {
+        let camera = |position: Vector3, target: Vector3| {
+            Camera3D::perspective(position, target, Vector3::new(0.0, 1.0, 0.0), presentation.fovy)
+        };
+
+        if let Some(spline) = presentation.path.as_ref().and_then(|name| paths.iter().find(|p| p.name == *name)) {
+            let sampled = spline.sample();
+            let length = sampled.length();
+            if length > 0.0 {
+                self.distance = (self.distance + presentation.speed * dt).rem_euclid(length);
+                let (position, _) = sampled.point_at(self.distance)?;
+                return Some(camera(position, presentation.look_at));
+            }
+        }
+
+        // Sin tomas propias se recorren los marcadores, con su lente
+        let shots: Vec<(Shot, f32)> = if presentation.shots.is_empty() {
+            bookmarks
+                .iter()
+                .flatten()
+                .map(|bookmark| {
+                    let shot = Shot {
+                        position: bookmark.position,
+                        target: bookmark.target,
+                        duration: DEFAULT_SHOT_DURATION,
+                    };
+                    (shot, bookmark.fovy)
+                })
+                .collect()
+        } else {
+            presentation.shots.iter().map(|shot| (*shot, presentation.fovy)).collect()
+        };
+        if shots.is_empty() {
+            return None;
+        }
+
+        self.time += dt;
+        let index = self.shot % shots.len();
+        let (shot, fovy) = shots[index];
+        let duration = shot.duration.max(BLEND_TIME);
+        if self.time >= duration {
+            self.next_shot();
+            return self.update(presentation, paths, bookmarks, 0.0);
+        }
+        // Al final de la toma la cámara se desliza suavemente hacia la siguiente
+        let (next, next_fovy) = shots[(index + 1) % shots.len()];
+        let blend = ((self.time - (duration - BLEND_TIME)) / BLEND_TIME).clamp(0.0, 1.0);
+        let t = if shots.len() > 1 { blend * blend * (3.0 - 2.0 * blend) } else { 0.0 };
+        let mut view = camera(shot.position.lerp(next.position, t), shot.target.lerp(next.target, t));
+        view.fovy = fovy + (next_fovy - fovy) * t;
+        Some(view)
+    }
+}
+
+pub fn presentation_from_json(value: &Value) -> Presentation {
+    let defaults = Presentation::default();
+    Presentation {
+        fovy: value["fovy"].as_f64().map_or(defaults.fovy, |v| v as f32),
+        shots: value["shots"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|shot| {
+                Some(Shot {
+                    position: vec3_from_json(&shot["position"])?,
+                    target: vec3_from_json(&shot["target"])?,
+                    duration: shot["duration"].as_f64().map_or(DEFAULT_SHOT_DURATION, |v| v as f32),
+                })
+            })
+            .collect(),
+        path: value["path"].as_str().map(str::to_string),
+        speed: value["speed"].as_f64().map_or(defaults.speed, |v| v as f32),
+        look_at: vec3_from_json(&value["look_at"]).unwrap_or(defaults.look_at),
+    }
+}
+
+pub fn presentation_to_json(presentation: &Presentation) -> Value {
+    let mut value = json!({
+        "fovy": f32_to_json(presentation.fovy),
+        "shots": presentation.shots.iter().map(|shot| json!({
+            "position": vec3_to_json(shot.position),
+            "target": vec3_to_json(shot.target),
+            "duration": f32_to_json(shot.duration),
+        })).collect::<Vec<_>>(),
+    });
+    if let Some(path) = &presentation.path {
+        value["path"] = json!(path);
+        value["speed"] = f32_to_json(presentation.speed);
+        value["look_at"] = vec3_to_json(presentation.look_at);
+    }
+    value
+}
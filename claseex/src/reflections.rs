@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+
+use raylib::ffi;
+use raylib::prelude::*;
+
+use crate::frustum::Frustum;
+use crate::geometry::Vertex;
+use crate::lighting::LightingParams;
+use crate::renderer::{self, ShapeLibrary};
+use crate::scene::{Scene, SceneObject, Shape};
+
+// Largo máximo del rayo reflejado, en unidades de la escena
+const MAX_DISTANCE: f32 = 8.0;
+// Espesor que se le supone a lo que hay en pantalla: más atrás de eso el rayo pasa por detrás
+const THICKNESS: f32 = 0.4;
+
+// Marcha del rayo reflejado sobre el buffer de profundidad. El color de la escena llega en texture0;
+// el buffer de normales guarda la normal en espacio de vista (rg) y la reflectividad (b)
+const SSR_FRAGMENT: &str = r#"#version 330
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform sampler2D normalTexture;
+uniform sampler2D depthTexture;
+uniform mat4 projection;
+uniform mat4 invProjection;
+uniform float maxDistance;
+uniform float thickness;
+uniform int orthographic;
+
+out vec4 finalColor;
+
+const int STEPS = 48;
+const int REFINE_STEPS = 6;
+
+vec3 viewPosition(vec2 uv) {
+    float depth = texture(depthTexture, uv).r;
+    vec4 view = invProjection*vec4(uv*2.0 - 1.0, depth*2.0 - 1.0, 1.0);
+    return view.xyz/view.w;
+}
+
+vec2 project(vec3 position) {
+    vec4 clip = projection*vec4(position, 1.0);
+    return clip.xy/clip.w*0.5 + 0.5;
+}
+
+void main() {
+    vec4 surface = texture(normalTexture, fragTexCoord);
+    float reflectivity = surface.b;
+    if (reflectivity < 0.01) discard;
+
+    vec3 position = viewPosition(fragTexCoord);
+    vec2 xy = surface.rg*2.0 - 1.0;
+    vec3 normal = vec3(xy, sqrt(max(0.0, 1.0 - dot(xy, xy))));
+    // En ortográfica todos los rayos de la cámara son paralelos y no salen del ojo
+    vec3 view = orthographic == 1 ? vec3(0.0, 0.0, -1.0) : normalize(position);
+    vec3 ray = reflect(view, normal);
+
+    float stepLength = maxDistance/float(STEPS);
+    vec3 previous = position;
+    vec2 hitUv = vec2(0.0);
+    float travelled = 1.0;
+    bool hit = false;
+    for (int i = 1; i <= STEPS; i++) {
+        vec3 current = position + ray*stepLength*float(i);
+        vec2 uv = project(current);
+        if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) break;
+        float behind = viewPosition(uv).z - current.z;
+        if (behind > 0.0 && behind < thickness) {
+            // Búsqueda binaria entre el paso anterior y este para no dejar escalones
+            vec3 front = previous;
+            vec3 back = current;
+            for (int j = 0; j < REFINE_STEPS; j++) {
+                vec3 middle = (front + back)*0.5;
+                if (viewPosition(project(middle)).z > middle.z) back = middle;
+                else front = middle;
+            }
+            hitUv = project(back);
+            travelled = float(i)/float(STEPS);
+            hit = true;
+            break;
+        }
+        previous = current;
+    }
+    if (!hit) discard;
+
+    // Se apaga cerca del borde de la pantalla, al final del rayo y cuando el rayo vuelve hacia la cámara
+    vec2 edge = abs(hitUv*2.0 - 1.0);
+    float fade = (1.0 - smoothstep(0.8, 1.0, max(edge.x, edge.y)))*(1.0 - travelled)*(1.0 - smoothstep(0.0, 0.4, ray.z));
+    finalColor = vec4(texture(texture0, hitUv).rgb, reflectivity*fade);
+}
+"#;
+
+// Lo que hace falta para dibujar el color de la escena y el buffer de normales
+pub struct ReflectionContext<'a> {
+    pub library: &'a ShapeLibrary,
+    pub scene: &'a Scene,
+    pub transforms: &'a HashMap<u32, Matrix>,
+    pub frustum: &'a Frustum,
+    pub lighting: &'a LightingParams<'a>,
+    pub wetness: f32,
+}
+
+// Reflectividad con la que se dibuja el objeto: la propia o, en el suelo, la que deja la lluvia
+pub fn surface_reflectivity(object: &SceneObject, wetness: f32) -> f32 {
+    if object.shape == Shape::Plane {
+        object.reflectivity.max(wetness)
+    } else {
+        object.reflectivity
+    }
+}
+
+// Render target con la profundidad en una textura que se puede leer desde el shader
+// (load_render_texture la deja en un renderbuffer)
+fn load_target_with_depth(width: i32, height: i32) -> Result<RenderTexture2D, String> {
+    unsafe {
+        let id = ffi::rlLoadFramebuffer();
+        if id == 0 {
+            return Err("no se pudo crear el framebuffer".to_string());
+        }
+        ffi::rlEnableFramebuffer(id);
+        let format = PixelFormat::PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32;
+        let color = ffi::rlLoadTexture(std::ptr::null(), width, height, format, 1);
+        let depth = ffi::rlLoadTextureDepth(width, height, false);
+        let texture_2d = ffi::rlFramebufferAttachTextureType::RL_ATTACHMENT_TEXTURE2D as i32;
+        ffi::rlFramebufferAttach(
+            id,
+            color,
+            ffi::rlFramebufferAttachType::RL_ATTACHMENT_COLOR_CHANNEL0 as i32,
+            texture_2d,
+            0,
+        );
+        ffi::rlFramebufferAttach(id, depth, ffi::rlFramebufferAttachType::RL_ATTACHMENT_DEPTH as i32, texture_2d, 0);
+        let complete = ffi::rlFramebufferComplete(id);
+        ffi::rlDisableFramebuffer();
+        // Al soltarlo se libera todo junto, como cualquier otra textura de render
+        let target = RenderTexture2D::from_raw(ffi::RenderTexture2D {
+            id,
+            texture: ffi::Texture2D {
+                id: color,
+                width,
+                height,
+                mipmaps: 1,
+                format,
+            },
+            depth: ffi::Texture2D {
+                id: depth,
+                width,
+                height,
+                mipmaps: 1,
+                format: 19, // Profundidad de 24 bits, el mismo valor que usa raylib
+            },
+        });
+        if complete { Ok(target) } else { Err("el framebuffer quedó incompleto".to_string()) }
+    }
+}
+
+// Reflejos en espacio de pantalla: la escena se dibuja en una textura junto con sus normales y
+// profundidad, y un shader busca sobre esa imagen qué ve cada superficie reflejante
+#[derive(Default)]
+pub struct Reflections {
+    shader: Option<Shader>,
+    color: Option<RenderTexture2D>,
+    normals: Option<RenderTexture2D>, // Con la profundidad en textura
+    projection: Matrix,
+    orthographic: bool,
+    ready: bool, // Hay un frame renderizado para componer
+}
+
+impl Reflections {
+    fn load_shader(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) -> Result<(), String> {
+        if self.shader.is_some() {
+            return Ok(());
+        }
+        let shader = rl.load_shader_from_memory(thread, None, Some(SSR_FRAGMENT));
+        // Si no compila raylib devuelve su shader por defecto
+        if shader.id == unsafe { ffi::rlGetShaderIdDefault() } {
+            return Err("no compiló el shader de reflejos".to_string());
+        }
+        self.shader = Some(shader);
+        Ok(())
+    }
+
+    // Dibuja el color y las normales de la escena al tamaño en que se va a componer; va antes de
+    // begin_drawing. Si nada refleja no se hace el trabajo y `draw` no compone nada
+    pub fn render(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        context: &ReflectionContext,
+        camera: Camera3D,
+        background: Color,
+        size: (u32, u32),
+    ) -> Result<(), String> {
+        let ReflectionContext {
+            library,
+            scene,
+            transforms,
+            frustum,
+            lighting,
+            wetness,
+        } = *context;
+        self.ready = false;
+        if !scene.objects.iter().any(|object| surface_reflectivity(object, wetness) > 0.0) {
+            return Ok(());
+        }
+        self.load_shader(rl, thread)?;
+
+        let (width, height) = (size.0 as i32, size.1 as i32);
+        let outdated = |target: &Option<RenderTexture2D>| {
+            target
+                .as_ref()
+                .is_none_or(|texture| texture.texture.width != width || texture.texture.height != height)
+        };
+        if outdated(&self.color) {
+            self.color = Some(rl.load_render_texture(thread, width as u32, height as u32).map_err(|e| e.to_string())?);
+        }
+        if outdated(&self.normals) {
+            self.normals = Some(load_target_with_depth(width, height)?);
+        }
+        let color = self.color.as_mut().ok_or("sin textura de render")?;
+        let normals = self.normals.as_mut().ok_or("sin textura de render")?;
+
+        {
+            let mut texture = rl.begin_texture_mode(thread, color);
+            texture.clear_background(background);
+            let mut d3d = texture.begin_mode3D(camera);
+            renderer::draw_scene(&mut d3d, library, scene, transforms, frustum, lighting, &HashSet::new());
+        }
+
+        // Normal en espacio de vista: con la cámara mirando hacia -z, las caras visibles tienen z
+        // positiva y alcanza con guardar x e y
+        let view = Matrix::look_at(camera.position, camera.target, camera.up);
+        let shade = |object: &SceneObject, vertex: &Vertex| {
+            let normal = renderer::transform_direction(&view, vertex.normal);
+            let channel = |c: f32| ((c * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0) as u8;
+            let reflectivity = (surface_reflectivity(object, wetness) * 255.0) as u8;
+            Color::new(channel(normal.x), channel(normal.y), reflectivity, 255)
+        };
+        {
+            let mut texture = rl.begin_texture_mode(thread, normals);
+            texture.clear_background(Color::new(128, 128, 0, 255));
+            let mut d3d = texture.begin_mode3D(camera);
+            renderer::draw_scene_with(&mut d3d, library, scene, transforms, frustum, &shade);
+        }
+
+        // La misma proyección que arma begin_mode3D para ese tamaño; en ortográfica fovy es el alto visible
+        let aspect = width as f32 / height as f32;
+        let (near, far) = (ffi::RL_CULL_DISTANCE_NEAR as f32, ffi::RL_CULL_DISTANCE_FAR as f32);
+        self.orthographic = camera.camera_type() == CameraProjection::CAMERA_ORTHOGRAPHIC;
+        self.projection = if self.orthographic {
+            let top = camera.fovy * 0.5;
+            Matrix::ortho(-top * aspect, top * aspect, -top, top, near, far)
+        } else {
+            Matrix::perspective(camera.fovy.to_radians(), aspect, near, far)
+        };
+        self.ready = true;
+        Ok(())
+    }
+
+    // Compone los reflejos sobre lo que ya se dibujó en pantalla, mezclados según la reflectividad
+    pub fn draw(&mut self, d: &mut impl RaylibDraw) {
+        if !self.ready {
+            return;
+        }
+        let (Some(shader), Some(color), Some(normals)) = (self.shader.as_mut(), &self.color, &self.normals) else {
+            return;
+        };
+        let location = |shader: &Shader, name: &str| shader.get_shader_location(name);
+        let normal_location = location(shader, "normalTexture");
+        let depth_location = location(shader, "depthTexture");
+        let projection_location = location(shader, "projection");
+        let inverse_location = location(shader, "invProjection");
+        let distance_location = location(shader, "maxDistance");
+        let thickness_location = location(shader, "thickness");
+        let orthographic_location = location(shader, "orthographic");
+        shader.set_shader_value_texture(normal_location, normals.texture());
+        unsafe {
+            ffi::SetShaderValueTexture(*shader.as_ref(), depth_location, normals.depth);
+        }
+        shader.set_shader_value_matrix(projection_location, self.projection);
+        shader.set_shader_value_matrix(inverse_location, self.projection.inverted());
+        shader.set_shader_value(distance_location, MAX_DISTANCE);
+        shader.set_shader_value(thickness_location, THICKNESS);
+        shader.set_shader_value(orthographic_location, self.orthographic as i32);
+
+        // Las texturas de render quedan invertidas en Y
+        let (width, height) = (color.texture.width as f32, color.texture.height as f32);
+        let source = Rectangle::new(0.0, 0.0, width, -height);
+        let mut shaded = d.begin_shader_mode(shader);
+        shaded.draw_texture_rec(color.texture(), source, Vector2::zero(), Color::WHITE);
+    }
+}
@@ -0,0 +1,80 @@
+use raylib::prelude::*;
+
+pub const MIN_RENDER_SCALE: f32 = 0.5;
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+pub const RENDER_SCALE_STEP: f32 = 0.25;
+
+// Tamaño de la imagen interna para la escala elegida; nunca menor a un pixel
+pub fn scaled_size(screen: (u32, u32), scale: f32) -> (u32, u32) {
+    let axis = |value: u32| ((value as f32 * scale).round() as u32).max(1);
+    (axis(screen.0), axis(screen.1))
+}
+
+// Textura interna donde se dibuja la escena 3D cuando la escala de render no es 100%: por debajo
+// alivia a las máquinas lentas y por encima suaviza los bordes al reducirse a la ventana
+#[derive(Default)]
+pub struct SceneTarget {
+    target: Option<RenderTexture2D>,
+}
+
+impl SceneTarget {
+    // Prepara la textura para el tamaño de este frame; con escala 1 se dibuja directo en la ventana
+    pub fn prepare(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        screen: (u32, u32),
+        scale: f32,
+    ) -> Result<(), String> {
+        if (scale - 1.0).abs() < 1e-3 {
+            self.target = None;
+            return Ok(());
+        }
+        let (width, height) = scaled_size(screen, scale);
+        let outdated = self
+            .target
+            .as_ref()
+            .is_none_or(|texture| texture.texture.width != width as i32 || texture.texture.height != height as i32);
+        if outdated {
+            let texture = rl.load_render_texture(thread, width, height).map_err(|e| e.to_string())?;
+            // Al ampliar o reducir se promedian los texeles vecinos
+            texture.texture().set_texture_filter(thread, TextureFilter::TEXTURE_FILTER_BILINEAR);
+            self.target = Some(texture);
+        }
+        Ok(())
+    }
+
+    pub fn active(&self) -> bool {
+        self.target.is_some()
+    }
+
+    // Dibuja la pasada 3D en la textura interna; devuelve false si no hay textura porque la escala
+    // es 100% y hay que dibujar directo en la ventana
+    pub fn render(
+        &mut self,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+        background: Color,
+        draw: impl FnOnce(&mut RaylibTextureMode<RaylibHandle>),
+    ) -> bool {
+        let Some(target) = &mut self.target else {
+            return false;
+        };
+        let mut texture = rl.begin_texture_mode(thread, target);
+        texture.clear_background(background);
+        draw(&mut texture);
+        true
+    }
+
+    // Estira la textura interna sobre toda la ventana
+    pub fn present(&self, d: &mut RaylibDrawHandle) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        let (width, height) = (target.texture.width as f32, target.texture.height as f32);
+        // Las texturas de render quedan invertidas en Y
+        let source = Rectangle::new(0.0, 0.0, width, -height);
+        let destination = Rectangle::new(0.0, 0.0, d.get_screen_width() as f32, d.get_screen_height() as f32);
+        d.draw_texture_pro(target.texture(), source, destination, Vector2::zero(), 0.0, Color::WHITE);
+    }
+}
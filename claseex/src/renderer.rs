@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+
+use raylib::ffi;
+use raylib::prelude::*;
+
+use crate::displacement::Displacement;
+use crate::frustum::Frustum;
+use crate::geometry::{self, Geometry, Vertex};
+use crate::lighting::{calculate_diffuse_lighting, Light, LightingParams};
+use crate::lod::LodSettings;
+use crate::scene::{Scene, SceneObject, Shape};
+
+// Máximo de triángulos por lote enviado a rlgl
+const TRIANGLES_PER_BATCH: usize = 1024;
+
+// Geometrías locales de cada forma, generadas una sola vez, con sus versiones simplificadas
+pub struct ShapeLibrary {
+    cube: Geometry,
+    plane: Geometry,
+    sphere: Geometry,
+    lods: Vec<(Shape, f32, Geometry)>, // Forma, distancia desde la que se usa y geometría reducida
+}
+
+impl ShapeLibrary {
+    pub fn new() -> Self {
+        ShapeLibrary {
+            cube: geometry::cube(4),
+            plane: geometry::plane(16),
+            sphere: geometry::sphere(12, 18),
+            lods: Vec::new(),
+        }
+    }
+
+    // Genera los niveles de detalle pedidos en la escena simplificando cada forma
+    pub fn with_lods(settings: &[LodSettings]) -> Self {
+        let mut library = ShapeLibrary::new();
+        for setting in settings {
+            for level in &setting.levels {
+                let reduced = geometry::simplify(library.geometry(setting.shape), level.triangles);
+                if reduced.triangle_count() > level.triangles {
+                    eprintln!(
+                        "LOD de {}: quedó en {} triángulos (se pidieron {})",
+                        setting.shape.name(),
+                        reduced.triangle_count(),
+                        level.triangles
+                    );
+                }
+                library.lods.push((setting.shape, level.distance, reduced));
+            }
+        }
+        library
+    }
+
+    pub fn geometry(&self, shape: Shape) -> &Geometry {
+        match shape {
+            Shape::Cube => &self.cube,
+            Shape::Plane => &self.plane,
+            Shape::Sphere => &self.sphere,
+        }
+    }
+
+    // Geometría para dibujar la forma a cierta distancia: el nivel más lejano que ya se alcanzó
+    pub fn geometry_at(&self, shape: Shape, distance: f32) -> &Geometry {
+        self.lods
+            .iter()
+            .filter(|(lod_shape, from, _)| *lod_shape == shape && distance >= *from)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(self.geometry(shape), |(_, _, geometry)| geometry)
+    }
+}
+
+// Geometría con la que se dibuja un objeto: su sólido CSG si lo tiene, o su forma según la distancia
+pub fn object_geometry<'a>(library: &'a ShapeLibrary, object: &'a SceneObject, distance: f32) -> &'a Geometry {
+    match &object.csg {
+        Some(csg) => &csg.geometry,
+        None => library.geometry_at(object.shape, distance),
+    }
+}
+
+// Transforma una dirección (sin traslación) con la parte 3x3 de la matriz
+pub fn transform_direction(m: &Matrix, v: Vector3) -> Vector3 {
+    Vector3::new(
+        m.m0 * v.x + m.m4 * v.y + m.m8 * v.z,
+        m.m1 * v.x + m.m5 * v.y + m.m9 * v.z,
+        m.m2 * v.x + m.m6 * v.y + m.m10 * v.z,
+    )
+}
+
+// Vértices de una geometría en coordenadas de mundo según su matriz de modelo
+pub fn world_vertices(geometry: &Geometry, model: Matrix) -> Vec<Vertex> {
+    // Las normales se transforman con la inversa transpuesta por si la escala no es uniforme
+    let normal_matrix = model.inverted().transposed();
+    geometry
+        .vertices
+        .iter()
+        .map(|vertex| Vertex {
+            position: vertex.position.transform_with(model),
+            normal: transform_direction(&normal_matrix, vertex.normal).normalized(),
+        })
+        .collect()
+}
+
+// Envía triángulos con color por vértice; requiere estar dentro del modo 3D
+pub fn draw_colored_triangles(_d3d: &mut impl RaylibDraw3D, vertices: &[(Vector3, Vector3, Color)]) {
+    for batch in vertices.chunks(TRIANGLES_PER_BATCH * 3) {
+        unsafe {
+            ffi::rlCheckRenderBatchLimit(batch.len() as i32);
+            ffi::rlBegin(ffi::RL_TRIANGLES as i32);
+            for (position, normal, color) in batch {
+                ffi::rlColor4ub(color.r, color.g, color.b, color.a);
+                ffi::rlNormal3f(normal.x, normal.y, normal.z);
+                ffi::rlVertex3f(position.x, position.y, position.z);
+            }
+            ffi::rlEnd();
+        }
+    }
+}
+
+// Dibuja un objeto con iluminación difusa calculada por vértice
+pub fn draw_object(
+    d3d: &mut impl RaylibDraw3D,
+    geometry: &Geometry,
+    object: &SceneObject,
+    model: Matrix,
+    color: Color,
+    lighting: &LightingParams,
+) {
+    let mut vertices = world_vertices(geometry, model);
+    // Con el shader la luz se calcula sobre la forma quieta y la GPU mueve los vértices después
+    let gpu_displacement = object.displacement.zip(lighting.displacement_shader);
+    let directions = match (gpu_displacement, &object.displacement) {
+        (Some(_), _) => Displacement::directions(&vertices),
+        (None, Some(displacement)) => {
+            displacement.apply(&mut vertices);
+            Vec::new()
+        }
+        (None, None) => Vec::new(),
+    };
+    let lit: Vec<_> = vertices
+        .into_iter()
+        .enumerate()
+        .map(|(index, vertex)| {
+            let lit_color = calculate_diffuse_lighting(
+                vertex.position,
+                vertex.normal,
+                lighting.lights,
+                lighting.shadows,
+                color,
+                lighting.ambient_intensity,
+                lighting.diffuse_intensity,
+            );
+            let final_color = match lighting.fog {
+                Some(fog) => fog.apply(lit_color, vertex.position),
+                None => lit_color,
+            };
+            let normal = directions.get(index).copied().unwrap_or(vertex.normal);
+            (vertex.position, normal, final_color)
+        })
+        .collect();
+    match gpu_displacement {
+        Some((displacement, shader)) => shader.draw(d3d, &displacement, &lit),
+        None => draw_colored_triangles(d3d, &lit),
+    }
+
+    if object.outline && object.shape == Shape::Cube {
+        draw_box_edges(d3d, model, Color::BLACK);
+    }
+}
+
+// Objetos visibles con el color por vértice que devuelve `shade`, sin luz ni niebla; lo usan las
+// pasadas de depuración y el buffer de normales de los reflejos
+pub fn draw_scene_with(
+    d3d: &mut impl RaylibDraw3D,
+    library: &ShapeLibrary,
+    scene: &Scene,
+    transforms: &HashMap<u32, Matrix>,
+    frustum: &Frustum,
+    shade: &dyn Fn(&SceneObject, &Vertex) -> Color,
+) {
+    for object in &scene.objects {
+        if !scene.layer_visible(&object.layer) {
+            continue;
+        }
+        let model = transforms[&object.id];
+        let center = Vector3::zero().transform_with(model);
+        if !frustum.contains_sphere(center, object.bounding_radius()) {
+            continue;
+        }
+        let geometry = object_geometry(library, object, center.distance_to(frustum.apex));
+        let mut vertices = world_vertices(geometry, model);
+        if let Some(displacement) = &object.displacement {
+            displacement.apply(&mut vertices);
+        }
+        let shaded: Vec<_> = vertices
+            .iter()
+            .map(|vertex| (vertex.position, vertex.normal, shade(object, vertex)))
+            .collect();
+        draw_colored_triangles(d3d, &shaded);
+    }
+}
+
+// Aristas de la caja orientada definida por la matriz de modelo
+pub fn draw_box_edges(d3d: &mut impl RaylibDraw3D, model: Matrix, color: Color) {
+    let corner = |i: usize| {
+        Vector3::new(
+            if i & 1 == 0 { -0.5 } else { 0.5 },
+            if i & 2 == 0 { -0.5 } else { 0.5 },
+            if i & 4 == 0 { -0.5 } else { 0.5 },
+        )
+        .transform_with(model)
+    };
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                d3d.draw_line_3D(corner(i), corner(i | bit), color);
+            }
+        }
+    }
+}
+
+// Sombra plana: proyecta el objeto desde la luz sobre el plano horizontal del receptor
+pub fn draw_planar_shadow(
+    d3d: &mut impl RaylibDraw3D,
+    caster: (&Geometry, Matrix),
+    light: &Light,
+    receiver_model: Matrix,
+    shadow_color: Color,
+) {
+    // Un poco por encima del receptor para evitar z-fighting
+    let center = Vector3::zero().transform_with(receiver_model);
+    let height = center.y + 0.01;
+    let half_x = transform_direction(&receiver_model, Vector3::new(0.5, 0.0, 0.0)).length();
+    let half_z = transform_direction(&receiver_model, Vector3::new(0.0, 0.0, 0.5)).length();
+    let up = Vector3::new(0.0, 1.0, 0.0);
+
+    let project = |point: Vector3| -> Option<Vector3> {
+        if point.y >= light.position.y - 0.001 || point.y < height {
+            return None;
+        }
+        let t = (height - light.position.y) / (point.y - light.position.y);
+        let projected = light.position + (point - light.position) * t;
+        let inside = (projected.x - center.x).abs() <= half_x && (projected.z - center.z).abs() <= half_z;
+        inside.then_some(projected)
+    };
+
+    let mut shadow = Vec::new();
+    for triangle in world_vertices(caster.0, caster.1).chunks(3) {
+        // Solo las caras que miran hacia la luz forman la silueta proyectada
+        let triangle_center = (triangle[0].position + triangle[1].position + triangle[2].position) / 3.0;
+        if triangle[0].normal.dot(light.position - triangle_center) <= 0.0 {
+            continue;
+        }
+        let projected: Option<Vec<Vector3>> = triangle.iter().map(|v| project(v.position)).collect();
+        if let Some(points) = projected {
+            // La proyección puede invertir el orden; se corrige para que mire hacia arriba
+            let facing_up = (points[1] - points[0]).cross(points[2] - points[0]).dot(up) >= 0.0;
+            let order = if facing_up { [0, 1, 2] } else { [0, 2, 1] };
+            for index in order {
+                shadow.push((points[index], up, shadow_color));
+            }
+        }
+    }
+    draw_colored_triangles(d3d, &shadow);
+}
+
+// Objetos visibles con iluminación difusa y las sombras planas según los flags de cada capa.
+// Los objetos en `imposters` no se dibujan (van como billboard) pero siguen proyectando sombra.
+// Devuelve las esferas envolventes de los objetos que descartó el frustum
+pub fn draw_scene(
+    d3d: &mut impl RaylibDraw3D,
+    library: &ShapeLibrary,
+    scene: &Scene,
+    transforms: &HashMap<u32, Matrix>,
+    frustum: &Frustum,
+    lighting: &LightingParams,
+    imposters: &HashSet<u32>,
+) -> Vec<(Vector3, f32)> {
+    let mut culled = Vec::new();
+    for object in &scene.objects {
+        if !scene.layer_visible(&object.layer) || imposters.contains(&object.id) {
+            continue;
+        }
+        let model = transforms[&object.id];
+        let center = Vector3::zero().transform_with(model);
+        if !frustum.contains_sphere(center, object.bounding_radius()) {
+            culled.push((center, object.bounding_radius()));
+            continue;
+        }
+        // El sólido CSG si lo tiene; si no, lejos de la cámara va la versión simplificada de la forma
+        let geometry = object_geometry(library, object, center.distance_to(frustum.apex));
+        draw_object(d3d, geometry, object, model, scene.object_color(object), lighting);
+    }
+
+    // Sombras planas de las luces con sombra este frame que no tienen mapa
+    let planar_lights: Vec<&Light> = lighting
+        .shadowed
+        .iter()
+        .filter(|&&index| !lighting.shadows.iter().any(|shadow| shadow.light == index))
+        .filter_map(|&index| lighting.lights.get(index))
+        .collect();
+    if planar_lights.is_empty() {
+        return culled;
+    }
+    let layer_allows = |name: &str, cast: bool| {
+        scene.layer(name).is_none_or(|layer| {
+            layer.visible && if cast { layer.cast_shadows } else { layer.receive_shadows }
+        })
+    };
+    for receiver in scene.objects.iter().filter(|o| o.shape == Shape::Plane) {
+        if !layer_allows(&receiver.layer, false) {
+            continue;
+        }
+        // Color del receptor iluminado solo por la luz ambiente
+        let shadow_color = calculate_diffuse_lighting(
+            receiver.position,
+            Vector3::new(0.0, 1.0, 0.0),
+            &[],
+            &[],
+            scene.object_color(receiver),
+            lighting.ambient_intensity,
+            lighting.diffuse_intensity,
+        );
+        let shadow_color = lighting.fog.map_or(shadow_color, |fog| fog.apply(shadow_color, receiver.position));
+        for caster in &scene.objects {
+            if caster.id == receiver.id || !layer_allows(&caster.layer, true) {
+                continue;
+            }
+            for light in &planar_lights {
+                draw_planar_shadow(
+                    d3d,
+                    (object_geometry(library, caster, 0.0), transforms[&caster.id]),
+                    light,
+                    transforms[&receiver.id],
+                    shadow_color,
+                );
+            }
+        }
+    }
+    culled
+}
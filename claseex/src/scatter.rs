@@ -0,0 +1,130 @@
+use raylib::prelude::*;
+
+use crate::picking;
+use crate::prefab::Prefab;
+use crate::renderer::transform_direction;
+use crate::rng::Rng;
+use crate::scene::{PrefabInstance, Scene, Shape};
+
+// Tope de instancias por pasada, para que una densidad alta no congele el editor
+const MAX_INSTANCES: usize = 300;
+// Altura desde la que se comprueba que no haya otro objeto encima del punto elegido
+const CLEARANCE: f32 = 4.0;
+
+// Parámetros de una pasada de esparcido
+#[derive(Clone, Copy, Debug)]
+pub struct ScatterSettings {
+    pub density: f32, // Instancias por unidad cuadrada de superficie
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub seed: u32,
+}
+
+impl Default for ScatterSettings {
+    fn default() -> Self {
+        ScatterSettings {
+            density: 0.5,
+            min_scale: 0.7,
+            max_scale: 1.3,
+            seed: 1,
+        }
+    }
+}
+
+// Rotación en grados (la que usa SceneObject::frame) que lleva el eje +Y a `normal` y gira `yaw`
+// radianes alrededor de ella. rotate_xyz arma Rz·Ry·Rx, así que se leen los ángulos de esa matriz
+pub fn rotation_from_normal(normal: Vector3, yaw: f32) -> Vector3 {
+    let forward = Vector3::new(yaw.sin(), 0.0, yaw.cos());
+    let mut z = forward - normal * forward.dot(normal);
+    if z.length() < 1e-4 {
+        // La normal es horizontal y coincide con el frente elegido
+        z = Vector3::new(0.0, 1.0, 0.0) - normal * normal.y;
+    }
+    let z = z.normalized();
+    let x = normal.cross(z);
+    Vector3::new(
+        normal.z.atan2(z.z).to_degrees(),
+        x.z.clamp(-1.0, 1.0).asin().to_degrees(),
+        x.y.atan2(x.x).to_degrees(),
+    )
+}
+
+impl Scene {
+    // Reparte instancias del prefab sobre la cara superior de un plano o cubo, o sobre la mitad de
+    // arriba de una esfera, orientadas según la normal y con giro y escala al azar. Se saltean los
+    // puntos que tienen otro objeto encima. Lo esparcido antes con el mismo prefab sobre la misma
+    // superficie se reemplaza. Devuelve cuántas instancias se crearon
+    pub fn scatter(&mut self, prefab: &Prefab, surface_name: &str, settings: ScatterSettings) -> Result<usize, String> {
+        let surface = self
+            .objects
+            .iter()
+            .find(|object| object.name == surface_name && object.instance.is_none() && !object.pooled)
+            .ok_or(format!("No existe la superficie '{}'", surface_name))?;
+        let (surface_id, shape) = (surface.id, surface.shape);
+
+        let previous: Vec<u32> = self
+            .instances
+            .iter()
+            .filter(|instance| instance.prefab == prefab.name && instance.scatter.as_deref() == Some(surface_name))
+            .map(|instance| instance.id)
+            .collect();
+        for id in previous {
+            self.remove_instance(id);
+        }
+
+        let transforms = self.world_transforms();
+        let world = transforms[&surface_id];
+        // Las normales se llevan al mundo con la inversa transpuesta por si la escala no es pareja
+        let normal_matrix = world.inverted().transposed();
+        let axis_x = transform_direction(&world, Vector3::new(1.0, 0.0, 0.0));
+        let axis_z = transform_direction(&world, Vector3::new(0.0, 0.0, 1.0));
+        let mut area = axis_x.cross(axis_z).length();
+        if shape == Shape::Sphere {
+            area *= std::f32::consts::PI * 0.5;
+        }
+        let count = ((area * settings.density.max(0.0)).round() as usize).min(MAX_INSTANCES);
+
+        let mut rng = Rng::new(settings.seed);
+        let mut placed = Vec::new();
+        for _ in 0..count {
+            let (u, v) = (rng.range(-0.5, 0.5), rng.range(-0.5, 0.5));
+            let (local, local_normal) = match shape {
+                Shape::Plane => (Vector3::new(u, 0.0, v), Vector3::new(0.0, 1.0, 0.0)),
+                Shape::Cube => (Vector3::new(u, 0.5, v), Vector3::new(0.0, 1.0, 0.0)),
+                Shape::Sphere => {
+                    // Altura uniforme da puntos uniformes sobre la superficie de la esfera
+                    let height = rng.next_f32();
+                    let angle = rng.range(0.0, std::f32::consts::TAU);
+                    let ring = (1.0 - height * height).sqrt();
+                    let direction = Vector3::new(ring * angle.cos(), height, ring * angle.sin());
+                    (direction * 0.5, direction)
+                }
+            };
+            let yaw = rng.range(0.0, std::f32::consts::TAU);
+            let scale = rng.range(settings.min_scale, settings.max_scale.max(settings.min_scale));
+            let point = local.transform_with(world);
+            let normal = transform_direction(&normal_matrix, local_normal).normalized();
+
+            let ray = Ray::new(point + normal * CLEARANCE, normal * -1.0);
+            if picking::pick(self, &transforms, ray).is_some_and(|hit| hit.id != surface_id) {
+                continue;
+            }
+            placed.push((point, rotation_from_normal(normal, yaw), scale));
+        }
+
+        for (position, rotation, scale) in &placed {
+            let instance = PrefabInstance {
+                id: self.allocate_id(),
+                prefab: prefab.name.clone(),
+                position: *position,
+                rotation: *rotation,
+                scale: *scale,
+                palette_slot: None,
+                scatter: Some(surface_name.to_string()),
+                parts: Vec::new(),
+            };
+            self.instantiate(prefab, instance);
+        }
+        Ok(placed.len())
+    }
+}
@@ -0,0 +1,674 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::audio::{emitter_from_json, emitter_to_json, AudioEmitter};
+use crate::behavior::{behavior_from_json, behavior_to_json, Behavior};
+use crate::csg::{csg_from_json, csg_to_json, Csg};
+use crate::displacement::{displacement_from_json, displacement_to_json, Displacement};
+use crate::lighting::{Light, LightKind};
+use crate::lod::{lod_from_json, lod_to_json, LodSettings};
+use crate::measure::{annotation_from_json, annotation_to_json, Annotation};
+use crate::path::{follow_from_json, follow_to_json, path_from_json, path_to_json, PathFollow, SplinePath};
+use crate::prefab::Prefab;
+use crate::presentation::{presentation_from_json, presentation_to_json, Presentation};
+use crate::trigger::{trigger_from_json, trigger_to_json, Trigger};
+use crate::weather::WeatherPreset;
+
+// Archivo de escena que se carga al iniciar
+pub const DEFAULT_SCENE_PATH: &str = "scenes/diorama.json";
+
+// Formas primitivas que sabe dibujar el diorama
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Cube,
+    Plane,
+    Sphere,
+}
+
+impl Shape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shape::Cube => "cube",
+            Shape::Plane => "plane",
+            Shape::Sphere => "sphere",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Shape> {
+        match name {
+            "cube" => Some(Shape::Cube),
+            "plane" => Some(Shape::Plane),
+            "sphere" => Some(Shape::Sphere),
+            _ => None,
+        }
+    }
+}
+
+// Capa o grupo de objetos con su visibilidad y comportamiento de sombras
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    pub cast_shadows: bool,
+    pub receive_shadows: bool,
+}
+
+impl Layer {
+    pub fn new(name: &str) -> Self {
+        Layer {
+            name: name.to_string(),
+            visible: true,
+            cast_shadows: true,
+            receive_shadows: true,
+        }
+    }
+}
+
+// Objeto del diorama; la rotación está en grados
+#[derive(Clone, Debug)]
+pub struct SceneObject {
+    pub id: u32,
+    pub name: String,
+    pub shape: Shape,
+    pub position: Vector3,
+    pub rotation: Vector3,
+    pub size: Vector3,
+    pub color: Color,
+    pub layer: String,
+    pub outline: bool,         // Dibujar las aristas en negro
+    pub label: Option<String>, // Texto que se muestra sobre el objeto
+    pub parent: Option<u32>,
+    pub palette_slot: Option<usize>, // Si está definido, el color sale de la paleta
+    pub instance: Option<u32>,       // Instancia de prefab a la que pertenece
+    pub follow: Option<PathFollow>,  // Curva que recorre el objeto
+    pub behaviors: Vec<Behavior>,    // Giro, vaivén u órbita que se aplican cada frame
+    pub csg: Option<Csg>,            // Sólido tallado que reemplaza a la forma al dibujar
+    pub displacement: Option<Displacement>, // Vértices movidos por ruido animado
+    pub reflectivity: f32,                  // Cuánto refleja de lo que hay en pantalla (0 a 1)
+    pub pooled: bool,                       // Lo creó el pool de objetos dinámicos; no se guarda
+}
+
+impl SceneObject {
+    // Escala del objeto; los tamaños en cero (planos) se tratan como 1
+    pub fn scale(&self) -> Matrix {
+        let axis = |value: f32| if value > 0.0 { value } else { 1.0 };
+        Matrix::scale(axis(self.size.x), axis(self.size.y), axis(self.size.z))
+    }
+
+    // Marco local sin escala: rotación y luego traslación; es lo que heredan los hijos
+    pub fn frame(&self) -> Matrix {
+        let rotation = Vector3::new(
+            self.rotation.x.to_radians(),
+            self.rotation.y.to_radians(),
+            self.rotation.z.to_radians(),
+        );
+        Matrix::rotate_xyz(rotation) * Matrix::translate(self.position.x, self.position.y, self.position.z)
+    }
+
+    // Radio de la esfera envolvente usada para el culling
+    pub fn bounding_radius(&self) -> f32 {
+        self.size.length() * 0.5
+    }
+}
+
+// Uso de un prefab dentro de la escena con sus valores sobrescritos
+#[derive(Clone, Debug)]
+pub struct PrefabInstance {
+    pub id: u32,
+    pub prefab: String,
+    pub position: Vector3,
+    pub rotation: Vector3,
+    pub scale: f32, // Escala uniforme de todas las partes
+    pub palette_slot: Option<usize>,
+    pub scatter: Option<String>, // Superficie sobre la que la esparció la herramienta de esparcido
+    // Ids de las partes en el orden del prefab, con cómo quedaron al instanciar; no se guarda y sirve
+    // para guardar solo las partes editadas a mano
+    pub parts: Vec<(u32, Value)>,
+}
+
+// Escena completa: capas, objetos, luces, sonidos, curvas, triggers, notas y parámetros de iluminación
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub layers: Vec<Layer>,
+    pub objects: Vec<SceneObject>,
+    pub lights: Vec<Light>,
+    pub emitters: Vec<AudioEmitter>,
+    pub paths: Vec<SplinePath>,
+    pub triggers: Vec<Trigger>,
+    pub annotations: Vec<Annotation>,
+    pub palette: Vec<Color>,
+    pub instances: Vec<PrefabInstance>,
+    pub lod: Vec<LodSettings>, // Niveles de detalle de cada forma
+    pub ambient_intensity: f32,
+    pub day_ambient: Option<f32>, // Luz ambiente de día mientras dura el modo noche
+    pub diffuse_intensity: f32,
+    pub weather: WeatherPreset,
+    pub presentation: Presentation, // Cámara que se usa al darle Play
+    pub baseline: Option<Value>, // Contenido del archivo cargado, para exportar parches
+    next_id: u32,
+}
+
+impl Scene {
+    // Escena mínima usada cuando no se encuentra el archivo
+    pub fn fallback() -> Self {
+        let mut scene = Scene {
+            layers: vec![Layer::new("terrain"), Layer::new("props"), Layer::new("debug")],
+            objects: Vec::new(),
+            lights: vec![Light::point(Vector3::new(3.0, 4.0, 2.0), Color::WHITE, 1.0, 10.0)],
+            emitters: Vec::new(),
+            paths: Vec::new(),
+            triggers: Vec::new(),
+            annotations: Vec::new(),
+            palette: Vec::new(),
+            instances: Vec::new(),
+            lod: Vec::new(),
+            ambient_intensity: 0.3,
+            day_ambient: None,
+            diffuse_intensity: 0.7,
+            weather: WeatherPreset::Clear,
+            presentation: Presentation::default(),
+            baseline: None,
+            next_id: 1,
+        };
+        scene.add_object(
+            "suelo",
+            Shape::Plane,
+            Vector3::new(0.0, -2.0, 0.0),
+            Vector3::new(10.0, 0.0, 10.0),
+            Color::new(100, 100, 100, 255),
+            "terrain",
+        );
+        let cube = scene.add_object(
+            "cubo",
+            Shape::Cube,
+            Vector3::zero(),
+            Vector3::new(2.0, 2.0, 2.0),
+            Color::new(100, 150, 255, 255),
+            "props",
+        );
+        if let Some(cube) = scene.object_mut(cube) {
+            cube.behaviors.push(Behavior::Spinner {
+                speed: Vector3::new(20.0, 30.0, 25.0),
+            });
+        }
+        scene
+    }
+
+    pub fn add_object(
+        &mut self,
+        name: &str,
+        shape: Shape,
+        position: Vector3,
+        size: Vector3,
+        color: Color,
+        layer: &str,
+    ) -> u32 {
+        let id = self.allocate_id();
+        self.ensure_layer(layer);
+        self.objects.push(SceneObject {
+            id,
+            name: name.to_string(),
+            shape,
+            position,
+            rotation: Vector3::zero(),
+            size,
+            color,
+            layer: layer.to_string(),
+            outline: shape == Shape::Cube,
+            label: None,
+            parent: None,
+            palette_slot: None,
+            instance: None,
+            follow: None,
+            behaviors: Vec::new(),
+            csg: None,
+            displacement: None,
+            reflectivity: 0.0,
+            pooled: false,
+        });
+        id
+    }
+
+    pub fn allocate_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    // No vuelve a dar ids por debajo de `next_id`, que pueden seguir en uso fuera del archivo
+    pub fn reserve_ids(&mut self, next_id: u32) {
+        self.next_id = self.next_id.max(next_id);
+    }
+
+    // Crea la capa con valores por defecto si todavía no existe
+    pub fn ensure_layer(&mut self, name: &str) {
+        if self.layer(name).is_none() {
+            self.layers.push(Layer::new(name));
+        }
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn layer_visible(&self, name: &str) -> bool {
+        self.layer(name).is_none_or(|layer| layer.visible)
+    }
+
+    pub fn find_by_name_mut(&mut self, name: &str) -> Option<&mut SceneObject> {
+        self.objects.iter_mut().find(|object| object.name == name)
+    }
+
+    pub fn object(&self, id: u32) -> Option<&SceneObject> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+
+    pub fn object_mut(&mut self, id: u32) -> Option<&mut SceneObject> {
+        self.objects.iter_mut().find(|object| object.id == id)
+    }
+
+    // Color final del objeto, tomando la paleta si usa un slot
+    pub fn object_color(&self, object: &SceneObject) -> Color {
+        object
+            .palette_slot
+            .and_then(|slot| self.palette.get(slot).copied())
+            .unwrap_or(object.color)
+    }
+
+    // Matrices de mundo de todos los objetos, componiendo la jerarquía padre-hijo
+    pub fn world_transforms(&self) -> HashMap<u32, Matrix> {
+        let mut frames = HashMap::new();
+        self.objects
+            .iter()
+            .map(|object| (object.id, object.scale() * self.world_frame(object, &mut frames, 0)))
+            .collect()
+    }
+
+    fn world_frame(&self, object: &SceneObject, cache: &mut HashMap<u32, Matrix>, depth: usize) -> Matrix {
+        if let Some(frame) = cache.get(&object.id) {
+            return *frame;
+        }
+        let local = object.frame();
+        // El límite de profundidad protege contra ciclos en archivos mal formados
+        let world = match object.parent.and_then(|id| self.object(id)) {
+            Some(parent) if depth < 64 => local * self.world_frame(parent, cache, depth + 1),
+            _ => local,
+        };
+        cache.insert(object.id, world);
+        world
+    }
+
+    // Ids del objeto y de todos sus descendientes, empezando por la raíz
+    pub fn subtree(&self, root: u32) -> Vec<u32> {
+        let mut ids = vec![root];
+        let mut index = 0;
+        while index < ids.len() {
+            let current = ids[index];
+            for child in self.objects.iter().filter(|o| o.parent == Some(current)) {
+                if !ids.contains(&child.id) {
+                    ids.push(child.id);
+                }
+            }
+            index += 1;
+        }
+        ids
+    }
+
+    pub fn load(path: &str) -> Result<Scene, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        let mut scene = Scene::from_json(&value)?;
+        // Se normaliza con to_json para que el parche no registre diferencias de formato
+        scene.baseline = Some(scene.to_json());
+        Ok(scene)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.to_json()).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn from_json(value: &Value) -> Result<Scene, String> {
+        let mut scene = Scene {
+            layers: Vec::new(),
+            objects: Vec::new(),
+            lights: Vec::new(),
+            emitters: Vec::new(),
+            paths: Vec::new(),
+            triggers: Vec::new(),
+            annotations: Vec::new(),
+            palette: value["palette"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(color_from_json)
+                .collect(),
+            instances: Vec::new(),
+            lod: lod_from_json(&value["lod"])?,
+            ambient_intensity: value["ambient_intensity"].as_f64().unwrap_or(0.3) as f32,
+            day_ambient: None,
+            diffuse_intensity: value["diffuse_intensity"].as_f64().unwrap_or(0.7) as f32,
+            weather: value["weather"]
+                .as_str()
+                .and_then(WeatherPreset::from_name)
+                .unwrap_or(WeatherPreset::Clear),
+            presentation: presentation_from_json(&value["presentation"]),
+            baseline: None,
+            next_id: 1,
+        };
+
+        for layer in value["layers"].as_array().into_iter().flatten() {
+            let name = layer["name"].as_str().ok_or("capa sin nombre")?;
+            scene.layers.push(Layer {
+                name: name.to_string(),
+                visible: layer["visible"].as_bool().unwrap_or(true),
+                cast_shadows: layer["cast_shadows"].as_bool().unwrap_or(true),
+                receive_shadows: layer["receive_shadows"].as_bool().unwrap_or(true),
+            });
+        }
+
+        for light in value["lights"].as_array().into_iter().flatten() {
+            scene.lights.push(light_from_json(light)?);
+        }
+
+        for emitter in value["audio"].as_array().into_iter().flatten() {
+            scene.emitters.push(emitter_from_json(emitter)?);
+        }
+
+        for path in value["paths"].as_array().into_iter().flatten() {
+            scene.paths.push(path_from_json(path)?);
+        }
+
+        for trigger in value["triggers"].as_array().into_iter().flatten() {
+            scene.triggers.push(trigger_from_json(trigger)?);
+        }
+
+        scene.annotations = value["annotations"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(annotation_from_json)
+            .collect();
+
+        for object in value["objects"].as_array().into_iter().flatten() {
+            let object = object_from_json(object, scene.next_id)?;
+            scene.next_id = scene.next_id.max(object.id + 1);
+            scene.ensure_layer(&object.layer);
+            scene.objects.push(object);
+        }
+
+        // Las instancias de prefabs se expanden al cargar la escena
+        for value_instance in value["prefabs"].as_array().into_iter().flatten() {
+            let instance = instance_from_json(value_instance, &mut scene)?;
+            match Prefab::load(&instance.prefab) {
+                Ok(prefab) => {
+                    let id = instance.id;
+                    scene.instantiate(&prefab, instance);
+                    scene.apply_part_overrides(id, value_instance)?;
+                }
+                Err(error) => eprintln!("No se pudo instanciar el prefab: {}", error),
+            }
+        }
+
+        Ok(scene)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            // El modo noche no se guarda: se escriben los valores de día
+            "ambient_intensity": f32_to_json(self.day_ambient.unwrap_or(self.ambient_intensity)),
+            "diffuse_intensity": f32_to_json(self.diffuse_intensity),
+            "weather": self.weather.name(),
+            "layers": self.layers.iter().map(|layer| json!({
+                "name": layer.name,
+                "visible": layer.visible,
+                "cast_shadows": layer.cast_shadows,
+                "receive_shadows": layer.receive_shadows,
+            })).collect::<Vec<_>>(),
+            "palette": self.palette.iter().map(|color| color_to_json(*color)).collect::<Vec<_>>(),
+            "lod": lod_to_json(&self.lod),
+            "lights": self.lights.iter().map(light_to_json).collect::<Vec<_>>(),
+            "audio": self.emitters.iter().map(emitter_to_json).collect::<Vec<_>>(),
+            "paths": self.paths.iter().map(path_to_json).collect::<Vec<_>>(),
+            "triggers": self.triggers.iter().map(trigger_to_json).collect::<Vec<_>>(),
+            "annotations": self.annotations.iter().map(annotation_to_json).collect::<Vec<_>>(),
+            // Los objetos que vienen de un prefab se guardan como referencia a la instancia y los
+            // dinámicos no se guardan
+            "objects": self
+                .objects
+                .iter()
+                .filter(|object| object.instance.is_none() && !object.pooled)
+                .map(object_to_json)
+                .collect::<Vec<_>>(),
+            "prefabs": self.instances.iter().map(|instance| instance_to_json(instance, self)).collect::<Vec<_>>(),
+        });
+        if !self.presentation.is_empty() {
+            value["presentation"] = presentation_to_json(&self.presentation);
+        }
+        value
+    }
+}
+
+pub fn object_from_json(value: &Value, default_id: u32) -> Result<SceneObject, String> {
+    let shape_name = value["shape"].as_str().unwrap_or("cube");
+    let shape = Shape::from_name(shape_name).ok_or(format!("forma desconocida: {}", shape_name))?;
+    Ok(SceneObject {
+        id: value["id"].as_u64().map(|id| id as u32).unwrap_or(default_id),
+        name: value["name"].as_str().unwrap_or("objeto").to_string(),
+        shape,
+        position: vec3_from_json(&value["position"]).unwrap_or(Vector3::zero()),
+        rotation: vec3_from_json(&value["rotation"]).unwrap_or(Vector3::zero()),
+        size: vec3_from_json(&value["size"]).unwrap_or(Vector3::one()),
+        color: color_from_json(&value["color"]).unwrap_or(Color::WHITE),
+        layer: value["layer"].as_str().unwrap_or("props").to_string(),
+        outline: value["outline"].as_bool().unwrap_or(false),
+        label: value["label"].as_str().map(str::to_string),
+        parent: value["parent"].as_u64().map(|id| id as u32),
+        palette_slot: value["palette_slot"].as_u64().map(|slot| slot as usize),
+        instance: None,
+        follow: follow_from_json(&value["follow"]),
+        behaviors: value["behaviors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(behavior_from_json)
+            .collect::<Result<Vec<_>, _>>()?,
+        // El sólido se calcula una sola vez, al cargar
+        csg: match &value["csg"] {
+            Value::Null => None,
+            csg => Some(Csg::new(csg_from_json(csg)?)),
+        },
+        displacement: displacement_from_json(&value["displacement"]),
+        reflectivity: value["reflectivity"].as_f64().map_or(0.0, |v| (v as f32).clamp(0.0, 1.0)),
+        pooled: false,
+    })
+}
+
+pub fn object_to_json(object: &SceneObject) -> Value {
+    let mut value = json!({
+        "id": object.id,
+        "name": object.name,
+        "shape": object.shape.name(),
+        "position": vec3_to_json(object.position),
+        "rotation": vec3_to_json(object.rotation),
+        "size": vec3_to_json(object.size),
+        "color": color_to_json(object.color),
+        "layer": object.layer,
+        "outline": object.outline,
+    });
+    if let Some(label) = &object.label {
+        value["label"] = json!(label);
+    }
+    if let Some(parent) = object.parent {
+        value["parent"] = json!(parent);
+    }
+    if let Some(slot) = object.palette_slot {
+        value["palette_slot"] = json!(slot);
+    }
+    if let Some(follow) = &object.follow {
+        value["follow"] = follow_to_json(follow);
+    }
+    if !object.behaviors.is_empty() {
+        value["behaviors"] = object.behaviors.iter().map(behavior_to_json).collect();
+    }
+    if let Some(csg) = &object.csg {
+        value["csg"] = csg_to_json(&csg.tree);
+    }
+    if let Some(displacement) = &object.displacement {
+        value["displacement"] = displacement_to_json(displacement);
+    }
+    if object.reflectivity > 0.0 {
+        value["reflectivity"] = f32_to_json(object.reflectivity);
+    }
+    value
+}
+
+fn instance_from_json(value: &Value, scene: &mut Scene) -> Result<PrefabInstance, String> {
+    Ok(PrefabInstance {
+        id: scene.allocate_id(),
+        prefab: value["prefab"].as_str().ok_or("instancia sin prefab")?.to_string(),
+        position: vec3_from_json(&value["position"]).unwrap_or(Vector3::zero()),
+        rotation: vec3_from_json(&value["rotation"]).unwrap_or(Vector3::zero()),
+        scale: value["scale"].as_f64().map_or(1.0, |v| v as f32),
+        palette_slot: value["palette_slot"].as_u64().map(|slot| slot as usize),
+        scatter: value["scatter"].as_str().map(str::to_string),
+        parts: Vec::new(),
+    })
+}
+
+// Además de la instancia se guardan las partes editadas a mano, completas, y las borradas, por su
+// índice en el prefab
+fn instance_to_json(instance: &PrefabInstance, scene: &Scene) -> Value {
+    let mut value = json!({
+        "prefab": instance.prefab,
+        "position": vec3_to_json(instance.position),
+        "rotation": vec3_to_json(instance.rotation),
+    });
+    if (instance.scale - 1.0).abs() > 1e-4 {
+        value["scale"] = f32_to_json(instance.scale);
+    }
+    if let Some(slot) = instance.palette_slot {
+        value["palette_slot"] = json!(slot);
+    }
+    if let Some(surface) = &instance.scatter {
+        value["scatter"] = json!(surface);
+    }
+    let mut overrides = Vec::new();
+    let mut removed = Vec::new();
+    for (index, (id, original)) in instance.parts.iter().enumerate() {
+        match scene.object(*id) {
+            Some(object) => {
+                let current = object_to_json(object);
+                if current != *original {
+                    overrides.push(json!({ "part": index, "object": current }));
+                }
+            }
+            None => removed.push(index),
+        }
+    }
+    if !overrides.is_empty() {
+        value["overrides"] = json!(overrides);
+    }
+    if !removed.is_empty() {
+        value["removed"] = json!(removed);
+    }
+    value
+}
+
+fn light_from_json(value: &Value) -> Result<Light, String> {
+    let position = vec3_from_json(&value["position"]).ok_or("luz sin posición")?;
+    let color = color_from_json(&value["color"]).unwrap_or(Color::WHITE);
+    let intensity = value["intensity"].as_f64().unwrap_or(1.0) as f32;
+    let range = value["range"].as_f64().unwrap_or(10.0) as f32;
+    let light = match value["kind"].as_str().unwrap_or("point") {
+        "point" => Light::point(position, color, intensity, range),
+        "spot" => {
+            let target = vec3_from_json(&value["target"]).unwrap_or(Vector3::zero());
+            let cone_angle = value["cone_angle"].as_f64().unwrap_or(30.0) as f32;
+            Light::spot(position, target, color, intensity, range, cone_angle)
+        }
+        other => return Err(format!("tipo de luz desconocido: {}", other)),
+    };
+    Ok(Light {
+        cast_shadows: value["cast_shadows"].as_bool().unwrap_or(true),
+        ..light
+    })
+}
+
+fn light_to_json(light: &Light) -> Value {
+    let mut value = json!({
+        "kind": match light.kind {
+            LightKind::Point => "point",
+            LightKind::Spot => "spot",
+        },
+        "position": vec3_to_json(light.position),
+        "color": color_to_json(light.color),
+        "intensity": f32_to_json(light.day_intensity.unwrap_or(light.intensity)),
+        "range": f32_to_json(light.range),
+    });
+    if light.kind == LightKind::Spot {
+        value["target"] = vec3_to_json(light.position + light.direction);
+        value["cone_angle"] = f32_to_json(light.cone_angle);
+    }
+    if !light.cast_shadows {
+        value["cast_shadows"] = json!(false);
+    }
+    value
+}
+
+// Conversión de vectores y colores al formato de arreglos del archivo de escena
+pub fn vec3_from_json(value: &Value) -> Option<Vector3> {
+    let array = value.as_array()?;
+    if array.len() != 3 {
+        return None;
+    }
+    Some(Vector3::new(
+        array[0].as_f64()? as f32,
+        array[1].as_f64()? as f32,
+        array[2].as_f64()? as f32,
+    ))
+}
+
+pub fn vec3_to_json(v: Vector3) -> Value {
+    json!([f32_to_json(v.x), f32_to_json(v.y), f32_to_json(v.z)])
+}
+
+// Redondea para que el archivo guardado no arrastre ruido de precisión de f32
+pub fn f32_to_json(v: f32) -> Value {
+    json!((v as f64 * 10000.0).round() / 10000.0)
+}
+
+pub fn color_from_json(value: &Value) -> Option<Color> {
+    let array = value.as_array()?;
+    let channel = |index: usize, default: u64| {
+        array.get(index).and_then(Value::as_u64).unwrap_or(default).min(255) as u8
+    };
+    if array.len() < 3 {
+        return None;
+    }
+    Some(Color::new(channel(0, 0), channel(1, 0), channel(2, 0), channel(3, 255)))
+}
+
+pub fn color_to_json(color: Color) -> Value {
+    json!([color.r, color.g, color.b, color.a])
+}
+
+pub fn camera_to_json(camera: &Camera3D) -> Value {
+    json!({
+        "position": vec3_to_json(camera.position),
+        "target": vec3_to_json(camera.target),
+        "up": vec3_to_json(camera.up),
+        "fovy": f32_to_json(camera.fovy),
+    })
+}
+
+pub fn camera_from_json(value: &Value) -> Option<Camera3D> {
+    Some(Camera3D::perspective(
+        vec3_from_json(&value["position"])?,
+        vec3_from_json(&value["target"])?,
+        vec3_from_json(&value["up"])?,
+        value["fovy"].as_f64()? as f32,
+    ))
+}
@@ -0,0 +1,190 @@
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::photo::PhotoMode;
+use crate::picking::PickBackend;
+use crate::render_scale::{MAX_RENDER_SCALE, MIN_RENDER_SCALE};
+use crate::scene::{camera_from_json, camera_to_json, f32_to_json};
+use crate::shadow::{DEFAULT_SHADOW_BUDGET, MAX_SHADOW_BUDGET};
+
+pub const SESSION_PATH: &str = "sesion.json";
+// Marcadores de cámara: Ctrl+1..4 guarda la vista, 1..4 vuelve a ella
+pub const BOOKMARK_SLOTS: usize = 4;
+
+// Todo lo que no es la escena y conviene recuperar al volver a abrir: cámara, marcadores,
+// paneles, vistas de depuración, paleta y ajustes de la imagen
+pub struct Session {
+    pub camera: Camera3D,
+    pub free_camera: bool,
+    pub orthographic: bool,
+    pub holding_view: bool, // La órbita quedó detenida en una vista del cubo de navegación
+    pub bookmarks: [Option<Camera3D>; BOOKMARK_SLOTS],
+    pub show_layer_panel: bool,
+    pub show_rig_panel: bool,
+    pub show_asset_browser: bool,
+    pub show_triggers: bool,
+    pub show_labels: bool,
+    pub show_light_volumes: bool,
+    pub view_from_debug: bool,
+    pub view_distance: f32,
+    pub use_imposters: bool,
+    pub cube_shadows: bool,
+    pub shadow_budget: usize, // Luces que pueden proyectar sombra por frame
+    pub reflections: bool,
+    pub render_scale: f32, // Resolución interna de la escena respecto de la ventana
+    pub pick_backend: PickBackend,
+    pub selected_light: usize,
+    pub next_palette_slot: usize,
+    pub time_scale: f32,
+    pub photo: PhotoMode, // Lente y exposición; el modo foto siempre arranca apagado
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            camera: Camera3D::perspective(
+                Vector3::new(5.0, 5.0, 5.0), // Posición de la cámara
+                Vector3::new(0.0, 0.0, 0.0), // Punto al que mira
+                Vector3::new(0.0, 1.0, 0.0), // Vector up
+                45.0,                        // FOV
+            ),
+            free_camera: false,
+            orthographic: false,
+            holding_view: false,
+            bookmarks: [None; BOOKMARK_SLOTS],
+            show_layer_panel: true,
+            show_rig_panel: false,
+            show_asset_browser: false,
+            show_triggers: false,
+            show_labels: true,
+            show_light_volumes: false,
+            view_from_debug: false,
+            view_distance: 20.0,
+            use_imposters: true,
+            cube_shadows: true,
+            shadow_budget: DEFAULT_SHADOW_BUDGET,
+            reflections: true,
+            render_scale: 1.0,
+            pick_backend: PickBackend::Ray,
+            selected_light: 0,
+            next_palette_slot: 0,
+            time_scale: 1.0,
+            photo: PhotoMode::new(),
+        }
+    }
+}
+
+impl Session {
+    pub fn load(path: &str) -> Result<Session, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Session::from_json(&value))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.to_json()).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Los campos que falten o no se entiendan quedan con su valor por defecto
+    pub fn from_json(value: &Value) -> Session {
+        let defaults = Session::default();
+        let flag = |key: &str, default: bool| value[key].as_bool().unwrap_or(default);
+        let number = |key: &str, default: f32| value[key].as_f64().map_or(default, |v| v as f32);
+        let index = |key: &str, default: usize| value[key].as_u64().map_or(default, |v| v as usize);
+
+        let mut bookmarks = [None; BOOKMARK_SLOTS];
+        if let Some(saved) = value["bookmarks"].as_array() {
+            for (slot, bookmark) in bookmarks.iter_mut().zip(saved) {
+                *slot = camera_from_json(bookmark);
+            }
+        }
+
+        let photo_value = &value["photo"];
+        let photo_number = |key: &str, default: f32| photo_value[key].as_f64().map_or(default, |v| v as f32);
+        let photo = PhotoMode {
+            active: false,
+            show_guides: photo_value["show_guides"].as_bool().unwrap_or(defaults.photo.show_guides),
+            roll: photo_number("roll", defaults.photo.roll),
+            fov: photo_number("fov", defaults.photo.fov),
+            aperture: photo_number("aperture", defaults.photo.aperture),
+            focus_distance: photo_number("focus_distance", defaults.photo.focus_distance),
+            exposure: photo_number("exposure", defaults.photo.exposure),
+            auto_exposure: photo_value["auto_exposure"].as_bool().unwrap_or(defaults.photo.auto_exposure),
+            supersample: photo_number("supersample", defaults.photo.supersample),
+        };
+
+        Session {
+            camera: camera_from_json(&value["camera"]).unwrap_or(defaults.camera),
+            free_camera: flag("free_camera", defaults.free_camera),
+            orthographic: flag("orthographic", defaults.orthographic),
+            holding_view: flag("holding_view", defaults.holding_view),
+            bookmarks,
+            show_layer_panel: flag("show_layer_panel", defaults.show_layer_panel),
+            show_rig_panel: flag("show_rig_panel", defaults.show_rig_panel),
+            show_asset_browser: flag("show_asset_browser", defaults.show_asset_browser),
+            show_triggers: flag("show_triggers", defaults.show_triggers),
+            show_labels: flag("show_labels", defaults.show_labels),
+            show_light_volumes: flag("show_light_volumes", defaults.show_light_volumes),
+            view_from_debug: flag("view_from_debug", defaults.view_from_debug),
+            view_distance: number("view_distance", defaults.view_distance),
+            use_imposters: flag("use_imposters", defaults.use_imposters),
+            cube_shadows: flag("cube_shadows", defaults.cube_shadows),
+            shadow_budget: index("shadow_budget", defaults.shadow_budget).min(MAX_SHADOW_BUDGET),
+            reflections: flag("reflections", defaults.reflections),
+            render_scale: number("render_scale", defaults.render_scale).clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE),
+            pick_backend: match value["pick_backend"].as_str() {
+                Some("id_buffer") => PickBackend::IdBuffer,
+                Some("ray") => PickBackend::Ray,
+                _ => defaults.pick_backend,
+            },
+            selected_light: index("selected_light", defaults.selected_light),
+            next_palette_slot: index("next_palette_slot", defaults.next_palette_slot),
+            time_scale: number("time_scale", defaults.time_scale),
+            photo,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "camera": camera_to_json(&self.camera),
+            "free_camera": self.free_camera,
+            "orthographic": self.orthographic,
+            "holding_view": self.holding_view,
+            "bookmarks": self.bookmarks
+                .iter()
+                .map(|bookmark| bookmark.as_ref().map_or(Value::Null, camera_to_json))
+                .collect::<Vec<_>>(),
+            "show_layer_panel": self.show_layer_panel,
+            "show_rig_panel": self.show_rig_panel,
+            "show_asset_browser": self.show_asset_browser,
+            "show_triggers": self.show_triggers,
+            "show_labels": self.show_labels,
+            "show_light_volumes": self.show_light_volumes,
+            "view_from_debug": self.view_from_debug,
+            "view_distance": f32_to_json(self.view_distance),
+            "use_imposters": self.use_imposters,
+            "cube_shadows": self.cube_shadows,
+            "shadow_budget": self.shadow_budget,
+            "reflections": self.reflections,
+            "render_scale": f32_to_json(self.render_scale),
+            "pick_backend": match self.pick_backend {
+                PickBackend::Ray => "ray",
+                PickBackend::IdBuffer => "id_buffer",
+            },
+            "selected_light": self.selected_light,
+            "next_palette_slot": self.next_palette_slot,
+            "time_scale": f32_to_json(self.time_scale),
+            "photo": {
+                "show_guides": self.photo.show_guides,
+                "roll": f32_to_json(self.photo.roll),
+                "fov": f32_to_json(self.photo.fov),
+                "aperture": f32_to_json(self.photo.aperture),
+                "focus_distance": f32_to_json(self.photo.focus_distance),
+                "exposure": f32_to_json(self.photo.exposure),
+                "auto_exposure": self.photo.auto_exposure,
+                "supersample": f32_to_json(self.photo.supersample),
+            },
+        })
+    }
+}
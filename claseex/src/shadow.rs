@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::lighting::{Light, LightKind};
+use crate::picking;
+use crate::renderer::transform_direction;
+use crate::scene::{Scene, SceneObject};
+
+// Texeles por lado de cada cara del cubo para la luz más importante; las siguientes usan la mitad
+const RESOLUTION: usize = 32;
+const REDUCED_RESOLUTION: usize = 16;
+// Luces que proyectan sombra por frame; el resto ilumina sin sombra
+pub const DEFAULT_SHADOW_BUDGET: usize = 2;
+pub const MAX_SHADOW_BUDGET: usize = 4;
+// Corrimiento de la superficie sobre la normal para que no se sombree a sí misma
+const NORMAL_OFFSET: f32 = 0.06;
+// Margen de profundidad proporcional a lo que mide un texel a esa distancia
+const DEPTH_BIAS: f32 = 1.5;
+
+// Eje principal y ejes (u, v) de cada cara: +X, -X, +Y, -Y, +Z, -Z
+const FACES: [(Vector3, Vector3, Vector3); 6] = [
+    (Vector3 { x: 1.0, y: 0.0, z: 0.0 }, Vector3 { x: 0.0, y: 0.0, z: 1.0 }, Vector3 { x: 0.0, y: 1.0, z: 0.0 }),
+    (Vector3 { x: -1.0, y: 0.0, z: 0.0 }, Vector3 { x: 0.0, y: 0.0, z: 1.0 }, Vector3 { x: 0.0, y: 1.0, z: 0.0 }),
+    (Vector3 { x: 0.0, y: 1.0, z: 0.0 }, Vector3 { x: 1.0, y: 0.0, z: 0.0 }, Vector3 { x: 0.0, y: 0.0, z: 1.0 }),
+    (Vector3 { x: 0.0, y: -1.0, z: 0.0 }, Vector3 { x: 1.0, y: 0.0, z: 0.0 }, Vector3 { x: 0.0, y: 0.0, z: 1.0 }),
+    (Vector3 { x: 0.0, y: 0.0, z: 1.0 }, Vector3 { x: 1.0, y: 0.0, z: 0.0 }, Vector3 { x: 0.0, y: 1.0, z: 0.0 }),
+    (Vector3 { x: 0.0, y: 0.0, z: -1.0 }, Vector3 { x: 1.0, y: 0.0, z: 0.0 }, Vector3 { x: 0.0, y: 1.0, z: 0.0 }),
+];
+
+// Objeto que puede tapar la luz, con su transformación inversa ya calculada
+struct Caster<'a> {
+    object: &'a SceneObject, // Con su sólido CSG, si lo tiene, para que los huecos dejen pasar la luz
+    inverse: Matrix,
+    center: Vector3,
+    radius: f32,
+}
+
+// Mapa de profundidad en las seis direcciones alrededor de una luz puntual:
+// cada texel guarda la distancia a la primera superficie que ve la luz en esa dirección
+#[derive(Debug)]
+pub struct ShadowCubeMap {
+    pub light: usize, // Índice de la luz en la escena
+    position: Vector3,
+    resolution: usize,
+    faces: [Vec<f32>; 6],
+}
+
+// Cara y coordenadas (u, v) en [-1, 1] de una dirección desde el centro del cubo
+fn face_coordinates(direction: Vector3) -> (usize, f32, f32) {
+    let (ax, ay, az) = (direction.x.abs(), direction.y.abs(), direction.z.abs());
+    let face = if ax >= ay && ax >= az {
+        if direction.x > 0.0 { 0 } else { 1 }
+    } else if ay >= az {
+        if direction.y > 0.0 { 2 } else { 3 }
+    } else if direction.z > 0.0 {
+        4
+    } else {
+        5
+    };
+    let (_, u_axis, v_axis) = FACES[face];
+    let major = ax.max(ay).max(az).max(1e-6);
+    (face, direction.dot(u_axis) / major, direction.dot(v_axis) / major)
+}
+
+impl ShadowCubeMap {
+    // Lanza un rayo por texel desde la luz contra los objetos que proyectan sombra
+    pub fn build(
+        light: usize,
+        position: Vector3,
+        resolution: usize,
+        scene: &Scene,
+        transforms: &HashMap<u32, Matrix>,
+    ) -> Self {
+        let casters: Vec<Caster> = scene
+            .objects
+            .iter()
+            .filter(|object| scene.layer(&object.layer).is_none_or(|layer| layer.visible && layer.cast_shadows))
+            .filter_map(|object| {
+                let world = *transforms.get(&object.id)?;
+                Some(Caster {
+                    object,
+                    inverse: world.inverted(),
+                    center: Vector3::zero().transform_with(world),
+                    radius: object.bounding_radius(),
+                })
+            })
+            .collect();
+        // El origen del rayo en el espacio local de cada objeto no cambia entre texeles
+        let local_origins: Vec<Vector3> = casters.iter().map(|caster| position.transform_with(caster.inverse)).collect();
+
+        let faces = std::array::from_fn(|face| {
+            let (major, u_axis, v_axis) = FACES[face];
+            let mut depths = vec![f32::INFINITY; resolution * resolution];
+            for v in 0..resolution {
+                for u in 0..resolution {
+                    let su = (u as f32 + 0.5) / resolution as f32 * 2.0 - 1.0;
+                    let sv = (v as f32 + 0.5) / resolution as f32 * 2.0 - 1.0;
+                    let direction = (major + u_axis * su + v_axis * sv).normalized();
+                    let mut nearest = f32::INFINITY;
+                    for (caster, origin) in casters.iter().zip(&local_origins) {
+                        // Descarte rápido con la esfera envolvente antes de ir al espacio local
+                        let to_center = caster.center - position;
+                        let along = to_center.dot(direction);
+                        if along + caster.radius < 0.0
+                            || to_center.dot(to_center) - along * along > caster.radius * caster.radius
+                        {
+                            continue;
+                        }
+                        // Dirección unitaria en mundo: el t local es la distancia en mundo
+                        let local_direction = transform_direction(&caster.inverse, direction);
+                        if let Some(t) = picking::intersect_solid(caster.object, *origin, local_direction) {
+                            nearest = nearest.min(t);
+                        }
+                    }
+                    depths[v * resolution + u] = nearest;
+                }
+            }
+            depths
+        });
+
+        ShadowCubeMap {
+            light,
+            position,
+            resolution,
+            faces,
+        }
+    }
+
+    // Texel de la cara que cubre la coordenada (u o v) dada
+    fn texel(&self, coordinate: f32) -> usize {
+        (((coordinate + 1.0) * 0.5 * self.resolution as f32) as usize).min(self.resolution - 1)
+    }
+
+    // 1 si la luz llega al punto, 0 si hay algo en el medio
+    pub fn visibility(&self, surface_position: Vector3, surface_normal: Vector3) -> f32 {
+        let point = surface_position + surface_normal * NORMAL_OFFSET;
+        let to_point = point - self.position;
+        let distance = to_point.length();
+        if distance < 1e-4 {
+            return 1.0;
+        }
+        let (face, u, v) = face_coordinates(to_point);
+        let depth = self.faces[face][self.texel(v) * self.resolution + self.texel(u)];
+        let bias = distance * 2.0 / self.resolution as f32 * DEPTH_BIAS;
+        if distance - bias <= depth { 1.0 } else { 0.0 }
+    }
+
+    // Las seis caras en una grilla de 3x2 (+X -X +Y / -Y +Z -Z), más claro cuanto más cerca de la
+    // luz y azul oscuro donde el rayo no tocó nada. Cada texel de la resolución completa ocupa
+    // `scale` pixeles, así los mapas reducidos se ven del mismo tamaño
+    pub fn depth_image(&self, scale: i32) -> Image {
+        let side = RESOLUTION as i32 * scale;
+        let texel_size = side / self.resolution as i32;
+        let mut image = Image::gen_image_color(side * 3, side * 2, Color::BLACK);
+        let farthest = self
+            .faces
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|depth| depth.is_finite())
+            .fold(1e-3, f32::max);
+        for (face, depths) in self.faces.iter().enumerate() {
+            let (left, top) = ((face % 3) as i32 * side, (face / 3) as i32 * side);
+            for (index, depth) in depths.iter().enumerate() {
+                let (u, v) = ((index % self.resolution) as i32, (index / self.resolution) as i32);
+                let color = if depth.is_finite() {
+                    let shade = ((1.0 - depth / farthest) * 215.0 + 40.0) as u8;
+                    Color::new(shade, shade, shade, 255)
+                } else {
+                    Color::new(10, 20, 60, 255)
+                };
+                // v crece hacia arriba y la imagen hacia abajo
+                let row = self.resolution as i32 - 1 - v;
+                image.draw_rectangle(left + u * texel_size, top + row * texel_size, texel_size, texel_size, color);
+            }
+            let label = ["+X", "-X", "+Y", "-Y", "+Z", "-Z"][face];
+            image.draw_rectangle_lines(Rectangle::new(left as f32, top as f32, side as f32, side as f32), 1, Color::GRAY);
+            image.draw_text(label, left + 4, top + 4, 10, Color::YELLOW);
+        }
+        image
+    }
+}
+
+// Peso de una luz para el presupuesto: su intensidad, reducida según qué tan lejos está de la
+// cámara comparado con su alcance
+fn significance(light: &Light, eye: Vector3) -> f32 {
+    light.intensity * light.range / (light.range + light.position.distance_to(eye))
+}
+
+// Luces que proyectan sombra este frame, de la más importante a la menos: las que tienen la sombra
+// activada, hasta llenar el presupuesto
+pub fn shadowed_lights(lights: &[Light], eye: Vector3, budget: usize) -> Vec<usize> {
+    let mut candidates: Vec<usize> = (0..lights.len())
+        .filter(|&index| lights[index].cast_shadows && lights[index].intensity > 0.0)
+        .collect();
+    candidates.sort_by(|&a, &b| significance(&lights[b], eye).total_cmp(&significance(&lights[a], eye)));
+    candidates.truncate(budget);
+    candidates
+}
+
+// Un mapa por cada luz puntual elegida (las spot siguen con la sombra plana). La primera, la más
+// importante, lleva la resolución completa
+pub fn build_point_shadows(
+    lights: &[Light],
+    shadowed: &[usize],
+    scene: &Scene,
+    transforms: &HashMap<u32, Matrix>,
+) -> Vec<ShadowCubeMap> {
+    shadowed
+        .iter()
+        .filter(|&&index| lights[index].kind == LightKind::Point)
+        .enumerate()
+        .map(|(rank, &index)| {
+            let resolution = if rank == 0 { RESOLUTION } else { REDUCED_RESOLUTION };
+            ShadowCubeMap::build(index, lights[index].position, resolution, scene, transforms)
+        })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+
+use crate::scene::{Scene, Shape};
+
+// Lado de cada celda de la grilla en el plano horizontal
+const CELL_SIZE: f32 = 2.0;
+
+// Objeto indexado: esfera que lo envuelve en coordenadas de mundo
+#[derive(Clone, Copy, Debug)]
+pub struct SpatialEntry {
+    pub root: u32, // Raíz de su jerarquía, para no chocar con las propias partes
+    pub center: Vector3,
+    pub radius: f32,
+}
+
+// Grilla uniforme sobre XZ con los objetos visibles, para preguntar qué hay cerca de un punto sin
+// recorrer toda la escena. Los planos no entran: son suelo, no obstáculos
+pub struct SpatialIndex {
+    entries: Vec<SpatialEntry>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+fn cell_of(value: f32) -> i32 {
+    (value / CELL_SIZE).floor() as i32
+}
+
+impl SpatialIndex {
+    pub fn build(scene: &Scene, transforms: &HashMap<u32, Matrix>) -> Self {
+        let parents: HashMap<u32, u32> =
+            scene.objects.iter().filter_map(|object| Some((object.id, object.parent?))).collect();
+        let root_of = |mut id: u32| {
+            // El tope evita colgarse si la jerarquía tuviera un ciclo
+            for _ in 0..parents.len() {
+                match parents.get(&id) {
+                    Some(parent) => id = *parent,
+                    None => break,
+                }
+            }
+            id
+        };
+
+        let mut index = SpatialIndex {
+            entries: Vec::new(),
+            cells: HashMap::new(),
+        };
+        for object in &scene.objects {
+            if object.shape == Shape::Plane || !scene.layer_visible(&object.layer) {
+                continue;
+            }
+            let Some(world) = transforms.get(&object.id) else {
+                continue;
+            };
+            let entry = SpatialEntry {
+                root: root_of(object.id),
+                center: Vector3::zero().transform_with(*world),
+                radius: object.bounding_radius(),
+            };
+            let slot = index.entries.len();
+            for x in cell_of(entry.center.x - entry.radius)..=cell_of(entry.center.x + entry.radius) {
+                for z in cell_of(entry.center.z - entry.radius)..=cell_of(entry.center.z + entry.radius) {
+                    index.cells.entry((x, z)).or_default().push(slot);
+                }
+            }
+            index.entries.push(entry);
+        }
+        index
+    }
+
+    // Objetos cuya esfera toca el círculo horizontal de centro y radio dados
+    pub fn query(&self, center: Vector3, radius: f32) -> Vec<&SpatialEntry> {
+        let mut slots = Vec::new();
+        for x in cell_of(center.x - radius)..=cell_of(center.x + radius) {
+            for z in cell_of(center.z - radius)..=cell_of(center.z + radius) {
+                if let Some(cell) = self.cells.get(&(x, z)) {
+                    slots.extend_from_slice(cell);
+                }
+            }
+        }
+        // Un objeto grande aparece en varias celdas
+        slots.sort_unstable();
+        slots.dedup();
+        slots
+            .into_iter()
+            .map(|slot| &self.entries[slot])
+            .filter(|entry| {
+                let (dx, dz) = (entry.center.x - center.x, entry.center.z - center.z);
+                (dx * dx + dz * dz).sqrt() <= radius + entry.radius
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use raylib::prelude::*;
+use serde_json::json;
+
+use crate::frustum::Frustum;
+use crate::light_rig::LightRig;
+use crate::lighting::LightingParams;
+use crate::prefab::Prefab;
+use crate::renderer::{self, ShapeLibrary};
+use crate::scene::Scene;
+
+pub const THUMBNAIL_DIR: &str = "thumbnails";
+// Lado de cada cuadro y cantidad de cuadros de la vuelta completa
+pub const THUMBNAIL_SIZE: u32 = 128;
+pub const TURNTABLE_FRAMES: u32 = 8;
+const FOVY: f32 = 30.0;
+// Inclinación de la cámara sobre el horizonte
+const ELEVATION: f32 = 25.0;
+
+pub fn thumbnail_path(name: &str) -> String {
+    format!("{}/{}.png", THUMBNAIL_DIR, name)
+}
+
+// Escena con una sola instancia del prefab en el origen
+fn prefab_scene(name: &str) -> Result<Scene, String> {
+    let scene = Scene::from_json(&json!({
+        "prefabs": [{ "prefab": name, "position": [0.0, 0.0, 0.0] }],
+    }))?;
+    if scene.objects.is_empty() {
+        return Err(format!("no se pudo cargar el prefab '{}'", name));
+    }
+    Ok(scene)
+}
+
+// Centro y radio de la esfera que envuelve a todos los objetos
+fn framing(scene: &Scene) -> (Vector3, f32) {
+    let transforms = scene.world_transforms();
+    let positions: Vec<(Vector3, f32)> = scene
+        .objects
+        .iter()
+        .map(|object| (Vector3::zero().transform_with(transforms[&object.id]), object.bounding_radius()))
+        .collect();
+    let center = positions.iter().fold(Vector3::zero(), |sum, (position, _)| sum + *position) / positions.len() as f32;
+    let radius = positions
+        .iter()
+        .map(|(position, radius)| position.distance_to(center) + radius)
+        .fold(0.1, f32::max);
+    (center, radius)
+}
+
+// Dibuja cada prefab de `prefabs` girando sobre sí mismo y guarda una tira de cuadros por prefab en
+// `thumbnails`, con fondo transparente. La cámara y las luces quedan quietas; gira el objeto
+pub fn render_thumbnails(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    library: &ShapeLibrary,
+) -> Result<Vec<String>, String> {
+    let names = Prefab::available()?;
+    if names.is_empty() {
+        return Err("no hay prefabs para dibujar".to_string());
+    }
+    std::fs::create_dir_all(THUMBNAIL_DIR).map_err(|e| format!("{}: {}", THUMBNAIL_DIR, e))?;
+    let mut frame = rl
+        .load_render_texture(thread, THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .map_err(|e| e.to_string())?;
+    let mut strip = rl
+        .load_render_texture(thread, THUMBNAIL_SIZE * TURNTABLE_FRAMES, THUMBNAIL_SIZE)
+        .map_err(|e| e.to_string())?;
+
+    let mut saved = Vec::new();
+    for name in &names {
+        let mut scene = match prefab_scene(name) {
+            Ok(scene) => scene,
+            Err(error) => {
+                // Un prefab roto no frena al resto
+                eprintln!("Miniatura de '{}': {}", name, error);
+                continue;
+            }
+        };
+        let (center, radius) = framing(&scene);
+        // Distancia a la que la esfera entra justa en el campo de visión, con un margen
+        let distance = radius / (FOVY * 0.5).to_radians().sin() * 1.1;
+        let elevation = ELEVATION.to_radians();
+        let eye = center + Vector3::new(0.0, elevation.sin(), elevation.cos()) * distance;
+        let camera = Camera3D::perspective(eye, center, Vector3::new(0.0, 1.0, 0.0), FOVY);
+        let frustum = Frustum::from_camera(&camera, 1.0, 0.1, distance + radius * 2.0);
+        let rig = LightRig::ThreePoint.build(center, radius, eye);
+        let lighting = LightingParams {
+            lights: &rig.lights,
+            ambient_intensity: rig.ambient_intensity + 0.1,
+            diffuse_intensity: 0.8,
+            fog: None,
+            shadows: &[],
+            shadowed: &[],
+            displacement_shader: None,
+        };
+
+        {
+            let mut target = rl.begin_texture_mode(thread, &mut strip);
+            target.clear_background(Color::BLANK);
+        }
+        let base_rotation: Vec<(u32, Vector3)> = scene
+            .objects
+            .iter()
+            .filter(|object| object.parent.is_none())
+            .map(|object| (object.id, object.rotation))
+            .collect();
+        for index in 0..TURNTABLE_FRAMES {
+            let angle = index as f32 * 360.0 / TURNTABLE_FRAMES as f32;
+            for (id, rotation) in &base_rotation {
+                if let Some(root) = scene.object_mut(*id) {
+                    root.rotation = *rotation + Vector3::new(0.0, angle, 0.0);
+                }
+            }
+            let transforms = scene.world_transforms();
+            {
+                let mut target = rl.begin_texture_mode(thread, &mut frame);
+                target.clear_background(Color::BLANK);
+                let mut d3d = target.begin_mode3D(camera);
+                renderer::draw_scene(&mut d3d, library, &scene, &transforms, &frustum, &lighting, &HashSet::new());
+            }
+            // El cuadro queda invertido en Y; se copia con alto negativo
+            let size = THUMBNAIL_SIZE as f32;
+            let source = Rectangle::new(0.0, 0.0, size, -size);
+            let mut target = rl.begin_texture_mode(thread, &mut strip);
+            target.draw_texture_rec(frame.texture(), source, Vector2::new(index as f32 * size, 0.0), Color::WHITE);
+        }
+
+        let mut image = strip.load_image().map_err(|e| e.to_string())?;
+        image.flip_vertical();
+        let path = thumbnail_path(name);
+        // export_image no avisa si falla; se borra la miniatura vieja y solo cuentan las que quedaron escritas
+        let _ = std::fs::remove_file(&path);
+        image.export_image(&path);
+        if std::path::Path::new(&path).exists() {
+            saved.push(path);
+        } else {
+            eprintln!("Miniatura de '{}': no se pudo escribir {}", name, path);
+        }
+    }
+    Ok(saved)
+}
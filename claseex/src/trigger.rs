@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+use serde_json::{json, Value};
+
+use crate::scene::{vec3_from_json, vec3_to_json, Scene};
+
+// Nombre especial del campo `watch` para vigilar la cámara en lugar de un objeto
+pub const CAMERA_WATCH: &str = "camera";
+
+// Caja invisible que ejecuta comandos de consola al entrar o salir de ella
+#[derive(Clone, Debug)]
+pub struct Trigger {
+    pub name: String,
+    pub position: Vector3,
+    pub size: Vector3,
+    pub watch: String, // Nombre del objeto vigilado o "camera"
+    pub on_enter: Vec<String>,
+    pub on_exit: Vec<String>,
+    pub occupied: bool,
+}
+
+impl Trigger {
+    pub fn contains(&self, point: Vector3) -> bool {
+        let offset = point - self.position;
+        offset.x.abs() <= self.size.x * 0.5
+            && offset.y.abs() <= self.size.y * 0.5
+            && offset.z.abs() <= self.size.z * 0.5
+    }
+}
+
+impl Scene {
+    // Revisa cada trigger y devuelve los comandos de los que cambiaron de estado
+    pub fn update_triggers(&mut self, transforms: &HashMap<u32, Matrix>, camera_position: Vector3) -> Vec<String> {
+        let mut commands = Vec::new();
+        for index in 0..self.triggers.len() {
+            let watch = &self.triggers[index].watch;
+            let position = if watch == CAMERA_WATCH {
+                Some(camera_position)
+            } else {
+                self.objects
+                    .iter()
+                    .find(|object| object.name == *watch)
+                    .and_then(|object| transforms.get(&object.id))
+                    .map(|model| Vector3::zero().transform_with(*model))
+            };
+
+            let trigger = &mut self.triggers[index];
+            let inside = position.is_some_and(|position| trigger.contains(position));
+            if inside != trigger.occupied {
+                trigger.occupied = inside;
+                let fired = if inside { &trigger.on_enter } else { &trigger.on_exit };
+                commands.extend(fired.iter().cloned());
+            }
+        }
+        commands
+    }
+}
+
+pub fn trigger_from_json(value: &Value) -> Result<Trigger, String> {
+    let commands = |key: &str| -> Vec<String> {
+        value[key]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|command| command.as_str().map(str::to_string))
+            .collect()
+    };
+    Ok(Trigger {
+        name: value["name"].as_str().ok_or("trigger sin nombre")?.to_string(),
+        position: vec3_from_json(&value["position"]).ok_or("trigger sin posición")?,
+        size: vec3_from_json(&value["size"]).unwrap_or(Vector3::one()),
+        watch: value["watch"].as_str().unwrap_or(CAMERA_WATCH).to_string(),
+        on_enter: commands("on_enter"),
+        on_exit: commands("on_exit"),
+        occupied: false,
+    })
+}
+
+pub fn trigger_to_json(trigger: &Trigger) -> Value {
+    json!({
+        "name": trigger.name,
+        "position": vec3_to_json(trigger.position),
+        "size": vec3_to_json(trigger.size),
+        "watch": trigger.watch,
+        "on_enter": trigger.on_enter,
+        "on_exit": trigger.on_exit,
+    })
+}
@@ -0,0 +1,266 @@
+use raylib::prelude::*;
+
+use crate::asset_browser::{Asset, AssetBrowser};
+use crate::behavior::Behavior;
+use crate::console::Console;
+use crate::lesson::LessonPlayer;
+use crate::light_rig::LightRig;
+use crate::labels::{ScreenLabel, LABEL_FONT_SIZE};
+use crate::scene::{Layer, SceneObject, Shape};
+use crate::thumbnails::THUMBNAIL_SIZE;
+
+const ROW_HEIGHT: f32 = 24.0;
+const CONSOLE_LINES: usize = 8;
+
+// Área que ocupa el panel de capas, para no elegir objetos a través de él
+pub fn layer_panel_bounds(layer_count: usize, x: f32, y: f32) -> Rectangle {
+    Rectangle::new(x, y, 300.0, 54.0 + layer_count as f32 * ROW_HEIGHT)
+}
+
+// Panel de capas: visibilidad y flags de sombras por capa
+pub fn draw_layer_panel(d: &mut RaylibDrawHandle, layers: &mut [Layer], x: f32, y: f32) {
+    d.gui_panel(layer_panel_bounds(layers.len(), x, y), "Capas");
+    d.gui_label(Rectangle::new(x + 120.0, y + 26.0, 180.0, 20.0), "Ver  Proyecta  Recibe");
+
+    for (index, layer) in layers.iter_mut().enumerate() {
+        let row_y = y + 50.0 + index as f32 * ROW_HEIGHT;
+        d.gui_label(Rectangle::new(x + 10.0, row_y, 105.0, 18.0), &layer.name);
+        d.gui_check_box(Rectangle::new(x + 124.0, row_y, 16.0, 16.0), "", &mut layer.visible);
+        d.gui_check_box(Rectangle::new(x + 166.0, row_y, 16.0, 16.0), "", &mut layer.cast_shadows);
+        d.gui_check_box(Rectangle::new(x + 232.0, row_y, 16.0, 16.0), "", &mut layer.receive_shadows);
+    }
+}
+
+// Filas que ocupa cada comportamiento en el inspector: el título y un control por parámetro
+fn behavior_rows(behavior: &Behavior) -> usize {
+    match behavior {
+        Behavior::Spinner { .. } => 4,
+        Behavior::Bobber { .. } | Behavior::Orbiter { .. } => 3,
+        Behavior::Agent { .. } => 6,
+    }
+}
+
+// Área del inspector del objeto seleccionado
+pub fn inspector_bounds(object: &SceneObject, x: f32, y: f32) -> Rectangle {
+    let rows: usize = object.behaviors.iter().map(behavior_rows).sum();
+    Rectangle::new(x, y, 300.0, 64.0 + rows as f32 * ROW_HEIGHT)
+}
+
+// Inspector: parámetros de los comportamientos del objeto seleccionado, para agregar o quitar
+pub fn draw_inspector(d: &mut RaylibDrawHandle, object: &mut SceneObject, x: f32, y: f32) {
+    let bounds = inspector_bounds(object, x, y);
+    d.gui_panel(bounds, &format!("Inspector: {}", object.name));
+
+    let mut row_y = y + 30.0;
+    let mut removed = None;
+    for (index, behavior) in object.behaviors.iter_mut().enumerate() {
+        d.gui_label(Rectangle::new(x + 10.0, row_y, 180.0, 18.0), behavior.kind());
+        if d.gui_button(Rectangle::new(x + 230.0, row_y, 60.0, 18.0), "Quitar") {
+            removed = Some(index);
+        }
+        row_y += ROW_HEIGHT;
+
+        if let Behavior::Agent { seek, .. } = behavior {
+            d.gui_check_box(Rectangle::new(x + 90.0, row_y, 16.0, 16.0), "Ir al destino", seek);
+            row_y += ROW_HEIGHT;
+        }
+        let sliders: Vec<(&str, &mut f32, f32, f32)> = match behavior {
+            Behavior::Spinner { speed } => vec![
+                ("Giro X", &mut speed.x, -180.0, 180.0),
+                ("Giro Y", &mut speed.y, -180.0, 180.0),
+                ("Giro Z", &mut speed.z, -180.0, 180.0),
+            ],
+            Behavior::Bobber {
+                amplitude, frequency, ..
+            } => vec![("Altura", amplitude, 0.0, 2.0), ("Frecuencia", frequency, 0.0, 4.0)],
+            Behavior::Orbiter { radius, speed, .. } => {
+                vec![("Radio", radius, 0.1, 10.0), ("Velocidad", speed, -180.0, 180.0)]
+            }
+            Behavior::Agent {
+                speed,
+                steering,
+                sight,
+                range,
+                ..
+            } => vec![
+                ("Velocidad", speed, 0.1, 5.0),
+                ("Giro", steering, 0.5, 10.0),
+                ("Vision", sight, 0.5, 6.0),
+                ("Alcance", range, 1.0, 20.0),
+            ],
+        };
+        for (name, value, min, max) in sliders {
+            let text = format!("{:.2}", value);
+            d.gui_slider_bar(Rectangle::new(x + 90.0, row_y, 150.0, 16.0), name, &text, value, min, max);
+            row_y += ROW_HEIGHT;
+        }
+    }
+    if let Some(index) = removed {
+        object.behaviors.remove(index);
+    }
+
+    // Botones para agregar un comportamiento nuevo
+    let button_width = 280.0 / Behavior::KINDS.len() as f32;
+    for (index, kind) in Behavior::KINDS.iter().enumerate() {
+        let button = Rectangle::new(x + 10.0 + index as f32 * button_width, row_y + 4.0, button_width - 5.0, 22.0);
+        if d.gui_button(button, &format!("+ {}", kind))
+            && let Some(behavior) = Behavior::with_defaults(kind, object.position)
+        {
+            object.behaviors.push(behavior);
+        }
+    }
+}
+
+// Área del panel de iluminación, a la izquierda debajo de la ayuda
+pub fn rig_panel_bounds(x: f32, y: f32) -> Rectangle {
+    Rectangle::new(x, y, 180.0, 34.0 + LightRig::ALL.len() as f32 * 30.0)
+}
+
+// Un botón por juego de luces; devuelve el que se eligió
+pub fn draw_rig_panel(d: &mut RaylibDrawHandle, x: f32, y: f32, has_selection: bool) -> Option<LightRig> {
+    d.gui_panel(rig_panel_bounds(x, y), "Iluminacion");
+    let mut chosen = None;
+    for (index, rig) in LightRig::ALL.into_iter().enumerate() {
+        let bounds = Rectangle::new(x + 10.0, y + 30.0 + index as f32 * 30.0, 160.0, 24.0);
+        // Sin selección, los tres puntos rodean el punto que mira la cámara
+        let text = if rig == LightRig::ThreePoint && !has_selection {
+            format!("{} (vista)", rig.label())
+        } else {
+            rig.label().to_string()
+        };
+        if d.gui_button(bounds, &text) {
+            chosen = Some(rig);
+        }
+    }
+    chosen
+}
+
+// Panel de recursos: miniatura y nombre de cada entrada, con la elegida resaltada. Mientras se
+// arrastra, el nombre sigue al mouse
+pub fn draw_asset_browser(d: &mut RaylibDrawHandle, browser: &AssetBrowser, palette: &[Color], x: f32, y: f32) {
+    d.gui_panel(browser.bounds(palette.len(), x, y), "Recursos");
+    let mouse = d.get_mouse_position();
+    let time = d.get_time();
+    for (asset, bounds) in browser.layout(palette.len(), x, y) {
+        let hovered = bounds.check_collision_point_rec(mouse);
+        let center = Vector2::new(bounds.x + bounds.width * 0.5, bounds.y + bounds.height * 0.5 - 6.0);
+        match &asset {
+            Asset::Color(slot) => d.draw_rectangle_rec(bounds, palette[*slot]),
+            Asset::Mesh(shape) => {
+                d.draw_rectangle_rec(bounds, Color::new(40, 40, 48, 255));
+                // Dibujo simple de la forma en lugar de una miniatura
+                match shape {
+                    Shape::Cube => d.draw_rectangle_lines(center.x as i32 - 16, center.y as i32 - 16, 32, 32, Color::SKYBLUE),
+                    Shape::Sphere => d.draw_circle_lines(center.x as i32, center.y as i32, 18.0, Color::SKYBLUE),
+                    Shape::Plane => {
+                        let corners = [(-24.0, 8.0), (12.0, 8.0), (24.0, -8.0), (-12.0, -8.0)];
+                        for index in 0..corners.len() {
+                            let (ax, ay) = corners[index];
+                            let (bx, by) = corners[(index + 1) % corners.len()];
+                            let start = Vector2::new(center.x + ax, center.y + ay);
+                            d.draw_line_v(start, Vector2::new(center.x + bx, center.y + by), Color::SKYBLUE);
+                        }
+                    }
+                }
+            }
+            Asset::Prefab(name) => {
+                d.draw_rectangle_rec(bounds, Color::new(40, 40, 48, 255));
+                match browser.thumbnail(name) {
+                    Some(texture) => {
+                        // La miniatura es una tira con la vuelta completa; gira con el mouse encima
+                        let size = THUMBNAIL_SIZE as f32;
+                        let frame = AssetBrowser::turntable_frame(hovered, time) as f32;
+                        let source = Rectangle::new(frame * size, 0.0, size, size);
+                        let side = bounds.height - 16.0;
+                        let dest = Rectangle::new(center.x - side * 0.5, bounds.y, side, side);
+                        d.draw_texture_pro(texture, source, dest, Vector2::zero(), 0.0, Color::WHITE);
+                    }
+                    None => d.draw_text("?", center.x as i32 - 5, center.y as i32 - 10, 20, Color::GRAY),
+                }
+            }
+        }
+        if !matches!(asset, Asset::Color(_)) {
+            let label = asset.label();
+            let label_x = center.x as i32 - d.measure_text(&label, 10) / 2;
+            d.draw_text(&label, label_x, (bounds.y + bounds.height) as i32 - 13, 10, Color::RAYWHITE);
+        }
+        if browser.held.as_ref() == Some(&asset) {
+            d.draw_rectangle_lines_ex(bounds, 2.0, Color::YELLOW);
+        } else if hovered {
+            d.draw_rectangle_lines_ex(bounds, 1.0, Color::LIGHTGRAY);
+        }
+    }
+
+    if browser.is_dragging()
+        && let Some(asset) = &browser.held
+    {
+        let text = format!("+ {}", asset.label());
+        let width = d.measure_text(&text, 16) + 8;
+        d.draw_rectangle(mouse.x as i32 + 12, mouse.y as i32 + 12, width, 22, Color::new(20, 20, 30, 200));
+        d.draw_text(&text, mouse.x as i32 + 16, mouse.y as i32 + 15, 16, Color::YELLOW);
+    }
+}
+
+// Botón Play / Editor, centrado abajo
+pub fn play_button_bounds(screen_width: i32, screen_height: i32) -> Rectangle {
+    Rectangle::new(screen_width as f32 * 0.5 - 50.0, screen_height as f32 - 40.0, 100.0, 30.0)
+}
+
+// Devuelve true si se hizo clic para entrar o salir de la presentación
+pub fn draw_play_button(d: &mut RaylibDrawHandle, playing: bool) -> bool {
+    let bounds = play_button_bounds(d.get_screen_width(), d.get_screen_height());
+    d.gui_button(bounds, if playing { "Editor" } else { "Play" })
+}
+
+// Consigna del paso actual de la lección, abajo a la derecha
+pub fn draw_lesson(d: &mut RaylibDrawHandle, player: &LessonPlayer) {
+    let Some(lesson) = player.lesson() else {
+        return;
+    };
+    let bounds = Rectangle::new(d.get_screen_width() as f32 - 430.0, d.get_screen_height() as f32 - 100.0, 420.0, 90.0);
+    d.draw_rectangle_rec(bounds, Color::new(20, 30, 40, 220));
+    d.draw_rectangle_lines_ex(bounds, 1.0, Color::SKYBLUE);
+    let (x, y) = (bounds.x as i32 + 10, bounds.y as i32 + 8);
+    d.draw_text(&format!("Leccion: {}", lesson.title), x, y, 18, Color::SKYBLUE);
+    match player.current() {
+        Some((index, checkpoint)) => {
+            let step = format!("Paso {}/{}: {}", index + 1, lesson.checkpoints.len(), checkpoint.text);
+            d.draw_text(&step, x, y + 26, 16, Color::RAYWHITE);
+            if let Some(hint) = &checkpoint.hint {
+                d.draw_text(hint, x, y + 48, 14, Color::LIGHTGRAY);
+            }
+        }
+        None => {
+            d.draw_text("Leccion completa", x, y + 26, 16, Color::LIME);
+            d.draw_text("leccion salir (consola) para cerrar", x, y + 48, 14, Color::LIGHTGRAY);
+        }
+    }
+}
+
+// Consola desplegada en la parte superior: últimas líneas del registro y la entrada
+pub fn draw_console(d: &mut RaylibDrawHandle, console: &Console) {
+    let width = d.get_screen_width();
+    let height = (CONSOLE_LINES as i32 + 1) * 20 + 10;
+    d.draw_rectangle(0, 0, width, height, Color::new(10, 10, 20, 220));
+
+    let start = console.log.len().saturating_sub(CONSOLE_LINES);
+    for (row, line) in console.log[start..].iter().enumerate() {
+        d.draw_text(line, 10, 6 + row as i32 * 20, 18, Color::LIGHTGRAY);
+    }
+    d.draw_text(&format!("> {}_", console.input), 10, height - 24, 18, Color::RAYWHITE);
+}
+
+// Etiquetas en pantalla con fondo; las lejanas primero para que las cercanas queden encima
+pub fn draw_labels(d: &mut RaylibDrawHandle, labels: &[ScreenLabel]) {
+    for label in labels.iter().rev() {
+        let bounds = label.bounds;
+        d.draw_rectangle_rec(bounds, Color::new(20, 20, 30, 200).alpha(label.alpha));
+        d.draw_text(
+            &label.text,
+            bounds.x as i32 + 4,
+            bounds.y as i32 + 4,
+            LABEL_FONT_SIZE,
+            Color::GOLD.alpha(label.alpha),
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use raylib::prelude::*;
+
+use crate::lighting::{Fog, Light};
+use crate::rng::Rng;
+
+// Zona sobre el diorama donde caen las partículas
+const AREA_HALF_SIZE: f32 = 6.0;
+const AREA_TOP: f32 = 6.0;
+const AREA_FLOOR: f32 = -2.0;
+// Qué tan rápido se pasa de un clima al siguiente (fracción por segundo)
+const TRANSITION_SPEED: f32 = 0.8;
+
+// Climas predefinidos que se pueden elegir en tiempo de ejecución
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeatherPreset {
+    Clear,
+    Rain,
+    Snow,
+    Storm,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
+// Valores que coordina cada clima
+#[derive(Clone, Copy, Debug)]
+struct WeatherSettings {
+    fog_density: f32,
+    fog_color: Color,
+    precipitation: Precipitation,
+    particle_count: usize,
+    light_tint: Color, // Se multiplica por el color de cada luz
+    light_scale: f32,
+    ambient_scale: f32,
+    lightning: bool,
+    wetness: f32, // Reflejo que toma el suelo mojado
+}
+
+impl WeatherPreset {
+    pub const ALL: [WeatherPreset; 4] = [
+        WeatherPreset::Clear,
+        WeatherPreset::Rain,
+        WeatherPreset::Snow,
+        WeatherPreset::Storm,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeatherPreset::Clear => "clear",
+            WeatherPreset::Rain => "rain",
+            WeatherPreset::Snow => "snow",
+            WeatherPreset::Storm => "storm",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WeatherPreset> {
+        WeatherPreset::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    pub fn next(&self) -> WeatherPreset {
+        let index = WeatherPreset::ALL.iter().position(|preset| preset == self).unwrap_or(0);
+        WeatherPreset::ALL[(index + 1) % WeatherPreset::ALL.len()]
+    }
+
+    fn settings(&self) -> WeatherSettings {
+        match self {
+            WeatherPreset::Clear => WeatherSettings {
+                fog_density: 0.0,
+                fog_color: Color::new(50, 50, 50, 255),
+                precipitation: Precipitation::None,
+                particle_count: 0,
+                light_tint: Color::WHITE,
+                light_scale: 1.0,
+                ambient_scale: 1.0,
+                lightning: false,
+                wetness: 0.0,
+            },
+            WeatherPreset::Rain => WeatherSettings {
+                fog_density: 0.05,
+                fog_color: Color::new(70, 75, 85, 255),
+                precipitation: Precipitation::Rain,
+                particle_count: 1200,
+                light_tint: Color::new(200, 210, 230, 255),
+                light_scale: 0.75,
+                ambient_scale: 0.8,
+                lightning: false,
+                wetness: 0.45,
+            },
+            WeatherPreset::Snow => WeatherSettings {
+                fog_density: 0.07,
+                fog_color: Color::new(190, 195, 205, 255),
+                precipitation: Precipitation::Snow,
+                particle_count: 900,
+                light_tint: Color::new(220, 230, 255, 255),
+                light_scale: 0.85,
+                ambient_scale: 1.2,
+                lightning: false,
+                wetness: 0.0,
+            },
+            WeatherPreset::Storm => WeatherSettings {
+                fog_density: 0.09,
+                fog_color: Color::new(35, 38, 48, 255),
+                precipitation: Precipitation::Rain,
+                particle_count: 2000,
+                light_tint: Color::new(170, 180, 210, 255),
+                light_scale: 0.5,
+                ambient_scale: 0.6,
+                lightning: true,
+                wetness: 0.6,
+            },
+        }
+    }
+}
+
+struct Particle {
+    position: Vector3,
+    velocity: Vector3,
+}
+
+// Controlador del clima: mezcla los valores hacia el clima elegido y simula partículas y relámpagos
+pub struct Weather {
+    preset: WeatherPreset,
+    blend: f32, // 0 al empezar una transición, 1 cuando el clima nuevo está completo
+    from: WeatherSettings,
+    particles: Vec<Particle>,
+    flash: f32,
+    next_flash: f32,
+    rng: Rng,
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let channel = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t) as u8;
+    Color::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b), 255)
+}
+
+fn tint(color: Color, tint: Color) -> Color {
+    let channel = |x: u8, y: u8| (x as u16 * y as u16 / 255) as u8;
+    Color::new(channel(color.r, tint.r), channel(color.g, tint.g), channel(color.b, tint.b), color.a)
+}
+
+impl Weather {
+    pub fn new(preset: WeatherPreset) -> Self {
+        Weather {
+            preset,
+            blend: 1.0,
+            from: preset.settings(),
+            particles: Vec::new(),
+            flash: 0.0,
+            next_flash: 4.0,
+            rng: Rng::new(0x5EED_1234),
+        }
+    }
+
+    // Cambia de clima partiendo de los valores que se ven en este momento
+    pub fn set_preset(&mut self, preset: WeatherPreset) {
+        if preset != self.preset {
+            self.from = self.current();
+            self.preset = preset;
+            self.blend = 0.0;
+        }
+    }
+
+    // Valores actuales mezclando el clima anterior con el elegido
+    fn current(&self) -> WeatherSettings {
+        let target = self.preset.settings();
+        let t = self.blend;
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        WeatherSettings {
+            fog_density: mix(self.from.fog_density, target.fog_density),
+            fog_color: lerp_color(self.from.fog_color, target.fog_color, t),
+            precipitation: target.precipitation,
+            particle_count: mix(self.from.particle_count as f32, target.particle_count as f32) as usize,
+            light_tint: lerp_color(self.from.light_tint, target.light_tint, t),
+            light_scale: mix(self.from.light_scale, target.light_scale),
+            ambient_scale: mix(self.from.ambient_scale, target.ambient_scale),
+            lightning: target.lightning,
+            wetness: mix(self.from.wetness, target.wetness),
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.blend = (self.blend + TRANSITION_SPEED * dt).min(1.0);
+        let settings = self.current();
+
+        // Ajustar la cantidad de partículas; las nuevas nacen en cualquier altura para no caer en bloque
+        self.particles.truncate(settings.particle_count);
+        while self.particles.len() < settings.particle_count {
+            let height = self.rng.range(AREA_FLOOR, AREA_TOP);
+            let particle = self.spawn(settings.precipitation, height);
+            self.particles.push(particle);
+        }
+
+        for index in 0..self.particles.len() {
+            let particle = &mut self.particles[index];
+            particle.position += particle.velocity * dt;
+            if settings.precipitation == Precipitation::Snow {
+                // Vaivén lateral de los copos
+                particle.position.x += (particle.position.y * 2.0 + index as f32).sin() * 0.3 * dt;
+            }
+            if particle.position.y < AREA_FLOOR {
+                self.particles[index] = self.spawn(settings.precipitation, AREA_TOP);
+            }
+        }
+
+        // Relámpagos: un destello que se apaga rápido cada pocos segundos al azar
+        self.flash = (self.flash - dt * 3.0).max(0.0);
+        if settings.lightning {
+            self.next_flash -= dt;
+            if self.next_flash <= 0.0 {
+                self.flash = 1.0;
+                self.next_flash = self.rng.range(3.0, 9.0);
+            }
+        }
+    }
+
+    fn spawn(&mut self, precipitation: Precipitation, height: f32) -> Particle {
+        let position = Vector3::new(
+            self.rng.range(-AREA_HALF_SIZE, AREA_HALF_SIZE),
+            height,
+            self.rng.range(-AREA_HALF_SIZE, AREA_HALF_SIZE),
+        );
+        let velocity = match precipitation {
+            Precipitation::Snow => Vector3::new(0.0, -self.rng.range(0.6, 1.2), 0.0),
+            _ => Vector3::new(0.4, -self.rng.range(9.0, 12.0), 0.2),
+        };
+        Particle { position, velocity }
+    }
+
+    // Luces de la escena afectadas por el clima; las originales no se modifican
+    pub fn apply_to_lights(&self, lights: &[Light]) -> Vec<Light> {
+        let settings = self.current();
+        lights
+            .iter()
+            .map(|light| Light {
+                color: tint(light.color, settings.light_tint),
+                intensity: light.intensity * settings.light_scale,
+                ..*light
+            })
+            .collect()
+    }
+
+    // Luz ambiente con el destello del relámpago sumado
+    pub fn ambient(&self, base: f32) -> f32 {
+        base * self.current().ambient_scale + self.flash * 0.8
+    }
+
+    // Qué tan mojado está el suelo: sube y baja con la transición del clima
+    pub fn wetness(&self) -> f32 {
+        self.current().wetness
+    }
+
+    pub fn fog(&self, eye: Vector3) -> Option<Fog> {
+        let settings = self.current();
+        (settings.fog_density > 0.001).then_some(Fog {
+            color: settings.fog_color,
+            density: settings.fog_density,
+            eye,
+        })
+    }
+
+    pub fn background(&self, default: Color) -> Color {
+        let settings = self.current();
+        let base = if settings.fog_density > 0.001 {
+            lerp_color(default, settings.fog_color, (settings.fog_density * 20.0).min(1.0))
+        } else {
+            default
+        };
+        lerp_color(base, Color::new(220, 225, 255, 255), self.flash * 0.7)
+    }
+
+    pub fn draw(&self, d3d: &mut impl RaylibDraw3D) {
+        let snow = self.current().precipitation == Precipitation::Snow;
+        for particle in &self.particles {
+            if snow {
+                d3d.draw_cube(particle.position, 0.05, 0.05, 0.05, Color::RAYWHITE);
+            } else {
+                let tail = particle.position - particle.velocity * 0.03;
+                d3d.draw_line_3D(particle.position, tail, Color::new(170, 190, 230, 180));
+            }
+        }
+    }
+}